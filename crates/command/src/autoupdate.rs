@@ -0,0 +1,434 @@
+use crate::init_env::{
+    get_all_buckets_dir_child_bucket_path, get_all_global_buckets_dir_child_bucket_path,
+    get_cache_dir_path, get_cache_dir_path_global,
+};
+use crate::install::{install_app_from_local_manifest_file, InstallOptions};
+use crate::manifest::install_manifest::InstallManifest;
+use crate::manifest::manifest::{get_all_manifest_files_from_bucket, MainBucket};
+use crate::manifest::manifest_deserialize::{AutoUpdateHashOrArray, AutoUpdateStruct, ManifestObj};
+use anyhow::{bail, Context};
+use crossterm::style::Stylize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub mod hash_extract;
+pub mod substitute;
+pub use hash_extract::*;
+pub use substitute::*;
+
+const ARCHITECTURES: [&str; 3] = ["64bit", "32bit", "arm64"];
+
+/// 根据 manifest 的 autoupdate 块生成指定版本的清单,
+/// 只有 autoupdate 中出现的字段才会被覆盖, url 被替换后旧的 hash 随之失效并被移除
+pub fn generate_manifest_for_version(
+    manifest: &ManifestObj,
+    version: &str,
+    custom_matches: &HashMap<String, String>,
+) -> anyhow::Result<ManifestObj> {
+    let Some(manifest_obj) = manifest.as_object() else {
+        bail!("manifest is not a json object")
+    };
+    let Some(autoupdate) = manifest_obj.get("autoupdate") else {
+        bail!("manifest does not have autoupdate field")
+    };
+    serde_json::from_value::<AutoUpdateStruct>(autoupdate.clone())
+        .context("autoupdate field format is invalid")?;
+
+    let substitutions = get_version_substitutions(version, custom_matches);
+    let mut template = autoupdate.clone();
+    substitute_value(&mut template, &substitutions, false);
+
+    let mut generated = manifest_obj.clone();
+    let template = template.as_object().unwrap();
+    for (key, value) in template {
+        match key.as_str() {
+            "architecture" | "hash" => continue,
+            "url" => {
+                generated.remove("hash");
+                generated.insert(key.clone(), value.clone());
+            }
+            _ => {
+                generated.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    if let Some(Value::Object(template_arch)) = template.get("architecture") {
+        let arch_entry = generated
+            .entry("architecture")
+            .or_insert_with(|| Value::Object(Default::default()));
+        if !arch_entry.is_object() {
+            bail!("manifest architecture field is not a json object")
+        }
+        let manifest_arch = arch_entry.as_object_mut().unwrap();
+        for arch in ARCHITECTURES {
+            let Some(Value::Object(arch_template)) = template_arch.get(arch) else {
+                continue;
+            };
+            let arch_value = manifest_arch
+                .entry(arch)
+                .or_insert_with(|| Value::Object(Default::default()));
+            let Some(arch_value) = arch_value.as_object_mut() else {
+                bail!("manifest architecture.{arch} is not a json object")
+            };
+            for (key, value) in arch_template {
+                match key.as_str() {
+                    "hash" => continue,
+                    "url" => {
+                        arch_value.remove("hash");
+                        arch_value.insert(key.clone(), value.clone());
+                    }
+                    _ => {
+                        arch_value.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+    }
+    generated.insert("version".into(), Value::String(version.to_string()));
+
+    let generated = Value::Object(generated);
+    if !has_download_url(&generated) {
+        bail!("generated manifest does not have any download url, check autoupdate field")
+    }
+    Ok(generated)
+}
+
+fn has_download_url(manifest: &ManifestObj) -> bool {
+    if manifest.get("url").is_some_and(|url| !url.is_null()) {
+        return true;
+    }
+    ARCHITECTURES.iter().any(|arch| {
+        manifest
+            .get("architecture")
+            .and_then(|a| a.get(arch))
+            .and_then(|a| a.get("url"))
+            .is_some_and(|url| !url.is_null())
+    })
+}
+
+pub fn generate_install_manifest(
+    manifest: &ManifestObj,
+    version: &str,
+) -> anyhow::Result<InstallManifest> {
+    let generated = generate_manifest_for_version(manifest, version, &HashMap::new())?;
+    let install_manifest = serde_json::from_value::<InstallManifest>(generated)
+        .context("generated manifest can not be deserialized to InstallManifest")?;
+    Ok(install_manifest)
+}
+
+pub fn get_autoupdate_manifest_path(
+    app_name: &str,
+    version: &str,
+    options: &[InstallOptions],
+) -> String {
+    let cache_dir = if options.contains(&InstallOptions::Global) {
+        get_cache_dir_path_global()
+    } else {
+        get_cache_dir_path()
+    };
+    format!(
+        "{}\\autoupdate\\{}@{}\\{}.json",
+        cache_dir, app_name, version, app_name
+    )
+}
+
+/// 安装流程通过清单文件名获取 app 名称, 因此生成的清单写入以 app 命名的文件
+pub fn write_autoupdate_manifest(
+    app_name: &str,
+    version: &str,
+    manifest: &ManifestObj,
+    options: &[InstallOptions],
+) -> anyhow::Result<String> {
+    let manifest_path = get_autoupdate_manifest_path(app_name, version, options);
+    let parent = Path::new(&manifest_path).parent().unwrap();
+    if !parent.exists() {
+        std::fs::create_dir_all(parent).context(format!(
+            "Failed to create autoupdate manifest dir {}",
+            parent.display()
+        ))?;
+    }
+    let content =
+        serde_json::to_string_pretty(manifest).context("Failed to serialize generated manifest")?;
+    std::fs::write(&manifest_path, content).context(format!(
+        "Failed to write generated manifest {}",
+        manifest_path
+    ))?;
+    Ok(manifest_path)
+}
+
+/// 在本地 bucket 中查找带有 autoupdate 的清单, 优先 main, extras, versions
+pub fn get_autoupdate_template_manifest(
+    app_name: &str,
+    options: &[InstallOptions],
+) -> anyhow::Result<(PathBuf, ManifestObj)> {
+    let buckets_root = if options.contains(&InstallOptions::Global) {
+        get_all_global_buckets_dir_child_bucket_path()?
+    } else {
+        get_all_buckets_dir_child_bucket_path()?
+    };
+    let mut candidates = get_all_manifest_files_from_bucket(buckets_root.as_slice(), app_name);
+    if candidates.is_empty() {
+        bail!("No app manifest found for '{app_name}'");
+    }
+    candidates.sort_by_key(|(_, bucket)| match bucket {
+        MainBucket::Main => 0,
+        MainBucket::Extras => 1,
+        MainBucket::Versions => 2,
+        _ => 3,
+    });
+    for (path, _) in candidates {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(manifest) = serde_json::from_str::<ManifestObj>(&content) else {
+            continue;
+        };
+        if manifest.get("autoupdate").is_some_and(|a| a.is_object()) {
+            return Ok((path, manifest));
+        }
+    }
+    bail!("No manifest of '{app_name}' has autoupdate field, can not generate other version")
+}
+
+/// 为生成清单中缺少 hash 的 url 补全哈希, 架构下未配置 hash 时沿用 autoupdate.hash
+pub async fn fill_autoupdate_hashes(
+    template: &ManifestObj,
+    generated: &mut ManifestObj,
+    version: &str,
+    custom_matches: &HashMap<String, String>,
+    allow_download: bool,
+) -> anyhow::Result<()> {
+    let autoupdate = &template["autoupdate"];
+    let substitutions = get_version_substitutions(version, custom_matches);
+
+    if !generated["url"].is_null() && generated.get("hash").is_none() {
+        let hash = get_hashes_for_urls(
+            &generated["url"],
+            &autoupdate["hash"],
+            &substitutions,
+            allow_download,
+        )
+        .await?;
+        generated["hash"] = hash;
+    }
+    for arch in ARCHITECTURES {
+        let arch_value = &generated["architecture"][arch];
+        if arch_value["url"].is_null() || arch_value.get("hash").is_some() {
+            continue;
+        }
+        let hash_config = if autoupdate["architecture"][arch]["hash"].is_null() {
+            &autoupdate["hash"]
+        } else {
+            &autoupdate["architecture"][arch]["hash"]
+        };
+        let hash = get_hashes_for_urls(
+            &arch_value["url"],
+            hash_config,
+            &substitutions,
+            allow_download,
+        )
+        .await?;
+        generated["architecture"][arch]["hash"] = hash;
+    }
+    Ok(())
+}
+
+/// url 为数组时 hash 配置可以是与之一一对应的数组
+async fn get_hashes_for_urls(
+    urls: &Value,
+    hash_config: &Value,
+    substitutions: &[(String, String)],
+    allow_download: bool,
+) -> anyhow::Result<Value> {
+    let hash_config = serde_json::from_value::<AutoUpdateHashOrArray>(hash_config.clone())
+        .context("autoupdate hash field format is invalid")?;
+    let config_at = |index: usize| match &hash_config {
+        AutoUpdateHashOrArray::Object(config) => Some(config),
+        AutoUpdateHashOrArray::ObjectArray(configs) => configs.get(index),
+        AutoUpdateHashOrArray::Null => None,
+    };
+    match urls {
+        Value::String(url) => {
+            let hash = get_hash_for_url(config_at(0), url, substitutions, allow_download).await?;
+            Ok(Value::String(hash))
+        }
+        Value::Array(urls) => {
+            let mut hashes = vec![];
+            for (index, url) in urls.iter().enumerate() {
+                let Some(url) = url.as_str() else {
+                    bail!("url must be string")
+                };
+                let hash =
+                    get_hash_for_url(config_at(index), url, substitutions, allow_download).await?;
+                hashes.push(Value::String(hash));
+            }
+            Ok(Value::Array(hashes))
+        }
+        _ => bail!("url must be string or string array"),
+    }
+}
+
+pub async fn generate_manifest_with_hash(
+    manifest: &ManifestObj,
+    version: &str,
+    custom_matches: &HashMap<String, String>,
+    allow_download: bool,
+) -> anyhow::Result<ManifestObj> {
+    let mut generated = generate_manifest_for_version(manifest, version, custom_matches)?;
+    fill_autoupdate_hashes(
+        manifest,
+        &mut generated,
+        version,
+        custom_matches,
+        allow_download,
+    )
+    .await?;
+    Ok(generated)
+}
+
+/// bucket 中没有对应版本清单时, 使用最新清单的 autoupdate 生成该版本清单并安装,
+/// 上游没有哈希时下载安装包计算, 仍无法获取哈希时只有指定跳过校验才继续安装
+pub async fn install_app_version_from_autoupdate(
+    app_name: &str,
+    app_version: &str,
+    options: &[InstallOptions<'_>],
+) -> anyhow::Result<()> {
+    let (template_path, template) = get_autoupdate_template_manifest(app_name, options)?;
+    let source_bucket = template_path
+        .parent()
+        .and_then(|p| p.parent())
+        .and_then(|p| p.file_name())
+        .and_then(|p| p.to_str())
+        .unwrap_or_default()
+        .to_string();
+    log::info!("autoupdate template manifest: {}", template_path.display());
+
+    let mut generated = generate_manifest_for_version(&template, app_version, &HashMap::new())?;
+    let hash_found = match fill_autoupdate_hashes(
+        &template,
+        &mut generated,
+        app_version,
+        &HashMap::new(),
+        true,
+    )
+    .await
+    {
+        Ok(_) => true,
+        Err(e) if options.contains(&InstallOptions::SkipDownloadHashCheck) => {
+            log::warn!("autoupdate hash extraction failed: {e:#}");
+            false
+        }
+        Err(e) => {
+            return Err(e.context(format!(
+                "Could not get hash for '{app_name}' version '{app_version}', use --skip-download-hash-check to install without verification"
+            )))
+        }
+    };
+    serde_json::from_value::<InstallManifest>(generated.clone())
+        .context("generated manifest can not be deserialized to InstallManifest")?;
+    let manifest_path = write_autoupdate_manifest(app_name, app_version, &generated, options)?;
+    log::info!("generated manifest path: {}", manifest_path);
+
+    if hash_found {
+        println!(
+            "{}",
+            format!("Generated manifest for '{app_name}' version '{app_version}' from autoupdate")
+                .dark_green()
+                .bold()
+        );
+    } else {
+        println!(
+            "{}",
+            format!(
+                "Generated manifest for '{app_name}' version '{app_version}' from autoupdate, hash not found and will not be verified"
+            )
+            .dark_yellow()
+            .bold()
+        );
+    }
+    let options = options
+        .iter()
+        .cloned()
+        .chain([InstallOptions::InstallSpecialVersionApp])
+        .collect::<Vec<_>>();
+    install_app_from_local_manifest_file(&manifest_path, options, Some(&source_bucket))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_autoupdate {
+    #[allow(unused_imports)]
+    use super::*;
+
+    fn gh_manifest() -> ManifestObj {
+        serde_json::json!({
+            "version": "2.40.0",
+            "description": "GitHub CLI",
+            "architecture": {
+                "64bit": {
+                    "url": "https://github.com/cli/cli/releases/download/v2.40.0/gh_2.40.0_windows_amd64.zip",
+                    "hash": "aaaa"
+                },
+                "32bit": {
+                    "url": "https://github.com/cli/cli/releases/download/v2.40.0/gh_2.40.0_windows_386.zip",
+                    "hash": "bbbb"
+                }
+            },
+            "bin": "bin\\gh.exe",
+            "checkver": { "github": "https://github.com/cli/cli" },
+            "autoupdate": {
+                "architecture": {
+                    "64bit": {
+                        "url": "https://github.com/cli/cli/releases/download/v$version/gh_$version_windows_amd64.zip"
+                    },
+                    "32bit": {
+                        "url": "https://github.com/cli/cli/releases/download/v$version/gh_$version_windows_386.zip"
+                    }
+                },
+                "hash": { "url": "$baseurl/gh_$version_checksums.txt" }
+            }
+        })
+    }
+
+    #[test]
+    fn test_generate_architecture_manifest() {
+        let generated =
+            generate_manifest_for_version(&gh_manifest(), "2.7.0", &HashMap::new()).unwrap();
+        assert_eq!(generated["version"], "2.7.0");
+        assert_eq!(
+            generated["architecture"]["64bit"]["url"],
+            "https://github.com/cli/cli/releases/download/v2.7.0/gh_2.7.0_windows_amd64.zip"
+        );
+        assert!(generated["architecture"]["64bit"].get("hash").is_none());
+        assert!(generated["architecture"]["32bit"].get("hash").is_none());
+        assert_eq!(generated["bin"], "bin\\gh.exe");
+        let install_manifest = generate_install_manifest(&gh_manifest(), "2.7.0").unwrap();
+        assert_eq!(install_manifest.version.as_deref(), Some("2.7.0"));
+    }
+
+    #[test]
+    fn test_generate_root_manifest() {
+        let manifest = serde_json::json!({
+            "version": "1.0.0",
+            "url": "https://example.com/tool-1.0.0.zip",
+            "hash": "cccc",
+            "extract_dir": "tool-1.0.0",
+            "autoupdate": {
+                "url": "https://example.com/tool-$version.zip",
+                "extract_dir": "tool-$majorVersion.$minorVersion"
+            }
+        });
+        let generated = generate_manifest_for_version(&manifest, "3.1.4", &HashMap::new()).unwrap();
+        assert_eq!(generated["url"], "https://example.com/tool-3.1.4.zip");
+        assert_eq!(generated["extract_dir"], "tool-3.1");
+        assert!(generated.get("hash").is_none());
+    }
+
+    #[test]
+    fn test_generate_without_autoupdate() {
+        let manifest = serde_json::json!({ "version": "1.0.0", "url": "https://a/b.zip" });
+        assert!(generate_manifest_for_version(&manifest, "2.0.0", &HashMap::new()).is_err());
+    }
+}
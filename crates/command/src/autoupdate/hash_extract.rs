@@ -0,0 +1,408 @@
+use crate::autoupdate::substitute_str;
+use crate::checkver::{build_request_client, fetch_text, json_path, regex_match, xpath};
+use crate::manifest::manifest_deserialize::{AutoUpdateHashStruct, HashModeStruct};
+use anyhow::{bail, Context};
+use base64::Engine;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+
+/// 与 Scoop 的 find_hash_in_textfile 一致, 可在 regex 中使用的哈希模板
+const HASH_TEMPLATES: [(&str, &str); 6] = [
+    ("$checksum", "([a-fA-F0-9]{32,128})"),
+    ("$sha256", "([a-fA-F0-9]{64})"),
+    ("$sha512", "([a-fA-F0-9]{128})"),
+    ("$base64", "([a-zA-Z0-9+/=]{24,88})"),
+    ("$sha1", "([a-fA-F0-9]{40})"),
+    ("$md5", "([a-fA-F0-9]{32})"),
+];
+
+/// 按长度识别哈希类型, sha256 不带前缀, 无法识别时返回 None
+pub fn format_hash(hash: &str) -> Option<String> {
+    let hash = hash.trim().to_lowercase();
+    if !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hash.len() {
+        32 => Some(format!("md5:{hash}")),
+        40 => Some(format!("sha1:{hash}")),
+        64 => Some(hash),
+        128 => Some(format!("sha512:{hash}")),
+        _ => None,
+    }
+}
+
+pub fn strip_fragment(url: &str) -> &str {
+    url.split('#').next().unwrap_or(url)
+}
+
+/// 下载链接对应的远程文件名, 路径末段不像文件名时使用 `#/` 之后的部分
+pub fn url_remote_filename(url: &str) -> String {
+    let (without_fragment, fragment) = url.split_once('#').unwrap_or((url, ""));
+    let path = without_fragment
+        .split('?')
+        .next()
+        .unwrap_or(without_fragment);
+    let basename = path
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default();
+    let basename = percent_encoding::percent_decode_str(basename)
+        .decode_utf8_lossy()
+        .to_string();
+    if !basename.contains('.') && !fragment.is_empty() {
+        return fragment.trim_matches(|c| c == '/' || c == '#').to_string();
+    }
+    basename
+}
+
+fn strip_ext(name: &str) -> String {
+    Regex::new(r"\.[^.]*$")
+        .unwrap()
+        .replace(name, "")
+        .to_string()
+}
+
+/// 在版本变量之外追加 `$url` `$baseurl` `$basename` `$urlNoExt` `$basenameNoExt`
+pub fn get_hash_substitutions(
+    url: &str,
+    version_substitutions: &[(String, String)],
+) -> Vec<(String, String)> {
+    let url_no_fragment = strip_fragment(url);
+    let basename = url_remote_filename(url);
+    let base_url = url_no_fragment
+        .rsplit_once('/')
+        .map(|(base, _)| base)
+        .unwrap_or(url_no_fragment)
+        .trim_end_matches('/');
+    let mut substitutions = version_substitutions.to_vec();
+    substitutions.extend([
+        ("$url".to_string(), url_no_fragment.to_string()),
+        ("$baseurl".to_string(), base_url.to_string()),
+        ("$basename".to_string(), basename.clone()),
+        ("$urlNoExt".to_string(), strip_ext(url_no_fragment)),
+        ("$basenameNoExt".to_string(), strip_ext(&basename)),
+    ]);
+    substitutions.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+    substitutions
+}
+
+fn decode_base64_hash(hash: &str) -> Option<String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(hash)
+        .ok()?;
+    Some(hex::encode(bytes))
+}
+
+/// 正则缺省时整个文件只能是一个哈希, 仍未找到时按文件名在校验文件中查找
+pub fn find_hash_in_text(
+    content: &str,
+    regex: Option<&str>,
+    substitutions: &[(String, String)],
+) -> Option<String> {
+    let regex = regex.unwrap_or(r"^\s*([a-fA-F0-9]+)\s*$");
+    let templates = HASH_TEMPLATES
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<Vec<_>>();
+    let regex = substitute_str(regex, &templates, false);
+    let regex = substitute_str(&regex, substitutions, true);
+
+    let mut hash = regex_match(content, &format!("(?i){regex}"), false)
+        .ok()
+        .and_then(|m| m.groups.get("1").cloned())
+        .map(|h| h.split_whitespace().collect::<String>())
+        .unwrap_or_default();
+
+    let is_hex = hash.chars().all(|c| c.is_ascii_hexdigit());
+    if !hash.is_empty() && !(is_hex && [32, 40, 64, 128].contains(&hash.len())) {
+        if let Some(decoded) = decode_base64_hash(&hash) {
+            hash = decoded;
+        }
+    }
+
+    if hash.is_empty() {
+        let filename_regex = substitute_str(
+            r"([a-fA-F0-9]{32,128})[\x20\t]+.*$basename(?:\s|$)|$basename[\x20\t]+.*?([a-fA-F0-9]{32,128})",
+            substitutions,
+            true,
+        );
+        if let Ok(m) = regex_match(content, &format!("(?i){filename_regex}"), false) {
+            hash = m
+                .groups
+                .get("1")
+                .or(m.groups.get("2"))
+                .cloned()
+                .unwrap_or_default();
+        }
+        if let Ok(m) = regex_match(content, r"<hash[^>]+>([a-fA-F0-9]{64})", false) {
+            hash = m.groups["1"].clone();
+        }
+    }
+    format_hash(&hash)
+}
+
+pub fn find_hash_in_json(
+    content: &str,
+    jsonpath: &str,
+    substitutions: &[(String, String)],
+) -> Option<String> {
+    let jsonpath = substitute_str(jsonpath, substitutions, false);
+    let hash = json_path(content, &jsonpath).ok()?;
+    format_hash(&hash)
+}
+
+pub fn find_hash_in_xml(
+    content: &str,
+    xpath_str: &str,
+    substitutions: &[(String, String)],
+) -> Option<String> {
+    let xpath_str = substitute_str(xpath_str, substitutions, false);
+    let hash = xpath(content, &xpath_str).ok()?;
+    format_hash(&hash)
+}
+
+/// RDF 中 `about` 属性等于文件名的 Content 节点下的 sha256
+pub fn find_hash_in_rdf(content: &str, basename: &str) -> Option<String> {
+    let xpath_str = format!(
+        "//*[local-name()='Content'][@*[local-name()='about']='{basename}']/*[local-name()='sha256']"
+    );
+    let hash = xpath(content, &xpath_str).ok()?;
+    format_hash(&hash)
+}
+
+/// 解析 `Digest: SHA-256=<base64>` 响应头
+pub fn find_hash_in_digest_header(digest: &str) -> Option<String> {
+    ["SHA-256=", "SHA=", "MD5="].iter().find_map(|prefix| {
+        let value = digest
+            .split(',')
+            .map(|part| part.trim())
+            .find_map(|part| part.strip_prefix(prefix))?;
+        decode_base64_hash(value).and_then(|hash| format_hash(&hash))
+    })
+}
+
+async fn find_hash_in_headers(url: &str) -> Option<String> {
+    let client = build_request_client(None).ok()?;
+    let response = client.head(url).send().await.ok()?;
+    let digest = response.headers().get("Digest")?.to_str().ok()?;
+    find_hash_in_digest_header(digest)
+}
+
+/// 下载文件计算 sha256, 作为无法从校验文件提取哈希时的兜底
+pub async fn compute_hash_by_download(url: &str) -> anyhow::Result<String> {
+    let url = strip_fragment(url);
+    let client = build_request_client(None)?;
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .context(format!("Failed to download {}", url))?;
+    if !response.status().is_success() {
+        bail!("Download {} failed: {}", url, response.status())
+    }
+    let mut hasher = Sha256::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .context(format!("Failed to download {}", url))?
+    {
+        hasher.update(&chunk);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// 根据 autoupdate.hash 配置获取下载链接的哈希, allow_download 为 false 时不下载文件兜底
+pub async fn get_hash_for_url(
+    config: Option<&AutoUpdateHashStruct>,
+    url: &str,
+    version_substitutions: &[(String, String)],
+    allow_download: bool,
+) -> anyhow::Result<String> {
+    let substitutions = get_hash_substitutions(url, version_substitutions);
+    let basename = url_remote_filename(url);
+    let config = config.cloned().unwrap_or_default();
+
+    let mut hashfile_url = config
+        .url
+        .as_ref()
+        .map(|u| substitute_str(u, &substitutions, false))
+        .unwrap_or_default();
+    let mut mode = if config.mode.is_some() || !hashfile_url.is_empty() {
+        config.mode.clone().unwrap_or_default()
+    } else {
+        HashModeStruct::Download
+    };
+    let jsonpath = config.jsonpath.clone().or(config.jp.clone());
+    if jsonpath.is_some() {
+        mode = HashModeStruct::Json;
+    }
+    if config.xpath.is_some() {
+        mode = HashModeStruct::Xpath;
+    }
+    let regex = config
+        .regex
+        .clone()
+        .or(config.re.clone())
+        .or(config.find.clone());
+
+    let fosshub = Regex::new(r"^(?:.*fosshub.com/).*(?:/|\?dwl=)(?<filename>.*)$").unwrap();
+    let sourceforge = Regex::new(
+        r"(?:downloads\.)?sourceforge.net/projects?/(?<project>[^/]+)/(?:files/)?(?<file>.*)",
+    )
+    .unwrap();
+    let mut fosshub_filename = String::new();
+    if hashfile_url.is_empty() {
+        if let Some(caps) = fosshub.captures(url) {
+            mode = HashModeStruct::Fosshub;
+            fosshub_filename = caps["filename"].to_string();
+        } else if let Some(caps) = sourceforge.captures(url) {
+            mode = HashModeStruct::Sourceforge;
+            let file_url = format!(
+                "https://sourceforge.net/projects/{}/files/{}",
+                &caps["project"], &caps["file"]
+            );
+            hashfile_url = file_url
+                .trim_end_matches('/')
+                .rsplit_once('/')
+                .map(|(base, _)| base.to_string())
+                .unwrap_or(file_url);
+        }
+    }
+    log::info!(
+        "hash mode {:?} for {}, hash url: {}",
+        mode,
+        url,
+        hashfile_url
+    );
+
+    let hash = match mode {
+        HashModeStruct::Extract => {
+            let content = fetch_text(&hashfile_url, None).await?;
+            find_hash_in_text(&content, regex.as_deref(), &substitutions)
+        }
+        HashModeStruct::Json => {
+            let content = fetch_text(&hashfile_url, None).await?;
+            find_hash_in_json(
+                &content,
+                jsonpath.as_deref().unwrap_or_default(),
+                &substitutions,
+            )
+        }
+        HashModeStruct::Xpath => {
+            let content = fetch_text(&hashfile_url, None).await?;
+            find_hash_in_xml(
+                &content,
+                config.xpath.as_deref().unwrap_or_default(),
+                &substitutions,
+            )
+        }
+        HashModeStruct::Rdf => {
+            let content = fetch_text(&hashfile_url, None).await?;
+            find_hash_in_rdf(&content, &basename)
+        }
+        HashModeStruct::Metalink => match find_hash_in_headers(url).await {
+            Some(hash) => Some(hash),
+            None => {
+                let meta_url = format!("{}.meta4", strip_fragment(url));
+                let content = fetch_text(&meta_url, None).await?;
+                find_hash_in_text(&content, None, &substitutions)
+            }
+        },
+        HashModeStruct::Fosshub => {
+            let content = fetch_text(strip_fragment(url), None).await?;
+            let regex = format!(
+                r#"{}.*?"sha256":"([a-fA-F0-9]{{64}})""#,
+                regex::escape(&fosshub_filename)
+            );
+            find_hash_in_text(&content, Some(&regex), &[])
+        }
+        HashModeStruct::Sourceforge => {
+            let content = fetch_text(&hashfile_url, None).await?;
+            let regex = format!(
+                r#""{}":.*?"sha1":\s*"([a-fA-F0-9]{{40}})""#,
+                regex::escape(&basename)
+            );
+            find_hash_in_text(&content, Some(&regex), &[])
+        }
+        HashModeStruct::Download => None,
+    };
+    if let Some(hash) = hash {
+        return Ok(hash);
+    }
+    if !allow_download {
+        bail!("Could not find hash for {} in {}", basename, hashfile_url)
+    }
+    log::info!("Download {} to compute hash", url);
+    compute_hash_by_download(url).await
+}
+
+#[cfg(test)]
+mod test_hash_extract {
+    #[allow(unused_imports)]
+    use super::*;
+
+    fn subs(url: &str) -> Vec<(String, String)> {
+        let version = crate::autoupdate::get_version_substitutions("2.7.0", &Default::default());
+        get_hash_substitutions(url, &version)
+    }
+
+    #[test]
+    fn test_url_substitutions() {
+        let url = "https://github.com/cli/cli/releases/download/v2.7.0/gh_2.7.0_windows_amd64.zip";
+        let subs = subs(url);
+        let get = |k: &str| subs.iter().find(|(n, _)| n == k).unwrap().1.clone();
+        assert_eq!(get("$basename"), "gh_2.7.0_windows_amd64.zip");
+        assert_eq!(get("$basenameNoExt"), "gh_2.7.0_windows_amd64");
+        assert_eq!(
+            get("$baseurl"),
+            "https://github.com/cli/cli/releases/download/v2.7.0"
+        );
+        assert_eq!(
+            url_remote_filename("https://a.com/download#/setup.7z"),
+            "setup.7z"
+        );
+    }
+
+    #[test]
+    fn test_find_hash_in_checksum_file() {
+        let sha = "a".repeat(64);
+        let other = "b".repeat(64);
+        let content =
+            format!("{other}  gh_2.7.0_linux_amd64.tar.gz\n{sha}  gh_2.7.0_windows_amd64.zip\n");
+        let url = "https://example.com/v2.7.0/gh_2.7.0_windows_amd64.zip";
+        assert_eq!(
+            find_hash_in_text(&content, None, &subs(url)),
+            Some(sha.clone())
+        );
+        let regex = "$sha256\\s+$basename";
+        assert_eq!(
+            find_hash_in_text(&content, Some(regex), &subs(url)),
+            Some(sha)
+        );
+        assert_eq!(
+            find_hash_in_text(&"C".repeat(40), None, &[]),
+            Some(format!("sha1:{}", "c".repeat(40)))
+        );
+    }
+
+    #[test]
+    fn test_find_hash_in_structured_content() {
+        let sha = "c".repeat(64);
+        let url = "https://example.com/tool-2.7.0.zip";
+        let json = format!(r#"{{"files":{{"tool-2.7.0.zip":{{"sha256":"{sha}"}}}}}}"#);
+        assert_eq!(
+            find_hash_in_json(&json, "$.files['$basename'].sha256", &subs(url)),
+            Some(sha.clone())
+        );
+        let rdf = format!(
+            r#"<RDF:RDF xmlns:RDF="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:NS="http://x"><RDF:Content RDF:about="tool-2.7.0.zip"><NS:sha256>{sha}</NS:sha256></RDF:Content></RDF:RDF>"#
+        );
+        assert_eq!(find_hash_in_rdf(&rdf, "tool-2.7.0.zip"), Some(sha.clone()));
+        let digest = "SHA-256=47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=";
+        assert_eq!(
+            find_hash_in_digest_header(digest).unwrap(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+}
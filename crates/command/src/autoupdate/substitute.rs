@@ -0,0 +1,172 @@
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// 与 Scoop 的 Get-VersionSubstitution 保持一致, 返回按变量名长度降序排列的替换表,
+/// 避免 `$match` 先于 `$matchHead` 被替换
+pub fn get_version_substitutions(
+    version: &str,
+    custom_matches: &HashMap<String, String>,
+) -> Vec<(String, String)> {
+    let first_part = version.split('-').next().unwrap_or_default();
+    let last_part = version.split('-').last().unwrap_or_default();
+    let dot_parts = first_part.split('.').collect::<Vec<&str>>();
+    let separator = Regex::new(r"[._-]").unwrap();
+
+    let mut substitutions = vec![
+        ("$version".to_string(), version.to_string()),
+        (
+            "$dotVersion".to_string(),
+            separator.replace_all(version, ".").to_string(),
+        ),
+        (
+            "$underscoreVersion".to_string(),
+            separator.replace_all(version, "_").to_string(),
+        ),
+        (
+            "$dashVersion".to_string(),
+            separator.replace_all(version, "-").to_string(),
+        ),
+        (
+            "$cleanVersion".to_string(),
+            separator.replace_all(version, "").to_string(),
+        ),
+        (
+            "$majorVersion".to_string(),
+            dot_parts.first().unwrap_or(&"").to_string(),
+        ),
+        (
+            "$minorVersion".to_string(),
+            dot_parts.get(1).unwrap_or(&"").to_string(),
+        ),
+        (
+            "$patchVersion".to_string(),
+            dot_parts.get(2).unwrap_or(&"").to_string(),
+        ),
+        (
+            "$buildVersion".to_string(),
+            dot_parts.get(3).unwrap_or(&"").to_string(),
+        ),
+        ("$preReleaseVersion".to_string(), last_part.to_string()),
+    ];
+
+    let head_tail = Regex::new(r"(?<head>\d+\.\d+(?:\.\d+)?)(?<tail>.*)").unwrap();
+    if let Some(caps) = head_tail.captures(version) {
+        substitutions.push(("$matchHead".to_string(), caps["head"].to_string()));
+        substitutions.push(("$matchTail".to_string(), caps["tail"].to_string()));
+    }
+
+    for (name, value) in custom_matches {
+        if name == "0" || name.is_empty() {
+            continue;
+        }
+        let key = format!("$match{}", title_case(name));
+        if substitutions.iter().any(|(k, _)| *k == key) {
+            continue;
+        }
+        substitutions.push((key, value.to_string()));
+    }
+
+    substitutions.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+    substitutions
+}
+
+/// 命名捕获组 `version` 对应 `$matchVersion`, 数字捕获组 `1` 对应 `$match1`
+fn title_case(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + &chars.as_str().to_lowercase(),
+        None => String::new(),
+    }
+}
+
+pub fn substitute_str(entity: &str, params: &[(String, String)], regex_escape: bool) -> String {
+    params
+        .iter()
+        .fold(entity.to_string(), |acc, (name, value)| {
+            if regex_escape {
+                acc.replace(name.as_str(), &regex::escape(value))
+            } else {
+                acc.replace(name.as_str(), value)
+            }
+        })
+}
+
+/// 递归替换 JSON 中所有字符串里的变量, 对象的键保持不变
+pub fn substitute_value(entity: &mut Value, params: &[(String, String)], regex_escape: bool) {
+    match entity {
+        Value::String(s) => {
+            *s = substitute_str(s, params, regex_escape);
+        }
+        Value::Array(arr) => {
+            arr.iter_mut()
+                .for_each(|item| substitute_value(item, params, regex_escape));
+        }
+        Value::Object(obj) => {
+            obj.values_mut()
+                .for_each(|item| substitute_value(item, params, regex_escape));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test_substitute {
+    #[allow(unused_imports)]
+    use super::*;
+
+    fn lookup<'a>(subs: &'a [(String, String)], key: &str) -> &'a str {
+        subs.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .unwrap_or("<none>")
+    }
+
+    #[test]
+    fn test_version_variables() {
+        let subs = get_version_substitutions("1.2.3.4-beta2", &HashMap::new());
+        assert_eq!(lookup(&subs, "$version"), "1.2.3.4-beta2");
+        assert_eq!(lookup(&subs, "$majorVersion"), "1");
+        assert_eq!(lookup(&subs, "$minorVersion"), "2");
+        assert_eq!(lookup(&subs, "$patchVersion"), "3");
+        assert_eq!(lookup(&subs, "$buildVersion"), "4");
+        assert_eq!(lookup(&subs, "$preReleaseVersion"), "beta2");
+        assert_eq!(lookup(&subs, "$cleanVersion"), "1234beta2");
+        assert_eq!(lookup(&subs, "$underscoreVersion"), "1_2_3_4_beta2");
+        assert_eq!(lookup(&subs, "$dashVersion"), "1-2-3-4-beta2");
+        assert_eq!(lookup(&subs, "$dotVersion"), "1.2.3.4.beta2");
+        assert_eq!(lookup(&subs, "$matchHead"), "1.2.3");
+        assert_eq!(lookup(&subs, "$matchTail"), ".4-beta2");
+    }
+
+    #[test]
+    fn test_custom_matches_and_order() {
+        let mut matches = HashMap::new();
+        matches.insert("build".to_string(), "b77".to_string());
+        matches.insert("0".to_string(), "ignored".to_string());
+        let subs = get_version_substitutions("2.7.0", &matches);
+        assert_eq!(lookup(&subs, "$matchBuild"), "b77");
+        assert_eq!(lookup(&subs, "$match0"), "<none>");
+        let url = substitute_str(
+            "https://example.com/$matchHead/v$version-$matchBuild.zip",
+            &subs,
+            false,
+        );
+        assert_eq!(url, "https://example.com/2.7.0/v2.7.0-b77.zip");
+    }
+
+    #[test]
+    fn test_substitute_value() {
+        let mut value = serde_json::json!({
+            "url": ["https://a/$version/x.zip", "https://a/$cleanVersion.7z"],
+            "extract_dir": "gh_$version_windows",
+            "keep": true
+        });
+        let subs = get_version_substitutions("2.7.0", &HashMap::new());
+        substitute_value(&mut value, &subs, false);
+        assert_eq!(value["url"][0], "https://a/2.7.0/x.zip");
+        assert_eq!(value["url"][1], "https://a/270.7z");
+        assert_eq!(value["extract_dir"], "gh_2.7.0_windows");
+        assert_eq!(value["keep"], true);
+    }
+}
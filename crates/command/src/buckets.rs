@@ -2,6 +2,7 @@
     get_apps_path, get_apps_path_global, get_buckets_root_dir_path,
     get_buckets_root_dir_path_global,
 };
+use crate::offline::ensure_online;
 use crate::utils::request::{get_git_repo_remote_url, request_git_clone_by_git2_with_progress};
 use anyhow::{anyhow, bail, Context};
 use chrono::{DateTime, Utc};
@@ -123,7 +124,7 @@ impl Buckets {
         }
         let result = self
             .download_bucket(&url, &bucket_name, &bucket_root_dir)
-            .expect("Failed to download bucket");
+            .context(format!("Failed to download bucket '{bucket_name}'"))?;
         println!("{}", result);
         Ok(())
     }
@@ -134,6 +135,7 @@ impl Buckets {
         bucket_name: &str,
         bucket_path: &str,
     ) -> Result<String, anyhow::Error> {
+        ensure_online(&format!("Downloading bucket '{bucket_name}'"))?;
         let bucket_path = bucket_path.to_string() + "\\" + bucket_name;
         println!("{} ", "开始下载...... ".dark_green().bold());
         let result = request_git_clone_by_git2_with_progress(url, &bucket_path)?;
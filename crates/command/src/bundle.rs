@@ -0,0 +1,501 @@
+//! 离线安装包 `.hpb`, 本质是一个 zip:
+//!
+//! ```text
+//! index.json                 索引, 按依赖顺序记录 App、版本、架构与缓存文件哈希
+//! manifests/<app>.json       解析后的清单, 包含全部依赖
+//! cache/<app>#<version>#...  已校验的缓存安装包, 文件名与 DownloadManager 的缓存命名一致
+//! ```
+use crate::init_env::{get_cache_dir_path, get_cache_dir_path_global};
+use crate::install::{
+    handle_arch, install_app_from_local_manifest_file, is_app_installed, resolve_root_node,
+    resolve_target_order, DependencyNode, DownloadManager, InstallOptions, InstallTarget,
+};
+use crate::offline::set_offline_mode;
+use crate::utils::safe_check::unsafe_relative_path_reason;
+use anyhow::{anyhow, bail, Context};
+use crossterm::style::Stylize;
+use hash::{hash_file, verify_files, ExpectedHash, HashAlgorithm, HashJob};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+const INDEX_FILE: &str = "index.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleIndex {
+    pub format_version: u32,
+    /// 打包时解析下载地址使用的架构, 安装时必须一致才能命中缓存文件名
+    pub architecture: String,
+    pub created: String,
+    /// 依赖在前
+    pub apps: Vec<BundleApp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleApp {
+    pub name: String,
+    pub version: String,
+    pub bucket: Option<String>,
+    /// 用户显式指定的 App, 其余作为依赖安装
+    pub requested: bool,
+    pub depends: Vec<String>,
+    pub cache_files: Vec<BundleCacheFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleCacheFile {
+    pub file: String,
+    /// 形如 `sha256:<hex>`
+    pub hash: String,
+}
+
+impl BundleApp {
+    fn manifest_entry(&self) -> String {
+        format!("manifests/{}.json", self.name)
+    }
+}
+
+fn cache_entry(file: &str) -> String {
+    format!("cache/{file}")
+}
+
+/// 索引中的 App 名与缓存文件名会拼接成本地路径, 只允许单个文件名
+fn check_bundle_file_name(field: &str, name: &str) -> anyhow::Result<()> {
+    let reason = if name.trim().is_empty() {
+        Some("empty name is not allowed")
+    } else if name.contains(['/', '\\']) {
+        Some("path separator is not allowed")
+    } else {
+        unsafe_relative_path_reason(name)
+    };
+    if let Some(reason) = reason {
+        bail!("Bundle {field} '{name}' is invalid: {reason}")
+    }
+    Ok(())
+}
+
+impl BundleIndex {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.format_version != BUNDLE_FORMAT_VERSION {
+            bail!(
+                "Unsupported bundle format version {}, expected {}",
+                self.format_version,
+                BUNDLE_FORMAT_VERSION
+            )
+        }
+        for app in &self.apps {
+            check_bundle_file_name("app name", &app.name)?;
+            for cache in &app.cache_files {
+                check_bundle_file_name("cache file", &cache.file)?;
+                ExpectedHash::parse(&cache.hash)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 待写入安装包的 App 及其本地清单和缓存文件, cache_paths 与 app.cache_files 一一对应
+pub struct PackedApp {
+    pub app: BundleApp,
+    pub manifest_path: PathBuf,
+    pub cache_paths: Vec<PathBuf>,
+}
+
+/// 先写入临时文件, 全部成功后再重命名, 避免留下不完整的安装包
+pub fn write_bundle(
+    output: &Path,
+    architecture: &str,
+    packed: &[PackedApp],
+) -> anyhow::Result<BundleIndex> {
+    let index = BundleIndex {
+        format_version: BUNDLE_FORMAT_VERSION,
+        architecture: architecture.to_string(),
+        created: chrono::Local::now().to_rfc3339(),
+        apps: packed.iter().map(|packed| packed.app.clone()).collect(),
+    };
+    index.validate()?;
+    let part = output.with_extension("hpb.part");
+    let result = write_bundle_entries(&part, &index, packed);
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&part);
+        return Err(e);
+    }
+    std::fs::rename(&part, output)
+        .context(format!("Failed to move bundle to {}", output.display()))?;
+    Ok(index)
+}
+
+fn write_bundle_entries(
+    path: &Path,
+    index: &BundleIndex,
+    packed: &[PackedApp],
+) -> anyhow::Result<()> {
+    let file = File::create(path).context(format!("Failed to create {}", path.display()))?;
+    let mut writer = ZipWriter::new(BufWriter::new(file));
+    let deflated = SimpleFileOptions::default();
+    // 安装包本身已经压缩, 直接存储更快
+    let stored = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .large_file(true);
+    for packed in packed {
+        writer.start_file(packed.app.manifest_entry(), deflated)?;
+        let manifest = std::fs::read(&packed.manifest_path).context(format!(
+            "Failed to read manifest {}",
+            packed.manifest_path.display()
+        ))?;
+        writer.write_all(&manifest)?;
+        for (cache, path) in packed.app.cache_files.iter().zip(&packed.cache_paths) {
+            writer.start_file(cache_entry(&cache.file), stored)?;
+            let mut source = File::open(path)
+                .context(format!("Failed to open cache file {}", path.display()))?;
+            std::io::copy(&mut source, &mut writer)
+                .context(format!("Failed to pack cache file {}", path.display()))?;
+        }
+    }
+    writer.start_file(INDEX_FILE, deflated)?;
+    writer.write_all(&serde_json::to_vec_pretty(index)?)?;
+    writer.finish()?.flush()?;
+    Ok(())
+}
+
+pub fn read_bundle_index<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+) -> anyhow::Result<BundleIndex> {
+    let mut content = String::new();
+    archive
+        .by_name(INDEX_FILE)
+        .context("Invalid bundle, index.json is missing")?
+        .read_to_string(&mut content)?;
+    let index: BundleIndex =
+        serde_json::from_str(&content).context("Failed to parse bundle index.json")?;
+    index.validate()?;
+    Ok(index)
+}
+
+fn extract_bundle_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    entry: &str,
+    target: &Path,
+) -> anyhow::Result<()> {
+    let mut source = archive
+        .by_name(entry)
+        .context(format!("Invalid bundle, '{entry}' is missing"))?;
+    let mut file =
+        File::create(target).context(format!("Failed to create {}", target.display()))?;
+    std::io::copy(&mut source, &mut file).context(format!(
+        "Failed to extract '{entry}' to {}",
+        target.display()
+    ))?;
+    Ok(())
+}
+
+/// 解出清单与缓存文件并校验哈希, 校验失败的缓存文件会被删除, 返回解出的清单路径
+pub fn unpack_bundle_app<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    app: &BundleApp,
+    manifest_dir: &Path,
+    cache_dir: &Path,
+) -> anyhow::Result<PathBuf> {
+    let manifest_path = manifest_dir.join(format!("{}.json", app.name));
+    extract_bundle_entry(archive, &app.manifest_entry(), &manifest_path)?;
+    let jobs = app
+        .cache_files
+        .iter()
+        .map(|cache| {
+            let path = cache_dir.join(&cache.file);
+            extract_bundle_entry(archive, &cache_entry(&cache.file), &path)?;
+            Ok(HashJob {
+                path,
+                expected: ExpectedHash::parse(&cache.hash)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let errors = verify_files(&jobs, |_, _, _| {})
+        .into_iter()
+        .zip(&jobs)
+        .filter_map(|(result, job)| {
+            let e = result.err()?;
+            let _ = std::fs::remove_file(&job.path);
+            Some(format!("{e:#}"))
+        })
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        bail!("{}", errors.join("\n"))
+    }
+    for cache in &app.cache_files {
+        println!(
+            "{} {}......✅",
+            "Checking hash of".dark_blue().bold(),
+            cache.file.as_str().dark_cyan().bold(),
+        );
+    }
+    Ok(manifest_path)
+}
+
+fn bundle_temp_dir(name: &str) -> anyhow::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("hp_bundle_{name}_{}", std::process::id()));
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir).context(format!("Failed to create {}", dir.display()))?;
+    Ok(dir)
+}
+
+/// 解析目标及其全部依赖(包括本机已安装的), 按依赖顺序去重
+fn resolve_bundle_nodes(
+    targets: &[String],
+    arch: &str,
+    options: &[InstallOptions],
+) -> anyhow::Result<Vec<(DependencyNode, bool)>> {
+    let global = options.contains(&InstallOptions::Global);
+    let mut nodes: Vec<(DependencyNode, bool)> = vec![];
+    for target in targets {
+        let parsed = InstallTarget::parse(target)?;
+        if let InstallTarget::Url(url) = &parsed {
+            bail!("Bundle does not support remote manifest '{url}', download it first")
+        }
+        let Some((root, _)) = resolve_root_node(&parsed, arch, options)? else {
+            bail!("No local manifest found for '{target}'")
+        };
+        let root_name = root.name.clone();
+        for node in resolve_target_order(root, arch, global)? {
+            let requested = node.name == root_name;
+            match nodes
+                .iter_mut()
+                .find(|(existing, _)| existing.name == node.name)
+            {
+                Some((_, existing_requested)) => *existing_requested |= requested,
+                None => nodes.push((node, requested)),
+            }
+        }
+    }
+    Ok(nodes)
+}
+
+/// 下载并校验目标及其依赖的安装包, 打包为单个离线安装包
+pub fn create_bundle(
+    output: &Path,
+    targets: &[String],
+    options: &[InstallOptions],
+) -> anyhow::Result<BundleIndex> {
+    let arch = handle_arch(options)?;
+    let nodes = resolve_bundle_nodes(targets, &arch, options)?;
+    // 清单统一复制为 <app>.json, 保证打包与安装时 DownloadManager 生成的缓存文件名一致
+    let manifest_dir = bundle_temp_dir("create")?;
+    let download_options = options
+        .iter()
+        .cloned()
+        .chain([InstallOptions::OnlyDownloadNoInstall])
+        .collect::<Vec<_>>();
+    let packed = nodes
+        .into_iter()
+        .map(|(node, requested)| {
+            let manifest_path = manifest_dir.join(format!("{}.json", node.name));
+            std::fs::copy(&node.manifest_path, &manifest_path).context(format!(
+                "Failed to copy manifest {}",
+                node.manifest_path.display()
+            ))?;
+            pack_app(node, requested, manifest_path, &download_options)
+        })
+        .collect::<anyhow::Result<Vec<_>>>();
+    let result = packed.and_then(|packed| write_bundle(output, &arch, &packed));
+    let _ = std::fs::remove_dir_all(&manifest_dir);
+    result
+}
+
+fn pack_app(
+    node: DependencyNode,
+    requested: bool,
+    manifest_path: PathBuf,
+    options: &[InstallOptions],
+) -> anyhow::Result<PackedApp> {
+    let manifest = manifest_path.to_string_lossy().to_string();
+    let download_manager = DownloadManager::new(options, &manifest, node.bucket.as_deref());
+    let cache_paths = download_manager
+        .get_final_cache_file_path()
+        .iter()
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+    if cache_paths.is_empty() {
+        bail!("Failed to resolve download files of '{}'", node.name)
+    }
+    download_manager
+        .start_download()
+        .context(format!("Failed to download '{}'", node.name))?;
+    download_manager.check_cache_file_hash()?;
+
+    // 清单没有提供哈希的文件, 以打包时计算的 sha256 为准
+    let hash_values = download_manager.get_hash_value();
+    let hash_formats = download_manager.get_hash_format();
+    let cache_files = cache_paths
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let file = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .ok_or_else(|| anyhow!("Invalid cache file path {}", path.display()))?;
+            let hash = match (hash_formats.get(i), hash_values.get(i)) {
+                (Some(format), Some(value)) if !value.is_empty() => {
+                    ExpectedHash::new(format.algorithm(), value)
+                }
+                _ => ExpectedHash::new(
+                    HashAlgorithm::Sha256,
+                    &hash_file(path, HashAlgorithm::Sha256)?,
+                ),
+            };
+            Ok(BundleCacheFile {
+                file,
+                hash: format!("{}:{}", hash.algorithm, hash.value),
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let app = BundleApp {
+        name: node.name,
+        version: download_manager.get_app_version().to_string(),
+        bucket: node.bucket,
+        requested,
+        depends: node.depends.iter().map(|spec| spec.name.clone()).collect(),
+        cache_files,
+    };
+    Ok(PackedApp {
+        app,
+        manifest_path,
+        cache_paths,
+    })
+}
+
+/// 只从安装包安装, 不访问 bucket 与网络; 已安装的依赖跳过, 单个 App 失败不会中断其余安装
+pub fn install_bundle(
+    bundle: &Path,
+    options: &[InstallOptions],
+) -> anyhow::Result<Vec<(String, anyhow::Result<()>)>> {
+    let file = File::open(bundle).context(format!("Failed to open bundle {}", bundle.display()))?;
+    let mut archive = ZipArchive::new(BufReader::new(file))
+        .context(format!("{} is not a valid hp bundle", bundle.display()))?;
+    let index = read_bundle_index(&mut archive)?;
+    set_offline_mode(true);
+
+    let global = options.contains(&InstallOptions::Global);
+    let cache_dir = PathBuf::from(if global {
+        get_cache_dir_path_global()
+    } else {
+        get_cache_dir_path()
+    });
+    std::fs::create_dir_all(&cache_dir).context(format!(
+        "Failed to create cache dir {}",
+        cache_dir.display()
+    ))?;
+    let manifest_dir = bundle_temp_dir("install")?;
+    // 安装包已在解包时校验, 架构固定为打包时的架构
+    let base_options = options
+        .iter()
+        .filter(|option| !matches!(option, InstallOptions::ArchOptions(_)))
+        .cloned()
+        .chain([
+            InstallOptions::ArchOptions(index.architecture.as_str()),
+            InstallOptions::NoAutoDownloadDepends,
+            InstallOptions::SkipDownloadHashCheck,
+        ])
+        .collect::<Vec<_>>();
+    let force = options.contains(&InstallOptions::ForceInstallOverride);
+
+    let mut results = vec![];
+    let mut failed = HashSet::new();
+    for app in &index.apps {
+        if !app.requested && !force && is_app_installed(&app.name, global) {
+            continue;
+        }
+        let failed_depend = app.depends.iter().find(|depend| failed.contains(*depend));
+        let result = if let Some(depend) = failed_depend {
+            Err(anyhow!("dependency '{depend}' failed to install"))
+        } else {
+            unpack_bundle_app(&mut archive, app, &manifest_dir, &cache_dir).and_then(
+                |manifest_path| {
+                    let mut install_options = base_options.clone();
+                    if !app.requested {
+                        install_options.push(InstallOptions::InstallAsDependency);
+                    }
+                    install_app_from_local_manifest_file(
+                        manifest_path,
+                        install_options,
+                        app.bucket.as_deref(),
+                    )
+                },
+            )
+        };
+        if result.is_err() {
+            failed.insert(app.name.clone());
+        }
+        results.push((app.name.clone(), result));
+    }
+    let _ = std::fs::remove_dir_all(&manifest_dir);
+    Ok(results)
+}
+
+#[cfg(test)]
+mod test_bundle {
+    #[allow(unused_imports)]
+    use super::*;
+    use crate::test_util::test_temp_dir;
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let dir = test_temp_dir("bundle_test");
+        let (source, manifests, cache) =
+            (dir.join("source"), dir.join("manifests"), dir.join("cache"));
+        for path in [&source, &manifests, &cache] {
+            std::fs::create_dir_all(path).unwrap();
+        }
+        let manifest_path = source.join("demo.json");
+        std::fs::write(&manifest_path, r#"{"version": "1.0"}"#).unwrap();
+        let cache_path = source.join("demo#1.0#abcdef1.zip");
+        std::fs::write(&cache_path, b"hello world").unwrap();
+        let mut app = BundleApp {
+            name: "demo".into(),
+            version: "1.0".into(),
+            bucket: Some("main".into()),
+            requested: true,
+            depends: vec![],
+            cache_files: vec![BundleCacheFile {
+                file: "demo#1.0#abcdef1.zip".into(),
+                hash: "md5:5eb63bbbe01eeed093cb22bb8f5acdc3".into(),
+            }],
+        };
+        let output = dir.join("out.hpb");
+        let packed = |app: &BundleApp| PackedApp {
+            app: app.clone(),
+            manifest_path: manifest_path.clone(),
+            cache_paths: vec![cache_path.clone()],
+        };
+        let index = write_bundle(&output, "64bit", &[packed(&app)]).unwrap();
+
+        let mut archive = ZipArchive::new(File::open(&output).unwrap()).unwrap();
+        assert_eq!(read_bundle_index(&mut archive).unwrap(), index);
+        let unpacked = unpack_bundle_app(&mut archive, &index.apps[0], &manifests, &cache).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(unpacked).unwrap(),
+            r#"{"version": "1.0"}"#
+        );
+        assert_eq!(
+            std::fs::read(cache.join("demo#1.0#abcdef1.zip")).unwrap(),
+            b"hello world"
+        );
+
+        // 哈希不匹配时拒绝安装并删除解出的缓存文件
+        app.cache_files[0].hash = "sha256:0000".into();
+        let index = write_bundle(&output, "64bit", &[packed(&app)]).unwrap();
+        let mut archive = ZipArchive::new(File::open(&output).unwrap()).unwrap();
+        assert!(unpack_bundle_app(&mut archive, &index.apps[0], &manifests, &cache).is_err());
+        assert!(!cache.join("demo#1.0#abcdef1.zip").exists());
+
+        app.name = "..\\evil".into();
+        assert!(write_bundle(&output, "64bit", &[packed(&app)]).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
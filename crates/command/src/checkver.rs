@@ -0,0 +1,329 @@
+use crate::autoupdate::{get_version_substitutions, substitute_str};
+use crate::config::get_config_value_no_print;
+use crate::manifest::manifest_deserialize::{CheckverStruct, ManifestObj};
+use crate::offline::ensure_online;
+use crate::utils::version::Version;
+use anyhow::{bail, Context};
+use futures::StreamExt;
+use reqwest::header;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub mod extract;
+pub use extract::*;
+
+/// Scoop 中 `"checkver": "github"` 及 `checkver.github` 使用的默认正则
+pub const GITHUB_REGEX: &str = r"/releases/tag/(?:v|V)?([\d.]+)";
+
+const CHECKVER_CONCURRENCY: usize = 8;
+
+/// 由清单中的 checkver 字段归一化得到, url 和 regex 已完成版本变量替换
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckverConfig {
+    pub url: String,
+    pub regex: Option<String>,
+    pub jsonpath: Option<String>,
+    pub xpath: Option<String>,
+    pub reverse: bool,
+    pub replace: Option<String>,
+    pub useragent: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckverMatch {
+    pub version: String,
+    /// 正则的全部捕获组, 供 autoupdate 的 `$matchXxx` 变量使用
+    pub matches: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckverResult {
+    pub app_name: String,
+    pub manifest_path: PathBuf,
+    pub current_version: String,
+    pub latest_version: String,
+    pub matches: HashMap<String, String>,
+    pub has_autoupdate: bool,
+}
+
+impl CheckverResult {
+    pub fn is_outdated(&self) -> bool {
+        Version::new(&self.latest_version) > Version::new(&self.current_version)
+    }
+}
+
+pub fn parse_checkver_config(manifest: &ManifestObj) -> anyhow::Result<CheckverConfig> {
+    let Some(checkver) = manifest.get("checkver") else {
+        bail!("manifest does not have checkver field")
+    };
+    let homepage = manifest["homepage"].as_str().unwrap_or_default();
+    let mut config = CheckverConfig {
+        url: homepage.to_string(),
+        ..Default::default()
+    };
+
+    match checkver {
+        serde_json::Value::String(checkver) if checkver == "github" => {
+            if !homepage.starts_with("https://github.com/") {
+                bail!("homepage is not a github repository, checkver 'github' can not be used")
+            }
+            config.url = format!("{}/releases/latest", homepage.trim_end_matches('/'));
+            config.regex = Some(GITHUB_REGEX.to_string());
+        }
+        serde_json::Value::String(regex) => {
+            config.regex = Some(regex.clone());
+        }
+        serde_json::Value::Object(_) => {
+            let checkver = serde_json::from_value::<CheckverStruct>(checkver.clone())
+                .context("checkver field format is invalid")?;
+            if checkver.script.is_some() {
+                bail!("checkver script is not supported")
+            }
+            if let Some(url) = checkver.url {
+                config.url = url;
+            }
+            if let Some(github) = checkver.github {
+                if !github.starts_with("https://github.com/") {
+                    bail!("checkver.github '{github}' is not a github repository")
+                }
+                config.url = format!("{}/releases/latest", github.trim_end_matches('/'));
+                config.regex = Some(GITHUB_REGEX.to_string());
+            }
+            if let Some(re) = checkver.re {
+                config.regex = Some(re);
+            }
+            if let Some(regex) = checkver.regex {
+                config.regex = Some(regex);
+            }
+            config.jsonpath = checkver.jsonpath.or(checkver.jp);
+            config.xpath = checkver.xpath;
+            config.reverse = checkver.reverse.unwrap_or(false);
+            config.replace = checkver.replace;
+            config.useragent = checkver.useragent;
+        }
+        _ => bail!("checkver field format is invalid"),
+    }
+
+    if config.regex.is_none() && config.jsonpath.is_none() && config.xpath.is_none() {
+        bail!("checkver does not have regex, jsonpath or xpath")
+    }
+    if config.url.is_empty() {
+        bail!("checkver does not have url and manifest does not have homepage")
+    }
+
+    let current_version = manifest["version"].as_str().unwrap_or_default();
+    let substitutions = get_version_substitutions(current_version, &HashMap::new());
+    config.url = substitute_str(&config.url, &substitutions, false);
+    config.regex = config
+        .regex
+        .map(|regex| substitute_str(&regex, &substitutions, true));
+    Ok(config)
+}
+
+/// 与 Scoop 一致: 同时存在 jsonpath/xpath 和 regex 时, regex 作用于前者的提取结果
+pub fn extract_version(page: &str, config: &CheckverConfig) -> anyhow::Result<CheckverMatch> {
+    let mut page = page.to_string();
+    let mut version = String::new();
+    if let Some(jsonpath) = config.jsonpath.as_ref() {
+        version = json_path(&page, jsonpath)?;
+    }
+    if let Some(xpath_str) = config.xpath.as_ref() {
+        version = xpath(&page, xpath_str)?;
+    }
+    if (config.jsonpath.is_some() || config.xpath.is_some()) && config.regex.is_some() {
+        page = std::mem::take(&mut version);
+    }
+
+    let mut matches = HashMap::new();
+    if let Some(regex) = config.regex.as_ref() {
+        let matched = regex_match(&page, regex, config.reverse)
+            .context(format!("Couldn't match '{}' in {}", regex, config.url))?;
+        version = matched.groups.get("version").cloned().unwrap_or_default();
+        if let Some(replace) = config.replace.as_ref() {
+            version = regex_replace(&matched.value, regex, replace)?;
+        }
+        if version.is_empty() {
+            version = matched.groups.get("1").cloned().unwrap_or_default();
+        }
+        matches = matched.groups;
+    }
+
+    let version = version.trim().to_string();
+    if version.is_empty() {
+        bail!("Couldn't find new version in {}", config.url)
+    }
+    Ok(CheckverMatch { version, matches })
+}
+
+pub fn build_request_client(useragent: Option<&str>) -> anyhow::Result<reqwest::Client> {
+    let default_agent = format!(
+        "hp/{} (+https://github.com/super1windcloud/hp)",
+        env!("CARGO_PKG_VERSION")
+    );
+    Ok(request_client_builder(useragent.unwrap_or(default_agent.as_str()))?.build()?)
+}
+
+/// 带 User-Agent 与 scoop 配置中 proxy 的 ClientBuilder, 下载器在此基础上追加超时设置
+pub fn request_client_builder(useragent: &str) -> anyhow::Result<reqwest::ClientBuilder> {
+    let mut builder = reqwest::Client::builder().user_agent(useragent);
+
+    let proxy_url = get_config_value_no_print("proxy");
+    if !proxy_url.is_empty() {
+        let proxy_url = if proxy_url.starts_with("http://") || proxy_url.starts_with("https://") {
+            proxy_url
+        } else {
+            format!("http://{}", proxy_url)
+        };
+        let proxy =
+            reqwest::Proxy::all(&proxy_url).context(format!("Invalid proxy url {}", proxy_url))?;
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder)
+}
+
+/// 请求 GitHub API 时如果设置了 GITHUB_TOKEN 环境变量则携带认证, 避免触发限流
+pub async fn fetch_text(url: &str, useragent: Option<&str>) -> anyhow::Result<String> {
+    ensure_online(&format!("Fetching {url}"))?;
+    let client = build_request_client(useragent)?;
+    let mut request = client.get(url);
+    if url.starts_with("https://api.github.com/") {
+        if let Ok(token) = std::env::var("GITHUB_TOKEN") {
+            request = request.header(header::AUTHORIZATION, format!("token {}", token));
+        }
+    }
+    let response = request
+        .send()
+        .await
+        .context(format!("Failed to request {}", url))?;
+    if !response.status().is_success() {
+        bail!("Request {} failed: {}", url, response.status())
+    }
+    let text = response
+        .text()
+        .await
+        .context(format!("Failed to read response from {}", url))?;
+    Ok(text)
+}
+
+pub async fn fetch_checkver_page(config: &CheckverConfig) -> anyhow::Result<String> {
+    fetch_text(&config.url, config.useragent.as_deref()).await
+}
+
+pub async fn checkver_manifest(manifest_path: &Path) -> anyhow::Result<CheckverResult> {
+    let content = std::fs::read_to_string(manifest_path).context(format!(
+        "Failed to read manifest {}",
+        manifest_path.display()
+    ))?;
+    let manifest = serde_json::from_str::<ManifestObj>(&content).context(format!(
+        "Failed to parse manifest {}",
+        manifest_path.display()
+    ))?;
+    let config = parse_checkver_config(&manifest)?;
+    log::info!("checkver url: {}", config.url);
+    let page = fetch_checkver_page(&config).await?;
+    let matched = extract_version(&page, &config)?;
+
+    let app_name = manifest_path
+        .file_stem()
+        .unwrap_or_default()
+        .to_str()
+        .unwrap_or_default()
+        .to_string();
+    Ok(CheckverResult {
+        app_name,
+        manifest_path: manifest_path.to_path_buf(),
+        current_version: manifest["version"].as_str().unwrap_or_default().to_string(),
+        latest_version: matched.version,
+        matches: matched.matches,
+        has_autoupdate: manifest.get("autoupdate").is_some_and(|a| a.is_object()),
+    })
+}
+
+/// 并发检查, 返回结果与输入顺序一致
+pub async fn checkver_manifests(
+    manifest_paths: &[PathBuf],
+) -> Vec<(PathBuf, anyhow::Result<CheckverResult>)> {
+    futures::stream::iter(manifest_paths.iter().cloned())
+        .map(|path| async move {
+            let result = checkver_manifest(&path).await;
+            (path, result)
+        })
+        .buffered(CHECKVER_CONCURRENCY)
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod test_checkver {
+    #[allow(unused_imports)]
+    use super::*;
+    use crate::test_util::{serve_http, test_temp_dir};
+
+    #[test]
+    fn test_parse_checkver_config() {
+        let manifest = serde_json::json!({
+            "version": "1.0.0",
+            "homepage": "https://github.com/cli/cli",
+            "checkver": "github"
+        });
+        let config = parse_checkver_config(&manifest).unwrap();
+        assert_eq!(config.url, "https://github.com/cli/cli/releases/latest");
+        assert_eq!(config.regex.as_deref(), Some(GITHUB_REGEX));
+
+        let manifest = serde_json::json!({
+            "version": "1.0.0",
+            "homepage": "https://example.com",
+            "checkver": { "url": "https://example.com/$majorVersion/feed", "jp": "$.tag" }
+        });
+        let config = parse_checkver_config(&manifest).unwrap();
+        assert_eq!(config.url, "https://example.com/1/feed");
+        assert_eq!(config.jsonpath.as_deref(), Some("$.tag"));
+
+        let manifest = serde_json::json!({ "version": "1.0.0", "checkver": { "script": "x" } });
+        assert!(parse_checkver_config(&manifest).is_err());
+    }
+
+    #[test]
+    fn test_extract_version_with_replace() {
+        let config = CheckverConfig {
+            url: "http://localhost".into(),
+            regex: Some(r"(?<major>\d+)_(?<minor>\d+)_(?<build>\d+)".into()),
+            replace: Some("${major}.${minor}.${build}".into()),
+            ..Default::default()
+        };
+        let matched = extract_version("download tool_2_5_17.zip", &config).unwrap();
+        assert_eq!(matched.version, "2.5.17");
+        assert_eq!(matched.matches["build"], "17");
+
+        let config = CheckverConfig {
+            url: "http://localhost".into(),
+            jsonpath: Some("$.tag_name".into()),
+            regex: Some(r"v(?<version>[\d.]+)".into()),
+            ..Default::default()
+        };
+        let matched = extract_version(r#"{"tag_name":"v0.9.1"}"#, &config).unwrap();
+        assert_eq!(matched.version, "0.9.1");
+    }
+
+    #[tokio::test]
+    async fn test_checkver_manifest_with_local_server() {
+        let base_url = serve_http(|_| ("200 OK", br#"{"release":{"version":"2.3.4"}}"#.to_vec()));
+        let dir = test_temp_dir("checkver");
+        let manifest_path = dir.join("demo.json");
+        let manifest = serde_json::json!({
+            "version": "2.3.0",
+            "url": "https://example.com/demo-2.3.0.zip",
+            "checkver": { "url": format!("{base_url}/latest.json"), "jsonpath": "$.release.version" },
+            "autoupdate": { "url": "https://example.com/demo-$version.zip" }
+        });
+        std::fs::write(&manifest_path, manifest.to_string()).unwrap();
+
+        let result = checkver_manifest(&manifest_path).await.unwrap();
+        assert_eq!(result.app_name, "demo");
+        assert_eq!(result.current_version, "2.3.0");
+        assert_eq!(result.latest_version, "2.3.4");
+        assert!(result.is_outdated());
+        assert!(result.has_autoupdate);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+use anyhow::{bail, Context};
+use regex::Regex;
+use serde_json_path::JsonPath;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegexMatch {
+    /// 整个匹配的文本
+    pub value: String,
+    /// 所有捕获组, 数字捕获组以 "1" "2" 作为键
+    pub groups: HashMap<String, String>,
+}
+
+/// 与 Scoop 保持一致, reverse 为 true 时取最后一个匹配
+pub fn regex_match(page: &str, pattern: &str, reverse: bool) -> anyhow::Result<RegexMatch> {
+    let regex = Regex::new(pattern).context(format!("Invalid regex '{pattern}'"))?;
+    let captures = if reverse {
+        regex.captures_iter(page).last()
+    } else {
+        regex.captures(page)
+    };
+    let Some(captures) = captures else {
+        bail!("Couldn't match '{pattern}'")
+    };
+    let mut groups = HashMap::new();
+    for (index, name) in regex.capture_names().enumerate() {
+        let Some(group) = captures.get(index) else {
+            continue;
+        };
+        groups.insert(index.to_string(), group.as_str().to_string());
+        if let Some(name) = name {
+            groups.insert(name.to_string(), group.as_str().to_string());
+        }
+    }
+    Ok(RegexMatch {
+        value: captures.get(0).unwrap().as_str().to_string(),
+        groups,
+    })
+}
+
+/// replace 使用 `$1` `${version}` 形式引用捕获组
+pub fn regex_replace(matched: &str, pattern: &str, replace: &str) -> anyhow::Result<String> {
+    let regex = Regex::new(pattern).context(format!("Invalid regex '{pattern}'"))?;
+    Ok(regex.replace(matched, replace).to_string())
+}
+
+/// 返回 JSONPath 匹配到的第一个节点, 字符串节点不带引号
+pub fn json_path(page: &str, path: &str) -> anyhow::Result<String> {
+    let json =
+        serde_json::from_str::<serde_json::Value>(page).context("Response is not a valid json")?;
+    let json_path = JsonPath::parse(path).context(format!("Invalid jsonpath '{path}'"))?;
+    let nodes = json_path.query(&json).all();
+    let Some(node) = nodes.first() else {
+        bail!("Couldn't find '{path}' in json")
+    };
+    let result = match node {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    Ok(result)
+}
+
+/// 根节点带有默认命名空间时, 可以通过 `ns:` 前缀访问
+pub fn xpath(page: &str, path: &str) -> anyhow::Result<String> {
+    let package = sxd_document::parser::parse(page).context("Response is not a valid xml")?;
+    let document = package.as_document();
+    let factory = sxd_xpath::Factory::new();
+    let xpath = factory
+        .build(path)
+        .context(format!("Invalid xpath '{path}'"))?
+        .context(format!("Invalid xpath '{path}'"))?;
+    let mut context = sxd_xpath::Context::new();
+    let root_element = document
+        .root()
+        .children()
+        .into_iter()
+        .find_map(|child| child.element());
+    if let Some(root_element) = root_element {
+        if let Some(namespace) = root_element.name().namespace_uri() {
+            context.set_namespace("ns", namespace);
+        }
+        for namespace in root_element.namespaces_in_scope() {
+            context.set_namespace(namespace.prefix(), namespace.uri());
+        }
+    }
+    let value = xpath
+        .evaluate(&context, document.root())
+        .context(format!("Failed to evaluate xpath '{path}'"))?;
+    let result = match value {
+        sxd_xpath::Value::Nodeset(nodes) => match nodes.document_order_first() {
+            Some(node) => node.string_value(),
+            None => bail!("Couldn't find '{path}' in xml"),
+        },
+        other => other.string(),
+    };
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test_extract {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_regex_match_groups() {
+        let page = "v1.2.0 v1.3.0 v1.4.0-beta";
+        let first = regex_match(page, r"v(?<version>[\d.]+)", false).unwrap();
+        assert_eq!(first.groups["version"], "1.2.0");
+        assert_eq!(first.groups["1"], "1.2.0");
+        let last = regex_match(page, r"v([\d.]+)\s", true).unwrap();
+        assert_eq!(last.groups["1"], "1.3.0");
+        assert!(regex_match(page, r"x(\d+)", false).is_err());
+    }
+
+    #[test]
+    fn test_json_path_and_xpath() {
+        let json = r#"{"releases":[{"tag":"v2.0.1","size":10}]}"#;
+        assert_eq!(json_path(json, "$.releases[0].tag").unwrap(), "v2.0.1");
+        assert_eq!(json_path(json, "$.releases[0].size").unwrap(), "10");
+        let xml = r#"<feed xmlns="urn:test"><entry><version>3.4.5</version></entry></feed>"#;
+        assert_eq!(xpath(xml, "//ns:version").unwrap(), "3.4.5");
+        let plain = "<root><item version=\"1.0\"/></root>";
+        assert_eq!(xpath(plain, "/root/item/@version").unwrap(), "1.0");
+    }
+}
@@ -0,0 +1,439 @@
+use crate::init_env::{
+    get_app_dir_install_json, get_app_dir_install_json_global, get_app_dir_manifest_json,
+    get_app_dir_manifest_json_global, get_apps_path, get_apps_path_global,
+    get_special_bucket_child_path, get_special_bucket_child_path_global,
+};
+use crate::install::{
+    get_install_reason, get_manifest_depends, infer_helper_depends, load_local_dependency_node,
+    read_manifest_obj, use_external_7zip, DependencyGraph, DependencySpec, InstallReason,
+};
+use crate::list::VersionJSON;
+use crate::utils::system::get_system_default_arch;
+use anyhow::Context;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyTree {
+    pub name: String,
+    pub bucket: Option<String>,
+    pub installed: bool,
+    /// 该节点已在当前路径上出现, 不再展开
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub circular: bool,
+    pub depends: Vec<DependencyTree>,
+}
+
+impl DependencyTree {
+    /// 带 bucket 前缀的名称, 例如 `extras/vcredist2022`
+    pub fn qualified_name(&self) -> String {
+        match &self.bucket {
+            Some(bucket) => format!("{}/{}", bucket, self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ReverseDependency {
+    pub name: String,
+    pub bucket: Option<String>,
+    /// 声明了该依赖的清单, `manifest.json` 或 `bucket`
+    pub sources: Vec<String>,
+}
+
+fn build_tree(graph: &DependencyGraph, key: &str, path: &mut Vec<String>) -> DependencyTree {
+    let node = &graph.nodes[key];
+    let circular = path.iter().any(|k| k == key);
+    let mut tree = DependencyTree {
+        name: node.name.clone(),
+        bucket: node.bucket.clone(),
+        installed: node.installed,
+        circular,
+        depends: vec![],
+    };
+    if circular {
+        return tree;
+    }
+    path.push(key.to_string());
+    tree.depends = node
+        .depends
+        .iter()
+        .filter(|depend| graph.nodes.contains_key(depend.key()))
+        .map(|depend| build_tree(graph, depend.key(), path))
+        .collect();
+    path.pop();
+    tree
+}
+
+/// 从本地 bucket 的清单解析完整依赖树, 包括推断出的解压工具
+pub fn get_dependency_tree(app: &str, global: bool) -> anyhow::Result<DependencyTree> {
+    let spec = DependencySpec::parse(app)?;
+    let arch = get_system_default_arch()?;
+    let root = load_local_dependency_node(&spec, &arch, global)?;
+    let key = root.name.clone();
+    let mut graph = DependencyGraph::new();
+    graph.add_root(root);
+    graph.expand(true, |spec| load_local_dependency_node(spec, &arch, global))?;
+    Ok(build_tree(&graph, &key, &mut vec![]))
+}
+
+pub fn get_installed_app_names(global: bool) -> anyhow::Result<Vec<String>> {
+    let apps_dir = if global {
+        get_apps_path_global()
+    } else {
+        get_apps_path()
+    };
+    if !Path::new(&apps_dir).exists() {
+        return Ok(vec![]);
+    }
+    let mut names = std::fs::read_dir(&apps_dir)
+        .context(format!("Failed to read apps dir {}", apps_dir))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join("current").exists())
+        .map(|entry| entry.file_name().to_string_lossy().to_lowercase())
+        .filter(|name| name != "scoop")
+        .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+}
+
+fn get_installed_bucket(app_name: &str, global: bool) -> Option<String> {
+    let install_json = if global {
+        get_app_dir_install_json_global(app_name)
+    } else {
+        get_app_dir_install_json(app_name)
+    };
+    let content = std::fs::read_to_string(install_json).ok()?;
+    let install_info: VersionJSON = serde_json::from_str(&content).ok()?;
+    install_info.bucket
+}
+
+/// 读取已安装 App 的 manifest.json 以及其来源 bucket 中的清单, 合并两者声明的 depends
+pub fn get_installed_app_depends(
+    app_name: &str,
+    global: bool,
+) -> (Option<String>, Vec<(DependencySpec, String)>) {
+    let manifest_json = if global {
+        get_app_dir_manifest_json_global(app_name)
+    } else {
+        get_app_dir_manifest_json(app_name)
+    };
+    let bucket = get_installed_bucket(app_name, global);
+    let mut sources = vec![(manifest_json, "manifest.json")];
+    if let Some(bucket) = bucket.as_ref() {
+        let bucket_dir = if global {
+            get_special_bucket_child_path_global(bucket)
+        } else {
+            get_special_bucket_child_path(bucket)
+        };
+        let bucket_manifest = Path::new(&bucket_dir).join(format!("{app_name}.json"));
+        sources.push((bucket_manifest.to_string_lossy().to_string(), "bucket"));
+    }
+
+    let depends = sources
+        .into_iter()
+        .filter(|(path, _)| Path::new(path).exists())
+        .filter_map(|(path, source)| {
+            let manifest = read_manifest_obj(Path::new(&path)).ok()?;
+            let depends = get_manifest_depends(&manifest).ok()?;
+            Some(
+                depends
+                    .into_iter()
+                    .map(|depend| (depend, source.to_string()))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .flatten()
+        .collect();
+    (bucket, depends)
+}
+
+struct InstalledAppDepends {
+    name: String,
+    bucket: Option<String>,
+    depends: Vec<(DependencySpec, String)>,
+}
+
+fn get_all_installed_app_depends(global: bool) -> anyhow::Result<Vec<InstalledAppDepends>> {
+    Ok(get_installed_app_names(global)?
+        .into_iter()
+        .map(|name| {
+            let (bucket, depends) = get_installed_app_depends(&name, global);
+            InstalledAppDepends {
+                name,
+                bucket,
+                depends,
+            }
+        })
+        .collect())
+}
+
+fn find_dependents(
+    installed: &[InstalledAppDepends],
+    target: &DependencySpec,
+) -> Vec<ReverseDependency> {
+    let mut dependents = vec![];
+    for app in installed.iter().filter(|app| app.name != target.name) {
+        let mut sources = vec![];
+        for (depend, source) in app.depends.iter() {
+            let bucket_matches = match (&target.bucket, &depend.bucket) {
+                (Some(expected), Some(actual)) => expected == actual,
+                _ => true,
+            };
+            if depend.name == target.name && bucket_matches && !sources.contains(source) {
+                sources.push(source.clone());
+            }
+        }
+        if !sources.is_empty() {
+            dependents.push(ReverseDependency {
+                name: app.name.clone(),
+                bucket: app.bucket.clone(),
+                sources,
+            });
+        }
+    }
+    dependents
+}
+
+/// 列出显式依赖 `app_name` 的已安装 App, 推断出的解压工具只在安装时需要, 不计入
+pub fn get_installed_dependents(
+    app_name: &str,
+    global: bool,
+) -> anyhow::Result<Vec<ReverseDependency>> {
+    let target = DependencySpec::parse(app_name)?;
+    let installed = get_all_installed_app_depends(global)?;
+    Ok(find_dependents(&installed, &target))
+}
+
+/// 卸载前的依赖检查, 仍被已安装 App 依赖且未指定 force 或 cascade 时返回错误
+pub fn check_uninstall_dependents(
+    app_name: &str,
+    global: bool,
+    force: bool,
+    cascade: bool,
+) -> anyhow::Result<Vec<ReverseDependency>> {
+    let dependents = get_installed_dependents(app_name, global)?;
+    if !dependents.is_empty() && !force && !cascade {
+        let names = dependents
+            .iter()
+            .map(|dependent| dependent.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        anyhow::bail!(
+            "'{app_name}' is required by installed app(s): {names}\nUse --force to uninstall anyway or --cascade to uninstall them too"
+        )
+    }
+    Ok(dependents)
+}
+
+/// 级联卸载的顺序, 依赖于 `app_name` 的 App 在前, `app_name` 本身在最后
+pub fn get_cascade_uninstall_order(app_name: &str, global: bool) -> anyhow::Result<Vec<String>> {
+    fn visit(
+        installed: &[InstalledAppDepends],
+        name: &str,
+        visited: &mut HashSet<String>,
+        order: &mut Vec<String>,
+    ) {
+        if !visited.insert(name.to_string()) {
+            return;
+        }
+        let target = DependencySpec {
+            bucket: None,
+            name: name.to_string(),
+        };
+        for dependent in find_dependents(installed, &target) {
+            visit(installed, &dependent.name, visited, order);
+        }
+        order.push(name.to_string());
+    }
+    let target = DependencySpec::parse(app_name)?;
+    let installed = get_all_installed_app_depends(global)?;
+    let mut order = vec![];
+    visit(&installed, &target.name, &mut HashSet::new(), &mut order);
+    Ok(order)
+}
+
+/// 已安装 App 更新时需要的解压工具, 使用 install.json 中记录的架构推断
+fn get_installed_app_helpers(app_name: &str, global: bool) -> Vec<String> {
+    let install_json = if global {
+        get_app_dir_install_json_global(app_name)
+    } else {
+        get_app_dir_install_json(app_name)
+    };
+    let manifest_json = if global {
+        get_app_dir_manifest_json_global(app_name)
+    } else {
+        get_app_dir_manifest_json(app_name)
+    };
+    let arch = std::fs::read_to_string(install_json)
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|info| info["architecture"].as_str().map(|a| a.to_string()))
+        .unwrap_or_else(|| get_system_default_arch().unwrap_or_default());
+    let Ok(manifest) = read_manifest_obj(Path::new(&manifest_json)) else {
+        return vec![];
+    };
+    infer_helper_depends(&manifest, &arch, use_external_7zip())
+        .into_iter()
+        .map(|helper| helper.to_string())
+        .collect()
+}
+
+/// 从显式安装的 App 出发沿依赖遍历, 未被触达的依赖型 App 即为孤立依赖
+fn find_orphans(apps: &[(String, InstallReason, Vec<String>)]) -> Vec<String> {
+    let depends = apps
+        .iter()
+        .map(|(name, _, depends)| (name.as_str(), depends))
+        .collect::<HashMap<_, _>>();
+    let mut required = HashSet::new();
+    let mut stack = apps
+        .iter()
+        .filter(|(_, reason, _)| *reason == InstallReason::Explicit)
+        .map(|(name, _, _)| name.as_str())
+        .collect::<Vec<_>>();
+    while let Some(name) = stack.pop() {
+        if !required.insert(name) {
+            continue;
+        }
+        if let Some(children) = depends.get(name) {
+            stack.extend(children.iter().map(|c| c.as_str()));
+        }
+    }
+    apps.iter()
+        .filter(|(name, _, _)| !required.contains(name.as_str()))
+        .map(|(name, _, _)| name.clone())
+        .collect()
+}
+
+/// 仅作为依赖安装且不再被任何显式安装的 App 需要的 App, 解压工具仍被已安装 App 的更新所需时保留
+pub fn get_orphaned_dependencies(global: bool) -> anyhow::Result<Vec<String>> {
+    let apps = get_all_installed_app_depends(global)?
+        .into_iter()
+        .map(|app| {
+            let reason = get_install_reason(&app.name, global);
+            let mut depends = app
+                .depends
+                .into_iter()
+                .map(|(depend, _)| depend.name)
+                .collect::<Vec<_>>();
+            depends.extend(get_installed_app_helpers(&app.name, global));
+            (app.name, reason, depends)
+        })
+        .collect::<Vec<_>>();
+    Ok(find_orphans(&apps))
+}
+
+/// 以树形文本输出依赖树, 每行一个节点
+pub fn render_dependency_tree(tree: &DependencyTree) -> Vec<String> {
+    fn render(tree: &DependencyTree, prefix: &str, lines: &mut Vec<String>) {
+        for (i, child) in tree.depends.iter().enumerate() {
+            let last = i + 1 == tree.depends.len();
+            let (branch, indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            lines.push(format!("{prefix}{branch}{}", describe(child)));
+            render(child, &format!("{prefix}{indent}"), lines);
+        }
+    }
+    fn describe(tree: &DependencyTree) -> String {
+        let mut line = tree.qualified_name();
+        if tree.installed {
+            line.push_str(" [installed]");
+        }
+        if tree.circular {
+            line.push_str(" [circular]");
+        }
+        line
+    }
+    let mut lines = vec![describe(tree)];
+    render(tree, "", &mut lines);
+    lines
+}
+
+#[cfg(test)]
+mod test_depends_tree {
+    #[allow(unused_imports)]
+    use super::*;
+
+    fn leaf(name: &str, bucket: Option<&str>) -> DependencyTree {
+        DependencyTree {
+            name: name.to_string(),
+            bucket: bucket.map(|b| b.to_string()),
+            installed: false,
+            circular: false,
+            depends: vec![],
+        }
+    }
+
+    #[test]
+    fn test_find_dependents() {
+        let app = |name: &str, depends: &[&str]| InstalledAppDepends {
+            name: name.to_string(),
+            bucket: Some("main".to_string()),
+            depends: depends
+                .iter()
+                .map(|d| {
+                    (
+                        DependencySpec::parse(d).unwrap(),
+                        "manifest.json".to_string(),
+                    )
+                })
+                .collect(),
+        };
+        let installed = vec![
+            app("a", &["extras/vcredist2022"]),
+            app("b", &["main/vcredist2022"]),
+            app("c", &["vcredist2022", "vcredist2022"]),
+            app("vcredist2022", &[]),
+        ];
+        let target = DependencySpec::parse("extras/vcredist2022").unwrap();
+        let names = find_dependents(&installed, &target)
+            .into_iter()
+            .map(|d| (d.name, d.sources.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![("a".to_string(), 1), ("c".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_find_orphans() {
+        let app = |name: &str, reason: InstallReason, depends: &[&str]| {
+            (
+                name.to_string(),
+                reason,
+                depends.iter().map(|d| d.to_string()).collect::<Vec<_>>(),
+            )
+        };
+        let apps = vec![
+            app("gh", InstallReason::Explicit, &["git"]),
+            app("git", InstallReason::Dependency, &["7zip"]),
+            app("7zip", InstallReason::Dependency, &[]),
+            app("vcredist", InstallReason::Dependency, &["dark"]),
+            app("dark", InstallReason::Dependency, &[]),
+        ];
+        assert_eq!(find_orphans(&apps), vec!["vcredist", "dark"]);
+    }
+
+    #[test]
+    fn test_render_dependency_tree() {
+        let mut lessmsi = leaf("lessmsi", Some("main"));
+        lessmsi.installed = true;
+        let mut seven_zip = leaf("7zip", Some("main"));
+        seven_zip.depends = vec![lessmsi];
+        let mut root = leaf("app", Some("main"));
+        root.depends = vec![seven_zip, leaf("vcredist2022", Some("extras"))];
+        assert_eq!(
+            render_dependency_tree(&root),
+            vec![
+                "main/app",
+                "├── main/7zip",
+                "│   └── main/lessmsi [installed]",
+                "└── extras/vcredist2022",
+            ]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use crate::buckets::get_buckets_path;
 use crate::init_env::{get_apps_path, get_scoop_cfg_path};
+use crate::lockfile::create_lockfile;
 use anyhow::{bail, Context};
 use chrono::{DateTime, Utc};
 use git2::Repository;
@@ -55,6 +56,8 @@ struct InstalledApp {
     Version: String,
     Source: String,
     Name: String,
+    Architecture: String,
+    Held: bool,
 }
 impl InstalledApp {
     fn new(name: String, source: String, updated: String, version: String) -> InstalledApp {
@@ -63,6 +66,8 @@ impl InstalledApp {
             Source: source,
             Updated: updated,
             Version: version,
+            Architecture: String::new(),
+            Held: false,
         }
     }
 }
@@ -124,12 +129,19 @@ fn get_all_installed_apps() -> anyhow::Result<Vec<InstalledApp>> {
             .context("Failed to deserialize manifest file at line 125")?;
         let version = content["version"].as_str().unwrap_or("unknown");
         let updated = get_repo_updated(&path)?;
-        installed_apps.push(InstalledApp::new(
+        let mut app = InstalledApp::new(
             name.to_string(),
             source.to_string(),
             updated,
             version.to_string(),
-        ));
+        );
+        // 导入时还原架构与锁定状态
+        app.Architecture = install_info["architecture"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        app.Held = install_info["hold"].as_bool().unwrap_or(false);
+        installed_apps.push(app);
     }
     Ok(installed_apps)
 }
@@ -247,6 +259,28 @@ pub fn export_config_to_current_dir_with_config(file_name: String) -> anyhow::Re
     Ok(())
 }
 
+/// 导出锁文件, 记录精确版本、架构、下载地址、哈希与 bucket 提交
+pub fn export_lockfile(file_name: String, with_config: bool) -> anyhow::Result<()> {
+    let config = if with_config {
+        let config = get_scoop_config_info()?;
+        Some(serde_json::from_str(&config).context("Failed to deserialize scoop config")?)
+    } else {
+        None
+    };
+    let lockfile = create_lockfile(config)?;
+    let pretty_json =
+        serde_json::to_string_pretty(&lockfile).context("Failed to serialize lockfile")?;
+    let path = std::env::current_dir()?.join(&file_name);
+    std::fs::write(&path, pretty_json)
+        .with_context(|| format!("Failed to write lockfile {}", path.display()))?;
+    println!(
+        "成功导出锁文件到 {}, 共 {} 个App",
+        path.display(),
+        lockfile.apps.len()
+    );
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::env::current_dir;
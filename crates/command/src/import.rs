@@ -1,66 +1,260 @@
-use crate::init_env::get_scoop_config_path;
-use crate::install::install_from_specific_bucket;
-use anyhow::bail;
-use crossterm::style::Stylize;
-use std::process::Stdio;
-
-pub fn write_into_scoop_config(config: String) {
-    let default_config_path = get_scoop_config_path().unwrap();
-    log::info!("{:?}", default_config_path);
-    std::fs::write(default_config_path, config).unwrap();
+use crate::buckets::Buckets;
+use crate::init_env::{
+    get_app_current_dir, get_app_current_dir_global, get_buckets_root_dir_path,
+    get_buckets_root_dir_path_global, get_scoop_config_path,
+};
+use crate::install::{
+    install_app_from_local_manifest_file, install_from_specific_bucket, InstallOptions,
+};
+use crate::lockfile::{resolve_locked_manifest, LockedApp, LockedBucket, Lockfile};
+use anyhow::{bail, Context};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// 导入中单项的结果, 失败时为 Err
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportStatus {
+    Done,
+    Skipped(String),
 }
 
-pub fn add_buckets(buckets: Vec<(&str, &str)>, path: String) -> Result<(), anyhow::Error> {
-    for bucket in buckets {
-        if bucket.0.is_empty() || bucket.1.is_empty() {
-            bail!(
-                "Config_File Error: bucket name or bucket source is empty on {}",
-                path.red().bold()
-            )
+pub type ImportResults = Vec<(String, anyhow::Result<ImportStatus>)>;
+
+/// 导入文件中的配置覆盖同名配置, 其余本地配置保持不变
+pub fn merge_config(existing: &str, imported: &Map<String, Value>) -> anyhow::Result<String> {
+    let existing = existing.trim_start_matches('\u{feff}').trim();
+    let mut config = if existing.is_empty() {
+        Map::new()
+    } else {
+        match serde_json::from_str::<Value>(existing).context("Failed to parse scoop config")? {
+            Value::Object(map) => map,
+            _ => bail!("Scoop config must be a json object"),
         }
-        let name = bucket.0;
-        let source = bucket.1;
-        log::info!("{:?}", name);
-        log::info!("{:?}", source);
-        invoke_hp_bucket_add(name, source);
+    };
+    for (key, value) in imported {
+        config.insert(key.clone(), value.clone());
     }
+    Ok(serde_json::to_string_pretty(&config)?)
+}
 
+pub fn import_config(imported: &Map<String, Value>) -> anyhow::Result<()> {
+    let config_path = get_scoop_config_path()?;
+    let existing =
+        std::fs::read_to_string(&config_path).context(format!("Failed to read {config_path}"))?;
+    let merged = merge_config(&existing, imported)?;
+    std::fs::write(&config_path, merged).context(format!("Failed to write {config_path}"))?;
     Ok(())
 }
 
-fn invoke_hp_bucket_add(name: &str, url: &str) {
-    let cmd = std::process::Command::new("hp")
-        .arg("bucket")
-        .arg("add")
-        .arg(name)
-        .arg(url)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .output()
-        .expect("failed to invoke HP bucket add");
-    if !cmd.status.success() {
-        {
-            let stderr = String::from_utf8_lossy(&cmd.stderr);
-            eprintln!("Error: {}", stderr);
-        }
+fn import_bucket(bucket: &LockedBucket) -> anyhow::Result<ImportStatus> {
+    if bucket.name.is_empty() || bucket.source.is_empty() {
+        bail!("bucket name or bucket source is empty")
+    }
+    let bucket_root = if bucket.global {
+        get_buckets_root_dir_path_global()
+    } else {
+        get_buckets_root_dir_path()
+    };
+    if Path::new(&bucket_root).join(&bucket.name).exists() {
+        return Ok(ImportStatus::Skipped("already added".into()));
+    }
+    Buckets::new()?.add_buckets(
+        Some(bucket.name.clone()),
+        Some(bucket.source.clone()),
+        bucket.global,
+    )?;
+    Ok(ImportStatus::Done)
+}
+
+pub(crate) fn installed_version(app_name: &str, global: bool) -> Option<String> {
+    let current = if global {
+        get_app_current_dir_global(app_name)
+    } else {
+        get_app_current_dir(app_name)
+    };
+    let content = std::fs::read_to_string(Path::new(&current).join("manifest.json")).ok()?;
+    let manifest = serde_json::from_str::<Value>(content.trim_start_matches('\u{feff}')).ok()?;
+    manifest["version"]
+        .as_str()
+        .map(|version| version.to_string())
+}
+
+/// 在 install.json 中写入 hold, 与 `hp hold` / `hp unhold` 一致
+pub fn set_app_hold(app_name: &str, global: bool, held: bool) -> anyhow::Result<()> {
+    let current = if global {
+        get_app_current_dir_global(app_name)
+    } else {
+        get_app_current_dir(app_name)
+    };
+    let install_json = Path::new(&current).join("install.json");
+    let content = std::fs::read_to_string(&install_json)
+        .context(format!("Failed to read {}", install_json.display()))?;
+    let mut install_info: Value = serde_json::from_str(content.trim_start_matches('\u{feff}'))
+        .context(format!("Failed to parse {}", install_json.display()))?;
+    let Value::Object(map) = &mut install_info else {
+        bail!("Invalid {}", install_json.display())
+    };
+    if held {
+        map.insert("hold".into(), Value::Bool(true));
+    } else {
+        map.remove("hold");
+    }
+    std::fs::write(&install_json, serde_json::to_string_pretty(&install_info)?)
+        .context(format!("Failed to write {}", install_json.display()))?;
+    Ok(())
+}
+
+fn app_options(app: &LockedApp) -> Vec<InstallOptions> {
+    let mut options = vec![];
+    if !app.architecture.is_empty() {
+        options.push(InstallOptions::ArchOptions(app.architecture.as_str()));
+    }
+    if app.global {
+        options.push(InstallOptions::Global);
+    }
+    options
+}
+
+/// 已安装导入文件中的版本时跳过; 没有记录版本时已安装即跳过
+fn skip_installed(app: &LockedApp) -> Option<ImportStatus> {
+    let version = installed_version(&app.name, app.global)?;
+    if app.version.is_empty() || app.version == version {
+        Some(ImportStatus::Skipped(format!(
+            "{version} already installed"
+        )))
+    } else {
+        None
+    }
+}
+
+fn finish_app(app: &LockedApp, installed: anyhow::Result<()>) -> anyhow::Result<ImportStatus> {
+    installed?;
+    if app.held {
+        set_app_hold(&app.name, app.global, true)?;
     }
+    Ok(ImportStatus::Done)
 }
 
-pub fn install_apps(app_info: Vec<(&str, &str, &str)>, path: String) -> Result<(), anyhow::Error> {
-    for (app_name, bucket, _) in app_info {
-        if app_name.is_empty() || bucket.is_empty() {
-            bail!(
-                "Config_File Error: app name or bucket name is empty, on {}",
-                path.red().bold()
-            )
+fn import_app(app: &LockedApp) -> anyhow::Result<ImportStatus> {
+    if app.name.is_empty() || app.source.is_empty() {
+        bail!("app name or bucket name is empty")
+    }
+    if let Some(skipped) = skip_installed(app) {
+        return Ok(skipped);
+    }
+    // 已安装其他版本时 install 不会做任何修改, 不能报告为完成
+    if let Some(version) = installed_version(&app.name, app.global) {
+        return Ok(ImportStatus::Skipped(format!(
+            "installed {version}, export has {}",
+            app.version
+        )));
+    }
+    let installed = install_from_specific_bucket(&app.source, &app.name, &app_options(app));
+    finish_app(app, installed)
+}
+
+/// 按锁文件还原精确版本, 依赖先于依赖它的 App 安装
+fn import_locked_apps(lockfile: &Lockfile) -> ImportResults {
+    let manifest_dir =
+        std::env::temp_dir().join(format!("hp_import_locked_{}", std::process::id()));
+    if let Err(e) = std::fs::create_dir_all(&manifest_dir) {
+        return vec![("lockfile".to_string(), Err(e.into()))];
+    }
+    let mut results = vec![];
+    let mut resolved = vec![];
+    for app in &lockfile.apps {
+        if let Some(skipped) = skip_installed(app) {
+            results.push((app.name.clone(), Ok(skipped)));
+            continue;
         }
-        invoke_hp_install(app_name, bucket)?;
+        match resolve_locked_manifest(lockfile, app, &manifest_dir) {
+            Ok((manifest_path, depends)) => resolved.push((app, manifest_path, depends)),
+            Err(e) => results.push((app.name.clone(), Err(e))),
+        }
+    }
+    for (app, manifest_path) in sort_by_depends(resolved) {
+        let options = app_options(app)
+            .into_iter()
+            .chain([InstallOptions::InstallSpecialVersionApp])
+            .collect();
+        let installed = install_app_from_local_manifest_file(
+            &manifest_path,
+            options,
+            Some(app.source.as_str()),
+        );
+        results.push((app.name.clone(), finish_app(app, installed)));
     }
+    let _ = std::fs::remove_dir_all(&manifest_dir);
+    results
+}
 
-    Ok(())
+fn sort_by_depends(
+    mut pending: Vec<(&LockedApp, PathBuf, Vec<String>)>,
+) -> Vec<(&LockedApp, PathBuf)> {
+    let names = pending
+        .iter()
+        .map(|(app, _, _)| app.name.clone())
+        .collect::<HashSet<_>>();
+    let mut done = HashSet::new();
+    let mut ordered = vec![];
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|(_, _, depends)| {
+            depends
+                .iter()
+                .all(|depend| !names.contains(depend) || done.contains(depend))
+        });
+        // 循环依赖时按原顺序安装
+        let (app, manifest_path, _) = pending.remove(ready.unwrap_or(0));
+        done.insert(app.name.clone());
+        ordered.push((app, manifest_path));
+    }
+    ordered
 }
 
-fn invoke_hp_install(app_name: &str, bucket: &str) -> Result<(), anyhow::Error> {
-    install_from_specific_bucket(bucket, app_name, &*vec![])?;
-    Ok(())
+/// 在当前进程内依次导入配置、bucket 与 App, 单项失败不会中断其余导入
+pub fn import_from_file(import: &Lockfile, locked: bool) -> ImportResults {
+    let mut results: ImportResults = vec![];
+    if let Some(config) = import.config.as_ref().and_then(|config| config.as_object()) {
+        if !config.is_empty() {
+            let result = import_config(config).map(|_| ImportStatus::Done);
+            results.push(("config".to_string(), result));
+        }
+    }
+    for bucket in &import.buckets {
+        results.push((format!("bucket {}", bucket.name), import_bucket(bucket)));
+    }
+    if locked {
+        results.extend(import_locked_apps(import));
+    } else {
+        for app in &import.apps {
+            results.push((app.name.clone(), import_app(app)));
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod test_import {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_merge_config() {
+        let imported = serde_json::from_str::<Map<String, Value>>(
+            r#"{"proxy": "127.0.0.1:7890", "aria2-enabled": false}"#,
+        )
+        .unwrap();
+        let merged = merge_config(
+            "\u{feff}{\"proxy\": \"old\", \"root_path\": \"D:\\\\scoop\"}",
+            &imported,
+        )
+        .unwrap();
+        let merged = serde_json::from_str::<Value>(&merged).unwrap();
+        assert_eq!(merged["proxy"], "127.0.0.1:7890");
+        assert_eq!(merged["root_path"], "D:\\scoop");
+        assert_eq!(merged["aria2-enabled"], false);
+        assert!(merge_config("", &imported).is_ok());
+        assert!(merge_config("[]", &imported).is_err());
+    }
 }
@@ -1,12 +1,14 @@
+use crate::autoupdate::install_app_version_from_autoupdate;
 use crate::manifest::install_manifest::InstallManifest;
 use anyhow::{bail, Context, Result};
 use crossterm::style::Stylize;
 use rayon::prelude::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub mod installer;
 use crate::init_env::{
-    check_bucket_whether_exists, get_app_current_dir, get_app_dir, get_app_dir_global,
+    check_bucket_whether_exists, get_app_current_dir, get_app_current_dir_global, get_app_dir,
+    get_app_dir_global, get_app_version_dir, get_app_version_dir_global,
     get_special_bucket_all_manifest_path, get_special_bucket_all_manifest_path_global,
     get_special_version_all_manifest_path, get_special_version_all_manifest_path_global,
 };
@@ -20,17 +22,30 @@ pub mod cli_options_store;
 pub use cli_options_store::*;
 pub mod aria2;
 pub use aria2::*;
+pub mod downloader;
+pub use downloader::*;
+pub mod mirror;
+pub use mirror::*;
 pub mod sevenzip;
 pub use sevenzip::*;
+pub mod extract;
+pub use extract::*;
 pub mod download;
 pub mod parse_lifecycle_scripts;
 pub use parse_lifecycle_scripts::*;
 pub mod env_operate;
+pub mod depends;
+pub use depends::*;
+pub mod batch;
+pub use batch::*;
+pub mod transaction;
+pub use transaction::*;
 use crate::list::VersionJSON;
 use crate::manifest::manifest::{
     get_best_manifest_from_local_bucket, get_best_manifest_from_local_bucket_global,
     get_latest_manifest_from_local_bucket, get_latest_manifest_from_local_bucket_global,
 };
+use crate::plan::{InstallStage, INSTALL_STAGES};
 use crate::utils::system::{ensure_persist_permission, kill_processes_using_app};
 use crate::utils::utility::{nightly_version, validate_version};
 pub use download::*;
@@ -64,34 +79,35 @@ pub fn install_app_from_local_manifest_file<P: AsRef<Path>>(
     if app_name.is_empty() {
         bail!("manifest file name is empty")
     }
+    let global = options.contains(&InstallOptions::Global);
+    // 更新时沿用之前的安装原因, 避免依赖被更新后变为显式安装
+    let options = if options.contains(&InstallOptions::UpdateTransaction)
+        && get_install_reason(&app_name, global) == InstallReason::Dependency
+    {
+        options
+            .to_vec()
+            .into_iter()
+            .chain([InstallOptions::InstallAsDependency])
+            .collect::<Box<[InstallOptions]>>()
+    } else {
+        options
+    };
     let version = &serde_obj.version.unwrap_or(String::new());
     if version.is_empty() {
         bail!("manifest file version is empty")
     }
-    if options.contains(&InstallOptions::ForceInstallOverride) {
-        let special_app_dir = if options.contains(&InstallOptions::Global) {
+    // 覆盖安装时旧目录在事务开始后才移走, 安装失败可以回滚到之前的版本
+    let override_app_dir = if options.contains(&InstallOptions::ForceInstallOverride)
+        && app_name.to_lowercase() != "hp"
+    {
+        Some(if global {
             get_app_dir_global(&app_name)
         } else {
             get_app_dir(&app_name)
-        };
-
-        if Path::new(&special_app_dir).exists() && app_name.to_lowercase() != "hp" {
-            let result = std::fs::remove_dir_all(&special_app_dir);
-            match result {
-                Ok(_) => {
-                    log::debug!("remove app dir success!");
-                }
-                Err(_) => {
-                    log::debug!("kill {app_name}  process");
-                    kill_processes_using_app(&app_name);
-                    std::fs::remove_dir_all(&special_app_dir).context(format!(
-                        "remove app dir '{}' failed at line 83",
-                        special_app_dir
-                    ))?;
-                }
-            }
-        }
-    }
+        })
+    } else {
+        None
+    };
     validate_version(version)?;
 
     let version = if version == "nightly" {
@@ -165,6 +181,17 @@ pub fn install_app_from_local_manifest_file<P: AsRef<Path>>(
         0
     };
     if result != 0 {
+        if !options.contains(&InstallOptions::InstallAsDependency)
+            && get_install_reason(&app_name, global) == InstallReason::Dependency
+        {
+            set_install_reason(&app_name, global, InstallReason::Explicit)?;
+            println!(
+                "{}",
+                format!("'{app_name}' is now marked as explicitly installed")
+                    .dark_green()
+                    .bold()
+            );
+        }
         return Ok(());
     };
     let end_message = if bucket_source.is_none() {
@@ -180,38 +207,33 @@ pub fn install_app_from_local_manifest_file<P: AsRef<Path>>(
             .dark_green()
     );
 
-    let depends = serde_obj.depends;
     let suggest = serde_obj.suggest;
     let notes = serde_obj.notes;
-    let env_set = serde_obj.env_set;
-    let env_add_path = serde_obj.env_add_path;
+    let mut env_set = serde_obj.env_set;
+    let mut env_add_path = serde_obj.env_add_path;
     // let url = serde_obj.url;
     // let hash = serde_obj.hash;
     // let installer = serde_obj.installer;
     // let shortcuts = serde_obj.shortcuts;
     let architecture = serde_obj.architecture;
     // let bin = serde_obj.bin;
-    let extract_dir = serde_obj.extract_dir;
-    let extract_to = serde_obj.extract_to;
+    let mut extract_dir = serde_obj.extract_dir;
+    let mut extract_to = serde_obj.extract_to;
     // let innosetup = serde_obj.innosetup;
     let persist = serde_obj.persist;
-    let psmodule = serde_obj.psmodule;
+    let mut psmodule = serde_obj.psmodule;
     // let pre_install = serde_obj.pre_install;
     // let post_install = serde_obj.post_install;
 
-    if !depends.is_none() && !options.contains(&InstallOptions::NoAutoDownloadDepends) {
-        let depend = depends.unwrap();
-        match depend {
-            StringArrayOrString::StringArray(array) => {
-                for depend in array {
-                    handle_depends(depend, options.as_ref())?;
-                }
-            }
-            StringArrayOrString::String(_depend) => {
-                handle_depends(_depend, options.as_ref())?;
-            }
-            StringArrayOrString::Null => {}
-        }
+    if !options.contains(&InstallOptions::NoAutoDownloadDepends) {
+        let plan = resolve_install_plan(
+            Path::new(manifest_path),
+            &app_name,
+            bucket_source,
+            &install_arch,
+            &options,
+        )?;
+        install_dependency_plan(&plan, &options)?;
     }
 
     //   **invoke aria2  to  download  file to cache
@@ -223,85 +245,127 @@ pub fn install_app_from_local_manifest_file<P: AsRef<Path>>(
     if options.contains(&InstallOptions::OnlyDownloadNoInstall) {
         return Ok(());
     }
-    //  * 提取 cache 中的zip 到 app dir
-    let senvenzip =
-        download_manager.invoke_7z_extract(extract_dir, extract_to, architecture.clone())?;
-    senvenzip.link_current()?;
-    // !  parse    pre_install
-    parse_lifecycle_scripts(
-        LifecycleScripts::PreInstall,
-        manifest_path,
-        &options,
-        &app_name,
-        None,
-    )
-    .expect("parse pre_install failed");
-
-    // !  parse    manifest installer
-    parse_lifecycle_scripts(
-        LifecycleScripts::Installer,
-        manifest_path,
-        &options,
-        &app_name,
-        None,
-    )
-    .expect("parse installer scripts failed");
-
-    //  ? linking   app current dir to app version dir
-    // senvenzip.link_current()?;
-    //*create_shims
-    //*create_startmenu_shortcuts
-    create_shim_or_shortcuts(manifest_path, &app_name, &options)
-        .expect("create shim or shortcuts failed");
-
-    // * install_psmodule
-    if psmodule.is_some() {
-        let psmodule = psmodule.unwrap();
-        let global = if options.contains(&InstallOptions::Global) {
-            true
-        } else {
-            false
-        };
-        install_psmodule(global, psmodule, &app_name, version).expect("install_psmodule failed");
-    }
-    if !env_set.is_none() {
-        handle_env_set(env_set.unwrap(), obj_copy, &options)?;
-    };
-    if env_add_path.is_some() {
-        let env_add_path = env_add_path.unwrap();
-        if env_add_path != StringArrayOrString::Null {
-            let app_current_dir = get_app_current_dir(&app_name);
-            handle_env_add_path(env_add_path, app_current_dir, &options)?;
+    // 解压之后的每一步都记录到安装日志, 任一步失败时逆序撤销, 恢复之前链接的版本
+    let transaction = InstallTransaction::begin(&app_name, version, global)?;
+    let result = (|| -> Result<()> {
+        if let Some(app_dir) = &override_app_dir {
+            let app_dir = Path::new(app_dir);
+            if journal_stash_dir(app_dir).is_err() {
+                log::debug!("kill {app_name}  process");
+                kill_processes_using_app(&app_name);
+                journal_stash_dir(app_dir)
+                    .context(format!("remove app dir '{}' failed", app_dir.display()))?;
+            }
         }
-    }
-    // ! linking  persist_data  链接 Persist 目录
-    create_persist_data_link(persist.clone(), &options, &app_name)
-        .expect("create persist link failed");
-
-    //*persist_permission  主要用于 设置文件系统权限，确保特定用户（通常是 "Users" 组）对某个目录具有写入权限。
-    if persist.is_some() {
-        let global = if options.contains(&InstallOptions::Global) {
-            true
-        } else {
-            false
-        };
-        if global {
-            ensure_persist_permission().expect("persist dir check failed");
+        // 阶段顺序与 plan_install_from_manifest 共用
+        for stage in INSTALL_STAGES {
+            match stage {
+                InstallStage::Extract => {
+                    let version_dir = if global {
+                        get_app_version_dir_global(&app_name, version)
+                    } else {
+                        get_app_version_dir(&app_name, version)
+                    };
+                    journal_create_dir(Path::new(&version_dir))?;
+                    //  * 提取 cache 中的zip 到 app dir
+                    let senvenzip = download_manager.invoke_7z_extract(
+                        extract_dir.take(),
+                        extract_to.take(),
+                        architecture.clone(),
+                    )?;
+                    let current_dir = if global {
+                        get_app_current_dir_global(&app_name)
+                    } else {
+                        get_app_current_dir(&app_name)
+                    };
+                    journal_replace_link(Path::new(&current_dir))?;
+                    senvenzip.link_current()?;
+                }
+                // !  parse    pre_install
+                InstallStage::PreInstall => parse_lifecycle_scripts(
+                    LifecycleScripts::PreInstall,
+                    manifest_path,
+                    &options,
+                    &app_name,
+                    None,
+                )
+                .context("parse pre_install failed")?,
+                // !  parse    manifest installer
+                InstallStage::Installer => parse_lifecycle_scripts(
+                    LifecycleScripts::Installer,
+                    manifest_path,
+                    &options,
+                    &app_name,
+                    None,
+                )
+                .context("parse installer scripts failed")?,
+                //*create_shims
+                //*create_startmenu_shortcuts
+                InstallStage::ShimsAndShortcuts => {
+                    create_shim_or_shortcuts(manifest_path, &app_name, &options)
+                        .context("create shim or shortcuts failed")?
+                }
+                // * install_psmodule
+                InstallStage::PsModule => {
+                    if let Some(psmodule) = psmodule.take() {
+                        install_psmodule(global, psmodule, &app_name, version)
+                            .context("install_psmodule failed")?;
+                    }
+                }
+                InstallStage::EnvSet => {
+                    if let Some(env_set) = env_set.take() {
+                        handle_env_set(env_set, obj_copy.clone(), &options)?;
+                    }
+                }
+                InstallStage::EnvAddPath => {
+                    if let Some(env_add_path) = env_add_path.take() {
+                        if env_add_path != StringArrayOrString::Null {
+                            let app_current_dir = get_app_current_dir(&app_name);
+                            handle_env_add_path(env_add_path, app_current_dir, &options)?;
+                        }
+                    }
+                }
+                InstallStage::Persist => {
+                    // ! linking  persist_data  链接 Persist 目录
+                    create_persist_data_link(persist.clone(), &options, &app_name)
+                        .context("create persist link failed")?;
+                    //*persist_permission  主要用于 设置文件系统权限，确保特定用户（通常是 "Users" 组）对某个目录具有写入权限。
+                    if persist.is_some() && global {
+                        ensure_persist_permission().context("persist dir check failed")?;
+                    }
+                }
+                // !   parse post_install
+                InstallStage::PostInstall => parse_lifecycle_scripts(
+                    LifecycleScripts::PostInstall,
+                    &manifest_path,
+                    &options,
+                    &app_name,
+                    None,
+                )
+                .context("parse post_install failed")?,
+            }
+        }
+        //*  save  install.json , manifest.json  to app version dir
+        download_manager
+            .save_install_info()
+            .context("save install info failed")?;
+        Ok(())
+    })();
+    if let Err(e) = result {
+        eprintln!(
+            "{}",
+            format!("Install '{app_name}' ({version}) failed: {e:#}")
+                .dark_red()
+                .bold()
+        );
+        if let Err(rollback_err) = transaction.rollback() {
+            return Err(e.context(rollback_err.to_string()));
         }
+        return Err(e.context(format!(
+            "'{app_name}' was rolled back to its previous state"
+        )));
     }
-    // !   parse post_install
-    parse_lifecycle_scripts(
-        LifecycleScripts::PostInstall,
-        &manifest_path,
-        &options,
-        &app_name,
-        None,
-    )
-    .expect("parse post_install failed");
-    //*  save  install.json , manifest.json  to app version dir
-    download_manager
-        .save_install_info()
-        .expect("save install info failed");
+    transaction.commit();
     if !suggest.is_none() {
         show_suggest(&suggest.unwrap())?;
     }
@@ -359,11 +423,12 @@ pub fn install_from_specific_bucket(
     Ok(())
 }
 
-pub async fn install_app_specific_version(
+/// 在本地 bucket 中查找指定版本的清单, 优先 main, extras, versions
+pub fn find_specific_version_manifest(
     app_name: &str,
     app_version: &str,
-    options: &Vec<InstallOptions<'_>>,
-) -> Result<()> {
+    options: &[InstallOptions<'_>],
+) -> Result<Option<String>> {
     let all_manifests = if options.contains(&InstallOptions::Global) {
         get_special_version_all_manifest_path_global()?
     } else {
@@ -443,16 +508,31 @@ pub async fn install_app_specific_version(
     } else {
         special_version_manifests.get(0)
     };
+    Ok(special_version_manifest.cloned())
+}
+
+pub async fn install_app_specific_version(
+    app_name: &str,
+    app_version: &str,
+    options: &Vec<InstallOptions<'_>>,
+) -> Result<()> {
+    let special_version_manifest = find_specific_version_manifest(app_name, app_version, options)?;
     if special_version_manifest.is_none() {
-        bail!(
-            "app '{}' version '{}' not found ,check it!",
+        log::info!(
+            "app '{}' version '{}' not found in buckets, try autoupdate",
             app_name,
             app_version
-        )
+        );
+        return install_app_version_from_autoupdate(app_name, app_version, options)
+            .await
+            .context(format!(
+                "app '{}' version '{}' not found ,check it!",
+                app_name, app_version
+            ));
     }
     let special_version_manifest = special_version_manifest.unwrap();
     let source_bucket = (|| {
-        let path = Path::new(special_version_manifest);
+        let path = Path::new(&special_version_manifest);
         let parent = path.parent().unwrap().parent().unwrap();
         parent.file_name().unwrap().to_str().unwrap()
     })();
@@ -463,15 +543,12 @@ pub async fn install_app_specific_version(
         .into_iter()
         .chain([InstallOptions::InstallSpecialVersionApp])
         .collect();
-    install_app_from_local_manifest_file(special_version_manifest, options, Some(source_bucket))?;
+    install_app_from_local_manifest_file(&special_version_manifest, options, Some(source_bucket))?;
     Ok(())
 }
 
-pub fn install_app(app_name: &str, options: &[InstallOptions<'_>]) -> Result<()> {
-    log::info!("install from app {}", app_name);
-    if app_name.to_lowercase() == "hp" {
-        bail!("Update self please use `hp u hp` or `hp u -f -k hp`")
-    }
+/// 更新时使用 bucket 中的最新版本清单, 安装时使用最优清单, 全局安装找不到时回退到用户 bucket
+pub fn find_app_manifest_path(app_name: &str, options: &[InstallOptions<'_>]) -> Result<PathBuf> {
     let manifest_path = if options.contains(&InstallOptions::Global) {
         if options.contains(&InstallOptions::UpdateTransaction) {
             let result = get_latest_manifest_from_local_bucket_global(app_name);
@@ -494,6 +571,15 @@ pub fn install_app(app_name: &str, options: &[InstallOptions<'_>]) -> Result<()>
     } else {
         get_best_manifest_from_local_bucket(app_name)?
     };
+    Ok(manifest_path)
+}
+
+pub fn install_app(app_name: &str, options: &[InstallOptions<'_>]) -> Result<()> {
+    log::info!("install from app {}", app_name);
+    if app_name.to_lowercase() == "hp" {
+        bail!("Update self please use `hp u hp` or `hp u -f -k hp`")
+    }
+    let manifest_path = find_app_manifest_path(app_name, options)?;
 
     let duplicate = manifest_path.clone();
     if !duplicate.exists() {
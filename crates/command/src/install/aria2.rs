@@ -1,4 +1,5 @@
 use crate::config::get_config_value_no_print;
+use crate::install::get_scoop_user_agent;
 use crate::utils::utility::is_valid_url;
 use anyhow::{bail, Context};
 use crossterm::style::Stylize;
@@ -334,35 +335,7 @@ impl<'a> Aria2C<'a> {
         &self.aria2c_path
     }
     pub fn get_scoop_user_agent(&self) -> String {
-        let os_info = os_info::get();
-        let os_version = os_info.version().to_string();
-        // 检测系统架构
-        let arch = env::consts::ARCH;
-        let mut arch_info = String::new();
-
-        // 检查是否是 ARM64
-        if cfg!(target_arch = "aarch64") {
-            arch_info.push_str("ARM64; ");
-        }
-        // 检查是否是 AMD64 (x86_64)
-        else if arch == "x86_64" {
-            arch_info.push_str("Win64; x64; ");
-        }
-
-        // 检查是否运行在 WOW64 模式下（32位程序在64位系统）
-        if let Ok(program_files_arm) = env::var("ProgramFiles(Arm)") {
-            if !program_files_arm.is_empty() {
-                arch_info.push_str("WOW64; ");
-            }
-        }
-
-        format!(
-            "Scoop/1.0 (+http://scoop.sh/) Rust/{} (Windows NT {}; {}){}",
-            env!("CARGO_PKG_VERSION"),
-            os_version,
-            arch_info,
-            if cfg!(windows) { "Windows" } else { "" }
-        )
+        get_scoop_user_agent()
     }
     pub fn invoke_aria2c_download<'cmd>(&self) -> anyhow::Result<String> {
         let aria2_exe = self.get_aria2c_path();
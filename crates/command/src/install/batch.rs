@@ -0,0 +1,317 @@
+use crate::autoupdate::install_app_version_from_autoupdate;
+use crate::install::{
+    find_specific_version_manifest, get_bucket_name_from_manifest_path, handle_arch,
+    install_app_from_local_manifest_file, install_app_from_url, is_app_installed,
+    load_local_dependency_node, read_manifest_obj, use_external_7zip, DependencyGraph,
+    DependencyNode, DependencySpec, DownloadManager, InstallOptions,
+};
+use crate::utils::utility::is_valid_url;
+use anyhow::{anyhow, bail};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// `hp install` 的单个参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallTarget {
+    ManifestFile(PathBuf),
+    Url(String),
+    App(DependencySpec),
+    Version { name: String, version: String },
+}
+
+impl InstallTarget {
+    pub fn parse(target: &str) -> anyhow::Result<Self> {
+        let target = target.trim();
+        if is_valid_url(target) {
+            return Ok(InstallTarget::Url(target.to_string()));
+        }
+        let path = Path::new(target);
+        if path.is_file() {
+            if path.extension().unwrap_or_default() != "json" {
+                bail!("{} is not a json file", path.display())
+            }
+            return Ok(InstallTarget::ManifestFile(path.to_path_buf()));
+        }
+        if let Some((name, version)) = target.split_once('@') {
+            let (name, version) = (name.trim(), version.trim());
+            if name.is_empty() || version.is_empty() || name.contains('/') {
+                bail!("指定的APP格式错误: {target}")
+            }
+            return Ok(InstallTarget::Version {
+                name: name.to_lowercase(),
+                version: version.to_lowercase(),
+            });
+        }
+        Ok(InstallTarget::App(DependencySpec::parse(target)?))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct PlannedApp {
+    pub(crate) node: DependencyNode,
+    pub(crate) requested: bool,
+    pub(crate) specific_version: bool,
+}
+
+/// 安装阶段才能确定清单的目标, 例如远程 URL 与需要 autoupdate 生成清单的版本
+pub(crate) enum DeferredInstall {
+    Url(String),
+    AutoupdateVersion { name: String, version: String },
+}
+
+/// 全部目标合并后的安装顺序, 依赖在前且去重
+pub(crate) struct ResolvedTargets {
+    pub(crate) plan: Vec<PlannedApp>,
+    pub(crate) deferred: Vec<(String, DeferredInstall)>,
+    pub(crate) failures: Vec<(String, anyhow::Error)>,
+}
+
+pub(crate) fn resolve_root_node(
+    target: &InstallTarget,
+    arch: &str,
+    options: &[InstallOptions],
+) -> anyhow::Result<Option<(DependencyNode, bool)>> {
+    let global = options.contains(&InstallOptions::Global);
+    let (spec, manifest_path, specific_version) = match target {
+        InstallTarget::App(spec) => {
+            let node = load_local_dependency_node(spec, arch, global)?;
+            return Ok(Some((node, false)));
+        }
+        InstallTarget::ManifestFile(path) => {
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            let spec = DependencySpec { bucket: None, name };
+            (spec, path.clone(), false)
+        }
+        InstallTarget::Version { name, version } => {
+            let Some(path) = find_specific_version_manifest(name, version, options)? else {
+                return Ok(None);
+            };
+            let path = PathBuf::from(path);
+            let spec = DependencySpec {
+                bucket: get_bucket_name_from_manifest_path(&path),
+                name: name.clone(),
+            };
+            (spec, path, true)
+        }
+        InstallTarget::Url(_) => return Ok(None),
+    };
+    let manifest = read_manifest_obj(&manifest_path)?;
+    let node = DependencyNode::from_manifest(
+        &spec,
+        manifest_path,
+        &manifest,
+        arch,
+        use_external_7zip(),
+        false,
+    )?;
+    Ok(Some((node, specific_version)))
+}
+
+pub(crate) fn resolve_target_order(
+    root: DependencyNode,
+    arch: &str,
+    global: bool,
+) -> anyhow::Result<Vec<DependencyNode>> {
+    let mut graph = DependencyGraph::new();
+    graph.add_root(root);
+    graph.expand(false, |spec| load_local_dependency_node(spec, arch, global))?;
+    Ok(graph.topological_order()?.into_iter().cloned().collect())
+}
+
+fn install_options_for<'a>(
+    options: &[InstallOptions<'a>],
+    app: &PlannedApp,
+) -> Vec<InstallOptions<'a>> {
+    // 安装包已在计划阶段下载, 安装时直接使用缓存
+    let mut install_options = options
+        .iter()
+        .filter(|option| {
+            !matches!(
+                option,
+                InstallOptions::NoUseDownloadCache
+                    | InstallOptions::ForceDownloadNoInstallOverrideCache
+            )
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    install_options.push(InstallOptions::NoAutoDownloadDepends);
+    if !app.requested {
+        install_options.push(InstallOptions::InstallAsDependency);
+    }
+    if app.specific_version {
+        install_options.push(InstallOptions::InstallSpecialVersionApp);
+    }
+    install_options
+}
+
+pub(crate) fn resolve_install_targets(
+    targets: &[String],
+    arch: &str,
+    options: &[InstallOptions],
+) -> ResolvedTargets {
+    let global = options.contains(&InstallOptions::Global);
+    let mut failures = vec![];
+    let mut plan: Vec<PlannedApp> = vec![];
+    let mut deferred = vec![];
+    for target in targets {
+        let resolved = InstallTarget::parse(target).and_then(|parsed| {
+            let root = resolve_root_node(&parsed, arch, options)?;
+            Ok((parsed, root))
+        });
+        let (parsed, root) = match resolved {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                failures.push((target.clone(), e));
+                continue;
+            }
+        };
+        let Some((root, specific_version)) = root else {
+            match parsed {
+                InstallTarget::Url(url) => {
+                    deferred.push((target.clone(), DeferredInstall::Url(url)))
+                }
+                InstallTarget::Version { name, version } => deferred.push((
+                    target.clone(),
+                    DeferredInstall::AutoupdateVersion { name, version },
+                )),
+                _ => {}
+            }
+            continue;
+        };
+        let root_name = root.name.clone();
+        let order = match resolve_target_order(root, arch, global) {
+            Ok(order) => order,
+            Err(e) => {
+                failures.push((target.clone(), e));
+                continue;
+            }
+        };
+        for node in order {
+            let requested = node.name == root_name;
+            if let Some(existing) = plan.iter_mut().find(|app| app.node.name == node.name) {
+                existing.requested |= requested;
+                continue;
+            }
+            if !requested && node.installed {
+                continue;
+            }
+            plan.push(PlannedApp {
+                node,
+                requested,
+                specific_version: requested && specific_version,
+            });
+        }
+    }
+
+    ResolvedTargets {
+        plan,
+        deferred,
+        failures,
+    }
+}
+
+/// 先统一解析全部目标及其依赖, 并发下载后按依赖顺序逐个安装, 单个 App 失败不会中断其余安装
+pub async fn install_apps_with_plan(
+    targets: &[String],
+    options: &[InstallOptions<'_>],
+) -> Vec<(String, anyhow::Result<()>)> {
+    let arch = match handle_arch(options) {
+        Ok(arch) => arch,
+        Err(e) => {
+            return vec![(targets.join(" "), Err(e))];
+        }
+    };
+    let global = options.contains(&InstallOptions::Global);
+    let ResolvedTargets {
+        plan,
+        deferred,
+        failures,
+    } = resolve_install_targets(targets, &arch, options);
+    let mut results = failures
+        .into_iter()
+        .map(|(target, e)| (target, Err(e)))
+        .collect::<Vec<_>>();
+
+    // 并发下载全部安装包, 失败的 App 在安装阶段直接报告
+    let force = options.contains(&InstallOptions::ForceInstallOverride);
+    let prefetch_errors = plan
+        .par_iter()
+        .filter(|app| force || !is_app_installed(&app.node.name, global))
+        .filter_map(|app| {
+            let manifest_path = app.node.manifest_path.to_string_lossy().to_string();
+            let download_manager =
+                DownloadManager::new(options, &manifest_path, app.node.bucket.as_deref());
+            download_manager
+                .start_download()
+                .err()
+                .map(|e| (app.node.name.clone(), e))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut failed = HashSet::new();
+    for app in plan.iter() {
+        let name = app.node.name.clone();
+        let failed_depend = app
+            .node
+            .depends
+            .iter()
+            .find(|depend| failed.contains(depend.key()));
+        let result = if let Some(depend) = failed_depend {
+            Err(anyhow!("dependency '{depend}' failed to install"))
+        } else if let Some(e) = prefetch_errors.get(&name) {
+            Err(anyhow!("download failed: {e:#}"))
+        } else {
+            let install_options = install_options_for(options, app);
+            install_app_from_local_manifest_file(
+                &app.node.manifest_path,
+                install_options,
+                app.node.bucket.as_deref(),
+            )
+        };
+        if result.is_err() {
+            failed.insert(name.clone());
+        }
+        results.push((name, result));
+    }
+
+    for (target, install) in deferred {
+        let result = match install {
+            DeferredInstall::Url(url) => install_app_from_url(Path::new(&url), options, None),
+            DeferredInstall::AutoupdateVersion { name, version } => {
+                install_app_version_from_autoupdate(&name, &version, options).await
+            }
+        };
+        results.push((target, result));
+    }
+    results
+}
+
+#[cfg(test)]
+mod test_batch {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_parse_install_target() {
+        assert_eq!(
+            InstallTarget::parse("extras/vcredist2022").unwrap(),
+            InstallTarget::App(DependencySpec::parse("extras/vcredist2022").unwrap())
+        );
+        assert_eq!(
+            InstallTarget::parse("gh@2.7.0").unwrap(),
+            InstallTarget::Version {
+                name: "gh".into(),
+                version: "2.7.0".into()
+            }
+        );
+        assert_eq!(
+            InstallTarget::parse("https://example.com/app.exe").unwrap(),
+            InstallTarget::Url("https://example.com/app.exe".into())
+        );
+        assert!(InstallTarget::parse("main/gh@2.7.0").is_err());
+    }
+}
@@ -15,6 +15,8 @@ pub enum InstallOptions<'a> {
     InteractiveInstall,
     InstallSpecialVersionApp,
     InstallSpecialBucketApp, // 单元变体（无数据）
+    InstallAsDependency,
+    DryRun,
     CurrentInstallApp {
         app_name: String,
         app_version: String,
@@ -64,6 +66,17 @@ pub enum HashFormat {
     SHA512,
 }
 
+impl HashFormat {
+    pub fn algorithm(&self) -> hash::HashAlgorithm {
+        match self {
+            HashFormat::MD5 => hash::HashAlgorithm::Md5,
+            HashFormat::SHA1 => hash::HashAlgorithm::Sha1,
+            HashFormat::SHA256 => hash::HashAlgorithm::Sha256,
+            HashFormat::SHA512 => hash::HashAlgorithm::Sha512,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum DownloadState {
     Queued,
@@ -0,0 +1,576 @@
+use crate::config::get_config_value_no_print;
+use crate::init_env::{
+    get_app_current_dir, get_app_current_dir_global, get_app_dir_install_json,
+    get_app_dir_install_json_global, get_special_bucket_child_path,
+    get_special_bucket_child_path_global,
+};
+use crate::install::{install_app_from_local_manifest_file, InstallOptions};
+use crate::manifest::manifest::{
+    get_best_manifest_from_local_bucket, get_best_manifest_from_local_bucket_global,
+};
+use crate::manifest::manifest_deserialize::ManifestObj;
+use anyhow::{bail, Context};
+use crossterm::style::Stylize;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// 只有 7zip 能解压的格式, zip/7z/tar/gz/xz/bz2/zst/nupkg 由 supports_native_extract 内置解压, 不需要 7zip
+const SEVEN_ZIP_PATTERN: &str = r"(?i)\.((t[ap]z2?)|(lzma)|(001)|(rar)|(iso)|(lzh))(\.[^\d.]+)?$";
+
+/// depends 中的条目, 支持 `app` 与 `bucket/app`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DependencySpec {
+    pub bucket: Option<String>,
+    pub name: String,
+}
+
+impl DependencySpec {
+    pub fn parse(spec: &str) -> anyhow::Result<Self> {
+        let spec = spec.trim();
+        match spec.split('/').collect::<Vec<_>>().as_slice() {
+            [name] if !name.is_empty() => Ok(Self {
+                bucket: None,
+                name: name.to_lowercase(),
+            }),
+            [bucket, name] if !bucket.is_empty() && !name.is_empty() => Ok(Self {
+                bucket: Some(bucket.to_lowercase()),
+                name: name.to_lowercase(),
+            }),
+            _ => bail!("manifest depends format error: '{spec}'"),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl std::fmt::Display for DependencySpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.bucket {
+            Some(bucket) => write!(f, "{}/{}", bucket, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DependencyNode {
+    pub name: String,
+    pub bucket: Option<String>,
+    pub manifest_path: PathBuf,
+    /// 显式 depends 与推断出的解压工具, 工具在前
+    pub depends: Vec<DependencySpec>,
+    pub installed: bool,
+}
+
+fn string_or_array(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::String(s)) => vec![s.clone()],
+        Some(Value::Array(arr)) => arr
+            .iter()
+            .filter_map(|item| item.as_str().map(|s| s.to_string()))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+pub fn get_manifest_depends(manifest: &ManifestObj) -> anyhow::Result<Vec<DependencySpec>> {
+    string_or_array(manifest.get("depends"))
+        .iter()
+        .map(|spec| DependencySpec::parse(spec))
+        .collect()
+}
+
+/// 根据下载链接类型, innosetup 以及脚本中的 Expand-* 调用推断所需的解压工具
+pub fn infer_helper_depends(
+    manifest: &ManifestObj,
+    arch: &str,
+    use_external_7zip: bool,
+) -> Vec<&'static str> {
+    let arch_block = manifest.get("architecture").and_then(|a| a.get(arch));
+    let blocks = [Some(manifest), arch_block];
+    let urls = blocks
+        .iter()
+        .flat_map(|block| string_or_array(block.and_then(|b| b.get("url"))))
+        .collect::<Vec<_>>();
+    let scripts = blocks
+        .iter()
+        .flat_map(|block| {
+            let block = block.and_then(|b| b.as_object());
+            ["pre_install", "post_install"]
+                .iter()
+                .flat_map(|key| string_or_array(block.and_then(|b| b.get(*key))))
+                .chain(string_or_array(
+                    block
+                        .and_then(|b| b.get("installer"))
+                        .and_then(|i| i.get("script")),
+                ))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let file_names = urls
+        .iter()
+        .map(|url| match url.split_once("#/") {
+            Some((_, name)) => name.to_string(),
+            None => url.split(['?', '#']).next().unwrap_or_default().to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    let seven_zip = Regex::new(SEVEN_ZIP_PATTERN).unwrap();
+    let mut helpers = vec![];
+    if !use_external_7zip
+        && (file_names.iter().any(|name| seven_zip.is_match(name))
+            || scripts.contains("Expand-7zipArchive"))
+    {
+        helpers.push("7zip");
+    }
+    if file_names
+        .iter()
+        .any(|name| name.to_lowercase().ends_with(".msi"))
+        || scripts.contains("Expand-MsiArchive")
+    {
+        helpers.push("lessmsi");
+    }
+    if manifest
+        .get("innosetup")
+        .is_some_and(|v| v == &Value::Bool(true))
+        || scripts.contains("Expand-InnoArchive")
+    {
+        helpers.push("innounp");
+    }
+    if scripts.contains("Expand-DarkArchive") {
+        helpers.push("dark");
+    }
+    helpers
+}
+
+impl DependencyNode {
+    pub fn from_manifest(
+        spec: &DependencySpec,
+        manifest_path: PathBuf,
+        manifest: &ManifestObj,
+        arch: &str,
+        use_external_7zip: bool,
+        installed: bool,
+    ) -> anyhow::Result<Self> {
+        let mut depends = infer_helper_depends(manifest, arch, use_external_7zip)
+            .into_iter()
+            .filter(|helper| *helper != spec.name)
+            .map(|helper| DependencySpec::parse(helper))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        for depend in get_manifest_depends(manifest)? {
+            if depend.name != spec.name && !depends.iter().any(|d| d.name == depend.name) {
+                depends.push(depend);
+            }
+        }
+        Ok(Self {
+            name: spec.name.clone(),
+            bucket: spec.bucket.clone(),
+            manifest_path,
+            depends,
+            installed,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VisitState {
+    Visiting,
+    Visited,
+}
+
+/// 以 App 名称(小写)为键的依赖图, 同名依赖只解析一次
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    pub nodes: HashMap<String, DependencyNode>,
+    pub roots: Vec<String>,
+}
+
+impl DependencyGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_root(&mut self, node: DependencyNode) {
+        let key = node.name.clone();
+        if !self.roots.contains(&key) {
+            self.roots.push(key.clone());
+        }
+        self.nodes.entry(key).or_insert(node);
+    }
+
+    /// 从根节点展开全部依赖, `expand_installed` 为 false 时不再展开已安装 App 的依赖
+    pub fn expand<F>(&mut self, expand_installed: bool, mut load: F) -> anyhow::Result<()>
+    where
+        F: FnMut(&DependencySpec) -> anyhow::Result<DependencyNode>,
+    {
+        let mut queue = self.roots.clone();
+        let mut expanded = HashSet::new();
+        while let Some(key) = queue.pop() {
+            if !expanded.insert(key.clone()) {
+                continue;
+            }
+            let node = &self.nodes[&key];
+            if node.installed && !expand_installed && !self.roots.contains(&key) {
+                continue;
+            }
+            for depend in node.depends.clone() {
+                if !self.nodes.contains_key(depend.key()) {
+                    let child = load(&depend).context(format!(
+                        "Failed to resolve dependency '{depend}' of '{key}'"
+                    ))?;
+                    self.nodes.insert(depend.key().to_string(), child);
+                }
+                queue.push(depend.key().to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// 依赖在前的拓扑序, 存在循环依赖时返回完整的依赖链
+    pub fn topological_order(&self) -> anyhow::Result<Vec<&DependencyNode>> {
+        let mut states = HashMap::new();
+        let mut stack = vec![];
+        let mut order = vec![];
+        for root in self.roots.iter() {
+            self.visit(root, &mut states, &mut stack, &mut order)?;
+        }
+        Ok(order)
+    }
+
+    fn visit<'a>(
+        &'a self,
+        key: &str,
+        states: &mut HashMap<String, VisitState>,
+        stack: &mut Vec<String>,
+        order: &mut Vec<&'a DependencyNode>,
+    ) -> anyhow::Result<()> {
+        match states.get(key) {
+            Some(VisitState::Visited) => return Ok(()),
+            Some(VisitState::Visiting) => {
+                let start = stack.iter().position(|k| k == key).unwrap_or(0);
+                let cycle = stack[start..]
+                    .iter()
+                    .map(|k| k.as_str())
+                    .chain([key])
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                bail!("Circular dependency detected: {cycle}")
+            }
+            None => {}
+        }
+        let Some(node) = self.nodes.get(key) else {
+            return Ok(());
+        };
+        states.insert(key.to_string(), VisitState::Visiting);
+        stack.push(key.to_string());
+        for depend in node.depends.iter() {
+            self.visit(depend.key(), states, stack, order)?;
+        }
+        stack.pop();
+        states.insert(key.to_string(), VisitState::Visited);
+        order.push(node);
+        Ok(())
+    }
+}
+
+pub fn is_app_installed(app_name: &str, global: bool) -> bool {
+    let current_dir = if global {
+        get_app_current_dir_global(app_name)
+    } else {
+        get_app_current_dir(app_name)
+    };
+    Path::new(&current_dir).exists()
+}
+
+/// 清单路径位于 `buckets/<bucket>/bucket/<app>.json`
+pub fn get_bucket_name_from_manifest_path(manifest_path: &Path) -> Option<String> {
+    manifest_path
+        .parent()
+        .and_then(|p| p.parent())
+        .and_then(|p| p.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+pub fn find_local_manifest(spec: &DependencySpec, global: bool) -> anyhow::Result<PathBuf> {
+    match &spec.bucket {
+        Some(bucket) => {
+            let bucket_dir = if global {
+                get_special_bucket_child_path_global(bucket)
+            } else {
+                get_special_bucket_child_path(bucket)
+            };
+            let manifest_path = Path::new(&bucket_dir).join(format!("{}.json", spec.name));
+            if !manifest_path.exists() {
+                bail!("No manifest found for '{}' in bucket '{bucket}'", spec.name)
+            }
+            Ok(manifest_path)
+        }
+        None if global => get_best_manifest_from_local_bucket_global(&spec.name)
+            .or_else(|_| get_best_manifest_from_local_bucket(&spec.name)),
+        None => get_best_manifest_from_local_bucket(&spec.name),
+    }
+}
+
+pub fn read_manifest_obj(manifest_path: &Path) -> anyhow::Result<ManifestObj> {
+    let content = std::fs::read_to_string(manifest_path).context(format!(
+        "Failed to read manifest {}",
+        manifest_path.display()
+    ))?;
+    serde_json::from_str(content.trim_start_matches('\u{feff}')).context(format!(
+        "Failed to parse manifest {}",
+        manifest_path.display()
+    ))
+}
+
+pub fn load_local_dependency_node(
+    spec: &DependencySpec,
+    arch: &str,
+    global: bool,
+) -> anyhow::Result<DependencyNode> {
+    let manifest_path = find_local_manifest(spec, global)?;
+    let manifest = read_manifest_obj(&manifest_path)?;
+    let spec = DependencySpec {
+        bucket: spec
+            .bucket
+            .clone()
+            .or_else(|| get_bucket_name_from_manifest_path(&manifest_path)),
+        name: spec.name.clone(),
+    };
+    DependencyNode::from_manifest(
+        &spec,
+        manifest_path,
+        &manifest,
+        arch,
+        use_external_7zip(),
+        is_app_installed(&spec.name, global),
+    )
+}
+
+pub(crate) fn use_external_7zip() -> bool {
+    get_config_value_no_print("use_external_7zip") == "true"
+}
+
+/// 记录在 install.json 的 `install_reason` 字段, 缺失时视为显式安装
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallReason {
+    Explicit,
+    Dependency,
+}
+
+impl InstallReason {
+    pub fn from_options(options: &[InstallOptions]) -> Self {
+        if options.contains(&InstallOptions::InstallAsDependency) {
+            InstallReason::Dependency
+        } else {
+            InstallReason::Explicit
+        }
+    }
+}
+
+fn get_install_json_path(app_name: &str, global: bool) -> String {
+    if global {
+        get_app_dir_install_json_global(app_name)
+    } else {
+        get_app_dir_install_json(app_name)
+    }
+}
+
+pub fn get_install_reason(app_name: &str, global: bool) -> InstallReason {
+    let install_json = get_install_json_path(app_name, global);
+    std::fs::read_to_string(install_json)
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|info| info.get("install_reason").cloned())
+        .and_then(|reason| serde_json::from_value(reason).ok())
+        .unwrap_or(InstallReason::Explicit)
+}
+
+pub fn set_install_reason(
+    app_name: &str,
+    global: bool,
+    reason: InstallReason,
+) -> anyhow::Result<()> {
+    let install_json = get_install_json_path(app_name, global);
+    let content =
+        std::fs::read_to_string(&install_json).context(format!("Failed to read {install_json}"))?;
+    let mut info: Value =
+        serde_json::from_str(&content).context(format!("Failed to parse {install_json}"))?;
+    let Some(obj) = info.as_object_mut() else {
+        bail!("Invalid install.json: {install_json}")
+    };
+    obj.insert("install_reason".into(), serde_json::to_value(reason)?);
+    std::fs::write(&install_json, serde_json::to_string_pretty(&info)?)
+        .context(format!("Failed to write {install_json}"))?;
+    Ok(())
+}
+
+/// 返回安装 `app_name` 之前需要按顺序安装的依赖, 不包含 App 本身与已安装的依赖
+pub fn resolve_install_plan(
+    manifest_path: &Path,
+    app_name: &str,
+    bucket: Option<&str>,
+    arch: &str,
+    options: &[InstallOptions],
+) -> anyhow::Result<Vec<DependencyNode>> {
+    let global = options.contains(&InstallOptions::Global);
+    let manifest = read_manifest_obj(manifest_path)?;
+    let spec = DependencySpec {
+        bucket: bucket.map(|b| b.to_string()),
+        name: app_name.to_lowercase(),
+    };
+    let root = DependencyNode::from_manifest(
+        &spec,
+        manifest_path.to_path_buf(),
+        &manifest,
+        arch,
+        use_external_7zip(),
+        false,
+    )?;
+    let mut graph = DependencyGraph::new();
+    graph.add_root(root);
+    graph.expand(false, |spec| load_local_dependency_node(spec, arch, global))?;
+    let plan = graph
+        .topological_order()?
+        .into_iter()
+        .filter(|node| node.name != spec.name && !node.installed)
+        .cloned()
+        .collect();
+    Ok(plan)
+}
+
+/// 依赖已由安装计划统一排序, 安装单个依赖时不再递归解析
+pub fn install_dependency_plan(
+    plan: &[DependencyNode],
+    options: &[InstallOptions],
+) -> anyhow::Result<()> {
+    if plan.is_empty() {
+        return Ok(());
+    }
+    let names = plan
+        .iter()
+        .map(|node| node.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    println!(
+        "{}",
+        format!("Installing dependencies: {names}")
+            .dark_cyan()
+            .bold()
+    );
+    let depend_options = options
+        .iter()
+        .filter(|option| {
+            !matches!(
+                option,
+                InstallOptions::CurrentInstallApp { .. }
+                    | InstallOptions::AppName(_)
+                    | InstallOptions::InstallSpecialVersionApp
+                    | InstallOptions::InstallSpecialBucketApp
+                    | InstallOptions::NoAutoDownloadDepends
+                    | InstallOptions::InstallAsDependency
+            )
+        })
+        .cloned()
+        .chain([
+            InstallOptions::NoAutoDownloadDepends,
+            InstallOptions::InstallAsDependency,
+        ])
+        .collect::<Vec<_>>();
+    for node in plan {
+        install_app_from_local_manifest_file(
+            &node.manifest_path,
+            depend_options.clone(),
+            node.bucket.as_deref(),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_depends {
+    #[allow(unused_imports)]
+    use super::*;
+
+    fn graph_from(manifests: &[(&str, Value)]) -> anyhow::Result<Vec<String>> {
+        let manifests = manifests
+            .iter()
+            .map(|(name, manifest)| (name.to_string(), manifest.clone()))
+            .collect::<HashMap<_, _>>();
+        let load = |spec: &DependencySpec| -> anyhow::Result<DependencyNode> {
+            let manifest = manifests
+                .get(&spec.name)
+                .context(format!("missing {}", spec.name))?;
+            DependencyNode::from_manifest(spec, PathBuf::new(), manifest, "64bit", false, false)
+        };
+        let mut graph = DependencyGraph::new();
+        graph.add_root(load(&DependencySpec::parse("app")?)?);
+        graph.expand(false, load)?;
+        Ok(graph
+            .topological_order()?
+            .into_iter()
+            .map(|node| node.name.clone())
+            .collect())
+    }
+
+    #[test]
+    fn test_topological_order_and_dedup() {
+        let order = graph_from(&[
+            ("app", serde_json::json!({ "depends": ["a", "extras/b"] })),
+            ("a", serde_json::json!({ "depends": "c" })),
+            ("b", serde_json::json!({ "depends": ["c"] })),
+            ("c", serde_json::json!({})),
+        ])
+        .unwrap();
+        assert_eq!(order, vec!["c", "a", "b", "app"]);
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let err = graph_from(&[
+            ("app", serde_json::json!({ "depends": "a" })),
+            ("a", serde_json::json!({ "depends": "b" })),
+            ("b", serde_json::json!({ "depends": "app" })),
+        ])
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Circular dependency detected: app -> a -> b -> app"));
+    }
+
+    #[test]
+    fn test_infer_helpers() {
+        let manifest = serde_json::json!({
+            "innosetup": true,
+            "architecture": {
+                "64bit": { "url": "https://example.com/app.msi" },
+                "32bit": { "url": "https://example.com/app.7z" }
+            },
+            "url": "https://example.com/dl?file=app#/app.rar",
+            "installer": { "script": "Expand-DarkArchive $dir\\setup.exe $dir" }
+        });
+        assert_eq!(
+            infer_helper_depends(&manifest, "64bit", false),
+            vec!["7zip", "lessmsi", "innounp", "dark"]
+        );
+        assert_eq!(
+            infer_helper_depends(&manifest, "64bit", true),
+            vec!["lessmsi", "innounp", "dark"]
+        );
+        for url in ["app.tar.gz", "app.7z", "app.nupkg", "app.tbz2"] {
+            let manifest = serde_json::json!({ "url": format!("https://example.com/{url}") });
+            assert!(infer_helper_depends(&manifest, "64bit", false).is_empty());
+        }
+        let manifest = serde_json::json!({ "url": "https://example.com/disk.iso" });
+        assert_eq!(
+            infer_helper_depends(&manifest, "64bit", false),
+            vec!["7zip"]
+        );
+    }
+}
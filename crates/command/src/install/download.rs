@@ -6,19 +6,24 @@ use crate::init_env::{
 use crate::install::InstallOptions::{
     ArchOptions, ForceDownloadNoInstallOverrideCache, Global, NoUseDownloadCache,
 };
-use crate::install::{ArchiveFormat, Aria2C, HashFormat, InstallOptions, SevenZipStruct};
+use crate::install::{
+    download_files_natively, load_url_rewrite_rules, rewrite_download_url, use_aria2_downloader,
+    ArchiveFormat, Aria2C, HashFormat, InstallOptions, InstallReason, SevenZipStruct,
+};
 use crate::manifest::install_manifest::InstallManifest;
 use crate::manifest::manifest_deserialize::{ArchitectureObject, StringArrayOrString};
-use crate::utils::system::{compute_hash_by_powershell, get_system_default_arch};
+use crate::offline::is_offline_mode;
+use crate::utils::system::get_system_default_arch;
 use crate::utils::utility::{assume_yes_to_cover_folder, get_parse_url_query, is_valid_url};
 use anyhow::{bail, Context};
 use crossterm::style::Stylize;
+use hash::{verify_files, ExpectedHash, HashJob};
 use hex;
-use sha1::Sha1;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use sha2::Digest;
-use sha2::{Sha256, Sha512};
+use sha2::Sha256;
 use std::borrow::Cow;
-use std::io::{Read, Write};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::vec;
 use windows_sys::Win32::System::Registry::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
@@ -40,6 +45,8 @@ pub struct DownloadManager<'a> {
     hash_value: Box<[String]>,
     input_file: String,
     download_urls: Box<[String]>,
+    /// 每个下载文件按顺序尝试的地址, 原始地址在最后
+    download_mirror_urls: Box<[Vec<String>]>,
     target_rename_alias: Box<[String]>,
     persist_data_dir: String,
     final_cache_file_path: Box<[String]>,
@@ -66,7 +73,8 @@ impl<'a> DownloadManager<'a> {
         };
         let install_json = serde_json::json!({
             "architecture": arch ,
-            "bucket": manifest
+            "bucket": manifest,
+            "install_reason": InstallReason::from_options(self.options)
         });
         let write_manifest_path = format!("{}\\manifest.json", current_dir);
         if Path::new(&install_json_path).exists() {
@@ -163,15 +171,15 @@ impl<'a> DownloadManager<'a> {
             let options_arch = self.get_user_options_arch()?;
             if options_arch == "64bit" {
                 let x64 = arch.x64bit.unwrap();
-                let hash = x64.hash.unwrap();
+                let hash = x64.hash.unwrap_or_default();
                 hash
             } else if options_arch == "32bit" {
                 let x86 = arch.x86bit.unwrap();
-                let hash = x86.hash.unwrap();
+                let hash = x86.hash.unwrap_or_default();
                 hash
             } else if options_arch == "arm64" {
                 let arm64 = arch.arm64.unwrap();
-                let hash = arm64.hash.unwrap();
+                let hash = arm64.hash.unwrap_or_default();
                 hash
             } else {
                 bail!("Unsupported architecture");
@@ -272,6 +280,12 @@ impl<'a> DownloadManager<'a> {
             })
             .collect::<Vec<_>>();
         self.download_urls = download_urls.to_vec().into_boxed_slice();
+        // 镜像只改变下载来源, 缓存文件名与哈希校验仍基于原始地址
+        let rules = load_url_rewrite_rules();
+        self.download_mirror_urls = download_urls
+            .iter()
+            .map(|url| rewrite_download_url(&rules, url))
+            .collect();
         let origin_files = download_urls
             .iter()
             .map(|url| {
@@ -290,6 +304,10 @@ impl<'a> DownloadManager<'a> {
         self.set_target_rename_alias(alias);
     }
 
+    pub fn get_download_mirror_urls(&self) -> &[Vec<String>] {
+        &self.download_mirror_urls
+    }
+
     pub fn check_is_no_special_char_url(&self, url: &str) -> bool {
         let special_chars = ['?', '&', '=', '#', '%'];
         for char in special_chars {
@@ -320,7 +338,12 @@ impl<'a> DownloadManager<'a> {
         let mut file = std::fs::File::create(self.get_input_file())
             .context("Failed to create input file at line 321")?;
         log::debug!("create input file {}", self.get_input_file());
-        let urls = self.get_download_urls();
+        // aria2 把同一行中 TAB 分隔的地址视为同一文件的镜像
+        let urls = self
+            .get_download_mirror_urls()
+            .iter()
+            .map(|urls| urls.join("\t"))
+            .collect::<Vec<_>>();
         let result =
             urls.iter()
                 .zip(self.get_cache_file_name())
@@ -458,7 +481,9 @@ impl<'a> DownloadManager<'a> {
             let url: StringArrayOrString = StringArrayOrString::String(manifest_path.to_string());
             self.set_cache_file_name(app_name, "remote_url", &url)?;
             self.set_final_cache_file_path()?;
-            self.create_input_file()?;
+            if !self.options.contains(&InstallOptions::DryRun) {
+                self.create_input_file()?;
+            }
             return Ok(());
         }
         let content = std::fs::read_to_string(manifest_path).context(format!(
@@ -479,7 +504,15 @@ impl<'a> DownloadManager<'a> {
 
         let hash = serde_obj.hash;
 
-        self.set_hash_format(hash, architecture.clone())?;
+        if let Err(e) = self.set_hash_format(hash, architecture.clone()) {
+            // 由 autoupdate 生成的清单可能没有 hash, 跳过校验时允许缺省
+            if !self
+                .options
+                .contains(&InstallOptions::SkipDownloadHashCheck)
+            {
+                return Err(e);
+            }
+        }
 
         let url = serde_obj.url;
         if url.is_some() {
@@ -515,11 +548,13 @@ impl<'a> DownloadManager<'a> {
         }
         self.set_app_current_dir();
         self.set_app_version_dir();
-        self.ensure_version_dir_exist()?;
-
         self.set_input_file();
         self.set_final_cache_file_path()?;
-        self.create_input_file()?;
+        // 生成执行计划时只解析下载信息, 不创建任何目录与文件
+        if !self.options.contains(&InstallOptions::DryRun) {
+            self.ensure_version_dir_exist()?;
+            self.create_input_file()?;
+        }
         Ok(())
     }
 
@@ -542,6 +577,7 @@ impl<'a> DownloadManager<'a> {
             hash_value: vec![].into(),
             input_file: "".into(),
             download_urls: vec![].into_boxed_slice(),
+            download_mirror_urls: Box::new([]),
             target_rename_alias: vec![].into_boxed_slice(),
             persist_data_dir: "".to_string(),
             final_cache_file_path: Box::new([]),
@@ -639,10 +675,10 @@ impl<'a> DownloadManager<'a> {
             .map(|extension| match extension.to_lowercase().as_str() {
                 "7z" => ArchiveFormat::SevenZip,
                 "zip" => ArchiveFormat::ZIP,
-                "gz" => ArchiveFormat::GZIP,
-                "xz" => ArchiveFormat::XZIP,
-                "bz2" => ArchiveFormat::BZIP2,
-                "zst" => ArchiveFormat::ZSTD,
+                "gz" | "tgz" => ArchiveFormat::GZIP,
+                "xz" | "txz" => ArchiveFormat::XZIP,
+                "bz2" | "tbz" | "tbz2" => ArchiveFormat::BZIP2,
+                "zst" | "tzst" => ArchiveFormat::ZSTD,
                 "rar" => ArchiveFormat::RAR,
                 "exe" => {
                     if self.exe_setup {
@@ -692,15 +728,11 @@ impl<'a> DownloadManager<'a> {
 
     pub fn start_download(&self) -> anyhow::Result<()> {
         self.ensure_install_dir_not_in_env_path()?;
-        let scoop_cache_dir = self.get_scoop_cache_dir();
         let input_file = self.get_input_file();
-        let mut aria2c = self.create_aria2c_instance();
-        log::info!("input  file: {}", input_file);
-        aria2c.set_input_file(input_file);
-        aria2c.set_scoop_cache_dir(scoop_cache_dir);
-        aria2c.set_download_urls(self.get_download_urls().as_slice());
-        if self.options.contains(&ForceDownloadNoInstallOverrideCache)
-            || self.options.contains(&NoUseDownloadCache)
+        // 离线模式下缓存是唯一来源, 不能删除
+        if (self.options.contains(&ForceDownloadNoInstallOverrideCache)
+            || self.options.contains(&NoUseDownloadCache))
+            && !is_offline_mode()
         {
             let cache_file_path = self
                 .get_cache_file_name()
@@ -759,7 +791,35 @@ impl<'a> DownloadManager<'a> {
             }
             return Ok(());
         }
+        if is_offline_mode() {
+            let missing = final_caches
+                .iter()
+                .filter(|path| !Path::new(path).exists())
+                .map(|path| format!("  {path}"))
+                .collect::<Vec<_>>();
+            bail!(
+                "Offline mode, missing cache files for '{}':\n{}",
+                self.app_name,
+                missing.join("\n")
+            )
+        }
         // !!only not exist cache file
+        if !use_aria2_downloader() {
+            let result = download_files_natively(
+                self.get_download_mirror_urls(),
+                &self.final_cache_file_path,
+            );
+            if Path::new(&input_file).exists() {
+                std::fs::remove_file(input_file)
+                    .context("failed to remove aria2 input file at line 751")?;
+            }
+            return result;
+        }
+        let mut aria2c = self.create_aria2c_instance();
+        log::info!("input  file: {}", input_file);
+        aria2c.set_input_file(input_file);
+        aria2c.set_scoop_cache_dir(self.get_scoop_cache_dir());
+        aria2c.set_download_urls(self.get_download_urls().as_slice());
         aria2c.init_aria2c_config()?;
 
         let output = aria2c.invoke_aria2c_download();
@@ -791,71 +851,54 @@ impl<'a> DownloadManager<'a> {
         let hash_formats = self.get_hash_format();
         let hash_values = self.get_hash_value();
         let origin_names = self.get_origin_cache_file_names();
-        let result = cache_files
+        let jobs = cache_files
             .iter()
             .zip(hash_formats)
             .zip(hash_values)
+            .map(|((file, format), hash_value)| HashJob {
+                path: PathBuf::from(file),
+                expected: ExpectedHash::new(format.algorithm(), hash_value),
+            })
+            .collect::<Vec<_>>();
+
+        // 流式并行校验, 大文件不再整体读入内存
+        let multi = MultiProgress::new();
+        let style = ProgressStyle::with_template(
+            "{prefix:.cyan.bold} [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} {msg}",
+        )
+        .unwrap()
+        .progress_chars("#>-");
+        let bars = jobs
+            .iter()
             .zip(origin_names)
-            .try_for_each(|(((file, format), hash_value), origin_name)| {
-                print!(
-                    "{} {}......",
+            .map(|(job, origin_name)| {
+                let bar = multi.add(ProgressBar::new(0));
+                bar.set_style(style.clone());
+                bar.set_prefix(format!("Checking hash of {origin_name}"));
+                bar.set_message(job.expected.algorithm.to_string());
+                bar
+            })
+            .collect::<Vec<_>>();
+        let results = verify_files(&jobs, |index, processed, total| {
+            let bar = &bars[index];
+            bar.set_length(total);
+            bar.set_position(processed);
+        });
+        bars.iter().for_each(|bar| bar.finish_and_clear());
+
+        let mut errors = vec![];
+        for (result, origin_name) in results.into_iter().zip(origin_names) {
+            match result {
+                Ok(()) => println!(
+                    "{} {}......✅",
                     "Checking hash of".dark_blue().bold(),
                     origin_name.to_string().dark_cyan().bold(),
-                );
-                std::io::stdout().flush().unwrap(); // 不刷新缓冲区会等待换行
-
-                let mut open_file = std::fs::File::open(file)
-                    .context(format!("failed to open cache file {file} at line 787"))?;
-                let mut buffer = vec![];
-                // let mut reader = std::io::BufReader::new(&open_file);
-                let caculate_hash = match format {
-                    HashFormat::SHA1 => {
-                        let mut hasher = Sha1::new();
-                        open_file.read_to_end(&mut buffer).unwrap();
-                        hasher.update(buffer.as_slice());
-                        let caculate_hash = hasher.finalize();
-
-                        let caculate_hash = hex::encode(caculate_hash);
-                        caculate_hash
-                    }
-                    HashFormat::SHA512 => {
-                        let mut hasher = Sha512::new();
-
-                        open_file.read_to_end(&mut buffer).unwrap();
-                        hasher.update(buffer.as_slice());
-                        let caculate_hash = hasher.finalize();
-                        let caculate_hash = hex::encode(caculate_hash);
-                        caculate_hash
-                    }
-                    HashFormat::SHA256 => {
-                        let mut hasher = Sha256::new();
-                        open_file.read_to_end(&mut buffer).unwrap();
-                        hasher.update(buffer.as_slice());
-                        let caculate_hash = hasher.finalize();
-                        let caculate_hash = hex::encode(caculate_hash);
-                        caculate_hash
-                    }
-                    HashFormat::MD5 => {
-                        let hash = compute_hash_by_powershell(file, "md5")?;
-                        hash
-                    }
-                };
-
-                if caculate_hash.to_lowercase() != *hash_value.to_lowercase() {
-                    bail!(
-                        "{} 文件哈希校验失败\n期望hash: {}\n实际hash: {}",
-                        file,
-                        hash_value,
-                        caculate_hash
-                    )
-                } else {
-                    println!("✅");
-                    Ok(())
-                }
-            }) as anyhow::Result<()>;
-
-        if result.is_err() {
-            bail!("{}", result.unwrap_err())
+                ),
+                Err(e) => errors.push(format!("{e:#}")),
+            }
+        }
+        if !errors.is_empty() {
+            bail!("{}", errors.join("\n"))
         }
         Ok(())
     }
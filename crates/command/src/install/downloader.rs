@@ -0,0 +1,662 @@
+use crate::checkver::request_client_builder;
+use crate::config::get_config_value_no_print;
+use crate::install::DownloadState;
+use anyhow::{bail, Context};
+use crossterm::style::Stylize;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use reqwest::header::{ACCEPT_RANGES, CONTENT_LENGTH, RANGE};
+use reqwest::{Client, StatusCode};
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+
+/// 同时下载的文件数
+const DEFAULT_CONCURRENCY: usize = 4;
+/// 超过该大小且服务器支持 Range 时分段下载
+const DEFAULT_SPLIT_THRESHOLD: u64 = 20 * 1024 * 1024;
+const DEFAULT_SPLIT_COUNT: u64 = 8;
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+pub type DownloadProgressCallback = Arc<dyn Fn(&DownloadTask, &DownloadState) + Send + Sync>;
+
+/// 配置 `aria2-enabled` 为 true 时使用 aria2c, 否则使用内置下载器
+pub(crate) fn use_aria2_downloader() -> bool {
+    get_config_value_no_print("aria2-enabled") == "true"
+}
+
+/// 拆分 `url#/rename.ext`, 返回实际请求的 URL 与重命名后的文件名
+pub fn split_rename_fragment(url: &str) -> (&str, Option<&str>) {
+    match url.split_once("#/") {
+        Some((url, rename)) if !rename.trim().is_empty() => (url, Some(rename.trim())),
+        Some((url, _)) => (url, None),
+        None => (url, None),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadTask {
+    pub url: String,
+    /// url 重试失败后按顺序尝试的镜像地址
+    pub fallback_urls: Vec<String>,
+    pub file_path: PathBuf,
+}
+
+impl DownloadTask {
+    /// 下载到指定文件, URL 中的 `#/rename.ext` 片段不会发送给服务器
+    pub fn new(url: &str, file_path: impl Into<PathBuf>) -> Self {
+        let (url, _) = split_rename_fragment(url.trim());
+        Self {
+            url: url.to_string(),
+            fallback_urls: vec![],
+            file_path: file_path.into(),
+        }
+    }
+
+    pub fn with_fallbacks(mut self, urls: &[String]) -> Self {
+        self.fallback_urls = urls
+            .iter()
+            .map(|url| split_rename_fragment(url.trim()).0.to_string())
+            .collect();
+        self
+    }
+
+    fn sources(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.url.as_str()).chain(self.fallback_urls.iter().map(String::as_str))
+    }
+
+    /// 下载到目录, 文件名优先使用 `#/rename.ext` 片段, 否则取 URL 最后一段
+    pub fn into_dir(url: &str, dir: &Path) -> Self {
+        let (request_url, rename) = split_rename_fragment(url.trim());
+        let file_name = rename.map(|rename| rename.to_string()).unwrap_or_else(|| {
+            let path = request_url.split(['?', '#']).next().unwrap_or(request_url);
+            path.trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        });
+        Self::new(request_url, dir.join(file_name))
+    }
+
+    pub fn file_name(&self) -> String {
+        self.file_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.url.clone())
+    }
+
+    /// 未完成的下载先写入该文件, 下次下载时从已有长度继续
+    fn partial_path(&self) -> PathBuf {
+        let mut path = self.file_path.clone().into_os_string();
+        path.push(".download");
+        PathBuf::from(path)
+    }
+
+    fn segment_path(&self, index: usize) -> PathBuf {
+        let mut path = self.partial_path().into_os_string();
+        path.push(format!(".{index}"));
+        PathBuf::from(path)
+    }
+
+    fn remove_partial_files(&self) {
+        let partial = self.partial_path();
+        let (Some(dir), Some(name)) = (partial.parent(), partial.file_name()) else {
+            return;
+        };
+        let name = name.to_string_lossy().to_string();
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&name))
+            .for_each(|entry| {
+                let _ = std::fs::remove_file(entry.path());
+            });
+    }
+}
+
+/// 把 `[0, total)` 均分为最多 count 段闭区间
+pub fn split_ranges(total: u64, count: u64) -> Vec<(u64, u64)> {
+    if total == 0 {
+        return vec![];
+    }
+    let count = count.clamp(1, total);
+    let chunk = total.div_ceil(count);
+    (0..count)
+        .map(|index| index * chunk)
+        .take_while(|start| *start < total)
+        .map(|start| (start, (start + chunk).min(total) - 1))
+        .collect()
+}
+
+fn file_len(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0)
+}
+
+/// 汇总单个文件所有分段的进度, 按固定间隔回调
+struct TransferProgress<'a> {
+    task: &'a DownloadTask,
+    total: Option<u64>,
+    downloaded: AtomicU64,
+    started: Instant,
+    last_report: Mutex<Instant>,
+    callback: &'a DownloadProgressCallback,
+}
+
+impl<'a> TransferProgress<'a> {
+    fn new(
+        task: &'a DownloadTask,
+        total: Option<u64>,
+        callback: &'a DownloadProgressCallback,
+    ) -> Self {
+        Self {
+            task,
+            total,
+            downloaded: AtomicU64::new(0),
+            started: Instant::now(),
+            last_report: Mutex::new(Instant::now() - PROGRESS_INTERVAL),
+            callback,
+        }
+    }
+
+    fn reset(&self, downloaded: u64) {
+        self.downloaded.store(downloaded, Ordering::Relaxed);
+    }
+
+    fn add(&self, amount: u64) {
+        let downloaded = self.downloaded.fetch_add(amount, Ordering::Relaxed) + amount;
+        let mut last_report = self.last_report.lock().unwrap();
+        if last_report.elapsed() < PROGRESS_INTERVAL {
+            return;
+        }
+        *last_report = Instant::now();
+        let elapsed = self.started.elapsed().as_secs_f64().max(0.001);
+        let progress = match self.total {
+            Some(total) if total > 0 => downloaded as f64 / total as f64 * 100.0,
+            _ => 0.0,
+        };
+        let state = DownloadState::Downloading {
+            progress,
+            speed: downloaded as f64 / elapsed,
+        };
+        (self.callback)(self.task, &state);
+    }
+}
+
+/// 基于 reqwest 的下载器, 支持多文件并发, 大文件分段, 断点续传与失败重试
+pub struct NativeDownloader {
+    client: Client,
+    concurrency: usize,
+    split_threshold: u64,
+    split_count: u64,
+    max_retries: u32,
+    callback: DownloadProgressCallback,
+}
+
+impl NativeDownloader {
+    pub fn new() -> anyhow::Result<Self> {
+        let client = request_client_builder(&get_scoop_user_agent())?
+            .connect_timeout(Duration::from_secs(15))
+            .build()?;
+        Ok(Self {
+            client,
+            concurrency: DEFAULT_CONCURRENCY,
+            split_threshold: DEFAULT_SPLIT_THRESHOLD,
+            split_count: DEFAULT_SPLIT_COUNT,
+            max_retries: DEFAULT_MAX_RETRIES,
+            callback: Arc::new(|_, _| {}),
+        })
+    }
+
+    pub fn set_concurrency(&mut self, concurrency: usize) {
+        self.concurrency = concurrency.max(1);
+    }
+
+    pub fn set_split(&mut self, threshold: u64, count: u64) {
+        self.split_threshold = threshold;
+        self.split_count = count.max(1);
+    }
+
+    pub fn set_max_retries(&mut self, max_retries: u32) {
+        self.max_retries = max_retries;
+    }
+
+    pub fn set_progress_callback(&mut self, callback: DownloadProgressCallback) {
+        self.callback = callback;
+    }
+
+    /// 在终端为每个文件显示一个进度条
+    pub fn with_progress_bars(mut self) -> Self {
+        let multi = MultiProgress::new();
+        let bars = Mutex::new(HashMap::<PathBuf, ProgressBar>::new());
+        let style = ProgressStyle::with_template(
+            "{prefix:.cyan.bold} [{wide_bar:.cyan/blue}] {percent:>3}% {msg}",
+        )
+        .unwrap()
+        .progress_chars("#>-");
+        self.callback = Arc::new(move |task, state| {
+            let mut bars = bars.lock().unwrap();
+            let bar = bars.entry(task.file_path.clone()).or_insert_with(|| {
+                let bar = multi.add(ProgressBar::new(100));
+                bar.set_style(style.clone());
+                bar.set_prefix(task.file_name());
+                bar
+            });
+            match state {
+                DownloadState::Queued => bar.set_message("queued"),
+                DownloadState::Downloading { progress, speed } => {
+                    bar.set_position(*progress as u64);
+                    bar.set_message(format!("{:.2} MB/s", speed / 1024.0 / 1024.0));
+                }
+                DownloadState::Paused => bar.set_message("retrying"),
+                DownloadState::Completed(_) => {
+                    bar.set_position(100);
+                    bar.finish_with_message("✅");
+                }
+                DownloadState::Failed(e) => bar.abandon_with_message(format!("❌ {e}")),
+            }
+        });
+        self
+    }
+
+    /// 并发下载全部任务, 全部完成后把所有失败的文件汇总为一个错误
+    pub async fn download_all(&self, tasks: &[DownloadTask]) -> anyhow::Result<()> {
+        tasks
+            .iter()
+            .for_each(|task| (self.callback)(task, &DownloadState::Queued));
+        let results = stream::iter(tasks)
+            .map(|task| async move { (task, self.download_with_retry(task).await) })
+            .buffer_unordered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+        let mut failed = vec![];
+        for (task, result) in results {
+            match result {
+                Ok(()) => {
+                    let path = task.file_path.to_string_lossy().to_string();
+                    (self.callback)(task, &DownloadState::Completed(path));
+                }
+                Err(e) => {
+                    (self.callback)(task, &DownloadState::Failed(format!("{e:#}")));
+                    failed.push(format!("{}: {e:#}", task.url));
+                }
+            }
+        }
+        if !failed.is_empty() {
+            bail!("Download failed:\n{}", failed.join("\n"))
+        }
+        Ok(())
+    }
+
+    /// 同步调用入口, 在独立线程的单线程运行时中执行, 可在 tokio 上下文与 rayon 线程中调用.
+    /// 并发由 download_all 内的 stream 驱动, 不需要多线程运行时
+    pub fn download_all_blocking(&self, tasks: &[DownloadTask]) -> anyhow::Result<()> {
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()
+                        .context("Failed to build download runtime")?
+                        .block_on(self.download_all(tasks))
+                })
+                .join()
+                .unwrap_or_else(|_| bail!("Download thread panicked"))
+        })
+    }
+
+    async fn download_with_retry(&self, task: &DownloadTask) -> anyhow::Result<()> {
+        if let Some(parent) = task.file_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .context(format!("Failed to create directory {}", parent.display()))?;
+        }
+        let mut last_error = None;
+        for (index, url) in task.sources().enumerate() {
+            if index > 0 {
+                // 不同来源的分段不一定一致, 切换镜像时丢弃未完成的数据
+                log::warn!("Switch to fallback {url} for {}", task.file_name());
+                task.remove_partial_files();
+            }
+            let mut attempt = 0;
+            loop {
+                match self.download_once(task, url).await {
+                    Ok(()) => return Ok(()),
+                    Err(e) if attempt < self.max_retries => {
+                        attempt += 1;
+                        log::warn!("Download {url} failed, retry {attempt}: {e:#}");
+                        (self.callback)(task, &DownloadState::Paused);
+                        tokio::time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
+                    }
+                    Err(e) => {
+                        last_error = Some(e.context(format!("Failed to download {url}")));
+                        break;
+                    }
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("No download url")))
+    }
+
+    async fn download_once(&self, task: &DownloadTask, url: &str) -> anyhow::Result<()> {
+        let (total, accept_ranges) = self.probe(url).await;
+        let progress = TransferProgress::new(task, total, &self.callback);
+        match total {
+            Some(total)
+                if accept_ranges && total >= self.split_threshold && self.split_count > 1 =>
+            {
+                self.download_segments(task, url, total, &progress).await?
+            }
+            _ => {
+                self.download_single(task, url, total, accept_ranges, &progress)
+                    .await?
+            }
+        }
+        tokio::fs::rename(task.partial_path(), &task.file_path)
+            .await
+            .context(format!(
+                "Failed to move download to {}",
+                task.file_path.display()
+            ))?;
+        Ok(())
+    }
+
+    /// HEAD 请求获取文件大小与是否支持 Range, 失败时按未知处理
+    async fn probe(&self, url: &str) -> (Option<u64>, bool) {
+        let Ok(response) = self.client.head(url).send().await else {
+            return (None, false);
+        };
+        if !response.status().is_success() {
+            return (None, false);
+        }
+        let headers = response.headers();
+        let total = headers
+            .get(CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok())
+            .and_then(|length| length.parse::<u64>().ok())
+            .filter(|length| *length > 0);
+        let accept_ranges = headers
+            .get(ACCEPT_RANGES)
+            .and_then(|ranges| ranges.to_str().ok())
+            .is_some_and(|ranges| ranges.eq_ignore_ascii_case("bytes"));
+        (total, accept_ranges)
+    }
+
+    async fn download_single(
+        &self,
+        task: &DownloadTask,
+        url: &str,
+        total: Option<u64>,
+        accept_ranges: bool,
+        progress: &TransferProgress<'_>,
+    ) -> anyhow::Result<()> {
+        let partial = task.partial_path();
+        let mut existing = if accept_ranges { file_len(&partial) } else { 0 };
+        if total.is_some_and(|total| existing > total) {
+            existing = 0;
+        }
+        if total.is_some_and(|total| existing == total) {
+            return Ok(());
+        }
+        let mut request = self.client.get(url);
+        if existing > 0 {
+            request = request.header(RANGE, format!("bytes={existing}-"));
+        }
+        let response = request.send().await?.error_for_status()?;
+        // 服务器忽略 Range 时从头下载
+        if response.status() != StatusCode::PARTIAL_CONTENT {
+            existing = 0;
+        }
+        progress.reset(existing);
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(existing > 0)
+            .truncate(existing == 0)
+            .open(&partial)
+            .await
+            .context(format!("Failed to open {}", partial.display()))?;
+        write_response(response, file, progress).await?;
+        let downloaded = file_len(&partial);
+        if let Some(total) = total {
+            if downloaded != total {
+                bail!("Incomplete download, expected {total} bytes, got {downloaded}")
+            }
+        }
+        Ok(())
+    }
+
+    /// 每段写入独立的分段文件, 各段可单独续传, 全部完成后按顺序合并
+    async fn download_segments(
+        &self,
+        task: &DownloadTask,
+        url: &str,
+        total: u64,
+        progress: &TransferProgress<'_>,
+    ) -> anyhow::Result<()> {
+        let ranges = split_ranges(total, self.split_count);
+        let existing = ranges
+            .iter()
+            .enumerate()
+            .map(|(index, (start, end))| file_len(&task.segment_path(index)).min(end - start + 1))
+            .sum();
+        progress.reset(existing);
+        stream::iter(ranges.iter().enumerate())
+            .map(Ok)
+            .try_for_each_concurrent(None, |(index, (start, end))| async move {
+                let path = task.segment_path(index);
+                let expected = end - start + 1;
+                let mut existing = file_len(&path);
+                if existing > expected {
+                    existing = 0;
+                }
+                if existing == expected {
+                    return Ok(());
+                }
+                let response = self
+                    .client
+                    .get(url)
+                    .header(RANGE, format!("bytes={}-{end}", start + existing))
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                if response.status() != StatusCode::PARTIAL_CONTENT {
+                    bail!("Server ignored range request for {url}")
+                }
+                let file = tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .append(existing > 0)
+                    .truncate(existing == 0)
+                    .open(&path)
+                    .await
+                    .context(format!("Failed to open {}", path.display()))?;
+                write_response(response, file, progress).await?;
+                if file_len(&path) != expected {
+                    bail!("Incomplete segment {index} of {url}")
+                }
+                Ok(())
+            })
+            .await?;
+
+        let partial = task.partial_path();
+        let mut output = tokio::fs::File::create(&partial)
+            .await
+            .context(format!("Failed to create {}", partial.display()))?;
+        for index in 0..ranges.len() {
+            let path = task.segment_path(index);
+            let mut segment = tokio::fs::File::open(&path).await?;
+            tokio::io::copy(&mut segment, &mut output).await?;
+            drop(segment);
+            tokio::fs::remove_file(&path).await?;
+        }
+        output.flush().await?;
+        Ok(())
+    }
+}
+
+async fn write_response(
+    mut response: reqwest::Response,
+    mut file: tokio::fs::File,
+    progress: &TransferProgress<'_>,
+) -> anyhow::Result<()> {
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+        progress.add(chunk.len() as u64);
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+pub fn get_scoop_user_agent() -> String {
+    let os_info = os_info::get();
+    let os_version = os_info.version().to_string();
+    // 检测系统架构
+    let arch = env::consts::ARCH;
+    let mut arch_info = String::new();
+
+    // 检查是否是 ARM64
+    if cfg!(target_arch = "aarch64") {
+        arch_info.push_str("ARM64; ");
+    }
+    // 检查是否是 AMD64 (x86_64)
+    else if arch == "x86_64" {
+        arch_info.push_str("Win64; x64; ");
+    }
+
+    // 检查是否运行在 WOW64 模式下（32位程序在64位系统）
+    if let Ok(program_files_arm) = env::var("ProgramFiles(Arm)") {
+        if !program_files_arm.is_empty() {
+            arch_info.push_str("WOW64; ");
+        }
+    }
+
+    format!(
+        "Scoop/1.0 (+http://scoop.sh/) Rust/{} (Windows NT {}; {}){}",
+        env!("CARGO_PKG_VERSION"),
+        os_version,
+        arch_info,
+        if cfg!(windows) { "Windows" } else { "" }
+    )
+}
+
+/// 使用内置下载器下载到缓存文件, 失败的文件保留 `.download` 以便下次续传
+pub fn download_files_natively(
+    mirror_urls: &[Vec<String>],
+    cache_files: &[String],
+) -> anyhow::Result<()> {
+    println!(
+        "{}",
+        "Starting Native Download Files......".dark_blue().bold()
+    );
+    let tasks = mirror_urls
+        .iter()
+        .zip(cache_files)
+        .filter_map(|(urls, cache_file)| {
+            let (url, fallbacks) = urls.split_first()?;
+            Some(DownloadTask::new(url, cache_file).with_fallbacks(fallbacks))
+        })
+        .collect::<Vec<_>>();
+    NativeDownloader::new()?
+        .with_progress_bars()
+        .download_all_blocking(&tasks)
+}
+
+#[cfg(test)]
+mod test_downloader {
+    #[allow(unused_imports)]
+    use super::*;
+    use crate::test_util::{serve_http, test_temp_dir};
+
+    /// 支持单区间 Range 请求的文件服务
+    fn serve_file(body: &'static [u8]) -> String {
+        serve_http(move |request| {
+            let range = request
+                .lines()
+                .find_map(|line| line.strip_prefix("range: bytes="))
+                .map(|range| {
+                    let (start, end) = range.trim().split_once('-').unwrap();
+                    let start = start.parse::<usize>().unwrap();
+                    let end = end.parse::<usize>().unwrap_or(body.len() - 1);
+                    (start, end)
+                });
+            match range {
+                Some((start, end)) => ("206 Partial Content", body[start..=end].to_vec()),
+                None => ("200 OK", body.to_vec()),
+            }
+        })
+    }
+
+    #[test]
+    fn test_split_rename_fragment() {
+        assert_eq!(
+            split_rename_fragment("https://example.com/setup.exe#/dl.7z"),
+            ("https://example.com/setup.exe", Some("dl.7z"))
+        );
+        assert_eq!(
+            split_rename_fragment("https://example.com/a.zip"),
+            ("https://example.com/a.zip", None)
+        );
+        let task =
+            DownloadTask::into_dir("https://example.com/setup.exe#/dl.7z", Path::new("cache"));
+        assert_eq!(task.url, "https://example.com/setup.exe");
+        assert_eq!(task.file_name(), "dl.7z");
+        let task = DownloadTask::into_dir("https://example.com/a.zip?x=1", Path::new("cache"));
+        assert_eq!(task.file_name(), "a.zip");
+    }
+
+    #[test]
+    fn test_split_ranges() {
+        assert_eq!(split_ranges(10, 3), vec![(0, 3), (4, 7), (8, 9)]);
+        assert_eq!(split_ranges(2, 8), vec![(0, 0), (1, 1)]);
+        assert!(split_ranges(0, 4).is_empty());
+    }
+
+    #[test]
+    fn test_native_download_split_and_resume() {
+        const BODY: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+        let url = serve_file(BODY);
+        let dir = test_temp_dir("downloader");
+
+        let split = DownloadTask::new(
+            &format!("{url}/split.bin#/renamed.bin"),
+            dir.join("split.bin"),
+        );
+        let resumed = DownloadTask::new(&format!("{url}/resume.bin"), dir.join("resume.bin"));
+        std::fs::write(resumed.partial_path(), &BODY[..10]).unwrap();
+
+        let mut downloader = NativeDownloader::new().unwrap();
+        downloader.set_max_retries(0);
+        downloader.set_split(u64::MAX, 4);
+        downloader
+            .download_all_blocking(std::slice::from_ref(&resumed))
+            .unwrap();
+        downloader.set_split(16, 4);
+        downloader
+            .download_all_blocking(std::slice::from_ref(&split))
+            .unwrap();
+
+        let fallback = DownloadTask::new(
+            "http://127.0.0.1:1/unreachable.bin",
+            dir.join("fallback.bin"),
+        )
+        .with_fallbacks(&[format!("{url}/fallback.bin")]);
+        downloader
+            .download_all_blocking(std::slice::from_ref(&fallback))
+            .unwrap();
+
+        assert_eq!(std::fs::read(&fallback.file_path).unwrap(), BODY);
+        assert_eq!(std::fs::read(&split.file_path).unwrap(), BODY);
+        assert_eq!(std::fs::read(&resumed.file_path).unwrap(), BODY);
+        assert!(!split.partial_path().exists());
+        assert!(!split.segment_path(0).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,5 +1,5 @@
 use crate::init_env::{get_old_scoop_dir, get_scoop_cfg_path, init_scoop_global, init_user_scoop};
-use crate::install::InstallOptions;
+use crate::install::{journal_env_var, InstallOptions};
 use crate::manifest::install_manifest::InstallManifest;
 use crate::manifest::manifest_deserialize::{ManifestObj, StringArrayOrString};
 use crate::utils::system::{set_global_env_var, set_user_env_var};
@@ -72,6 +72,7 @@ pub fn handle_env_set(
                 )
             };
 
+            journal_env_var(&key, options.contains(&InstallOptions::Global))?;
             let output = Command::new("powershell")
                 .arg("-NoProfile")
                 .arg("-Command")
@@ -158,6 +159,7 @@ pub fn add_bin_to_path(
     }; 
     
     log::debug!("\n 更新后的用户的 PATH: {}", user_path);
+    journal_env_var("Path", options.contains(&InstallOptions::Global))?;
     let script =
         format!(r#"[System.Environment]::SetEnvironmentVariable("PATH","{user_path}", "Machine")"#);
 
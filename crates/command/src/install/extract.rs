@@ -0,0 +1,349 @@
+use crate::install::ArchiveFormat;
+use crate::utils::safe_check::unsafe_relative_path_reason;
+use anyhow::{bail, Context};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+
+/// 内置解压器支持的格式, 其余格式(rar 等)仍交给 7z
+pub fn supports_native_extract(format: &ArchiveFormat) -> bool {
+    matches!(
+        format,
+        ArchiveFormat::ZIP
+            | ArchiveFormat::NUPKG
+            | ArchiveFormat::SevenZip
+            | ArchiveFormat::TAR
+            | ArchiveFormat::GZIP
+            | ArchiveFormat::XZIP
+            | ArchiveFormat::BZIP2
+            | ArchiveFormat::ZSTD
+    )
+}
+
+/// 不调用 7z.exe 直接解压到 target_dir, archive_name 为原始文件名, 用于识别 `.tar.gz` 这类两段式压缩包
+pub fn extract_archive_natively(
+    archive_path: &Path,
+    archive_name: &str,
+    format: &ArchiveFormat,
+    target_dir: &Path,
+) -> anyhow::Result<()> {
+    std::fs::create_dir_all(target_dir)
+        .context(format!("Failed to create {}", target_dir.display()))?;
+    match format {
+        ArchiveFormat::ZIP => extract_zip(archive_path, target_dir),
+        ArchiveFormat::NUPKG => extract_nupkg(archive_path, target_dir),
+        ArchiveFormat::SevenZip => extract_7z(archive_path, target_dir),
+        ArchiveFormat::TAR => {
+            let file = open_archive(archive_path)?;
+            unpack_tar(BufReader::new(file), target_dir)
+        }
+        ArchiveFormat::GZIP | ArchiveFormat::XZIP | ArchiveFormat::BZIP2 | ArchiveFormat::ZSTD => {
+            let decoder = decompress_reader(archive_path, format)?;
+            if is_compressed_tar(archive_name) {
+                unpack_tar(decoder, target_dir)
+            } else {
+                // 单文件压缩, 与 7z 一样输出去掉压缩后缀的文件
+                let output = entry_output_path(target_dir, decompressed_file_name(archive_name))?;
+                write_entry(decoder, &output)
+            }
+        }
+        _ => bail!("{archive_name} can not be extracted natively"),
+    }
+    .context(format!("Failed to extract {}", archive_path.display()))
+}
+
+fn open_archive(path: &Path) -> anyhow::Result<File> {
+    File::open(path).context(format!("Failed to open archive {}", path.display()))
+}
+
+fn decompress_reader(path: &Path, format: &ArchiveFormat) -> anyhow::Result<Box<dyn Read>> {
+    let file = BufReader::new(open_archive(path)?);
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::GZIP => Box::new(flate2::read::MultiGzDecoder::new(file)),
+        ArchiveFormat::XZIP => Box::new(xz2::read::XzDecoder::new_multi_decoder(file)),
+        ArchiveFormat::BZIP2 => Box::new(bzip2::read::MultiBzDecoder::new(file)),
+        ArchiveFormat::ZSTD => Box::new(zstd::stream::read::Decoder::with_buffer(file)?),
+        _ => bail!("{format:?} is not a compression format"),
+    };
+    Ok(reader)
+}
+
+fn is_compressed_tar(archive_name: &str) -> bool {
+    let name = archive_name.to_lowercase();
+    let Some((stem, extension)) = name.rsplit_once('.') else {
+        return false;
+    };
+    matches!(extension, "tgz" | "txz" | "tbz" | "tbz2" | "tzst") || stem.ends_with(".tar")
+}
+
+fn decompressed_file_name(archive_name: &str) -> &str {
+    match archive_name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => archive_name,
+    }
+}
+
+/// 压缩包内的条目路径映射到解压目录下, 拒绝任何可能写到解压目录之外的条目(zip-slip)
+fn entry_output_path(target_dir: &Path, entry_name: &str) -> anyhow::Result<PathBuf> {
+    if let Some(reason) = unsafe_relative_path_reason(entry_name) {
+        bail!("Archive entry '{entry_name}' escapes the extract directory: {reason}")
+    }
+    let entry_name = entry_name.replace('\\', "/");
+    let mut output = target_dir.to_path_buf();
+    for component in Path::new(&entry_name).components() {
+        match component {
+            Component::Normal(name) => output.push(name),
+            Component::CurDir => {}
+            _ => bail!("Archive entry '{entry_name}' escapes the extract directory"),
+        }
+    }
+    Ok(output)
+}
+
+fn write_entry(mut reader: impl Read, output: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)
+            .context(format!("Failed to create {}", parent.display()))?;
+    }
+    // *!与 7z -aoa 一致, 自动覆盖同名文件
+    let mut file =
+        File::create(output).context(format!("Failed to create {}", output.display()))?;
+    std::io::copy(&mut reader, &mut file)
+        .context(format!("Failed to write {}", output.display()))?;
+    Ok(())
+}
+
+fn extract_zip(archive_path: &Path, target_dir: &Path) -> anyhow::Result<()> {
+    extract_zip_entries(archive_path, target_dir, |name| Some(name.to_string()))
+}
+
+/// nupkg 就是 zip, 去掉 NuGet 元数据后包根目录即为应用根目录, extract_dir 相对包根目录
+fn extract_nupkg(archive_path: &Path, target_dir: &Path) -> anyhow::Result<()> {
+    extract_zip_entries(archive_path, target_dir, |name| {
+        if is_nupkg_metadata(name) {
+            return None;
+        }
+        // NuGet 打包时会对文件名做 URI 转义, 如空格为 %20
+        Some(
+            percent_encoding::percent_decode_str(name)
+                .decode_utf8_lossy()
+                .to_string(),
+        )
+    })
+}
+
+fn is_nupkg_metadata(entry_name: &str) -> bool {
+    let name = entry_name.replace('\\', "/").to_lowercase();
+    let name = name.trim_start_matches('/');
+    name.starts_with("_rels/")
+        || name.starts_with("package/")
+        || name == "[content_types].xml"
+        || (!name.contains('/') && name.ends_with(".nuspec"))
+}
+
+/// map_name 返回 None 的条目不解压
+fn extract_zip_entries(
+    archive_path: &Path,
+    target_dir: &Path,
+    map_name: impl Fn(&str) -> Option<String>,
+) -> anyhow::Result<()> {
+    let file = BufReader::new(open_archive(archive_path)?);
+    let mut archive = zip::ZipArchive::new(file)?;
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index)?;
+        let Some(entry_name) = map_name(entry.name()) else {
+            continue;
+        };
+        let output = entry_output_path(target_dir, &entry_name)?;
+        if entry.is_dir() {
+            std::fs::create_dir_all(&output)
+                .context(format!("Failed to create {}", output.display()))?;
+        } else {
+            write_entry(entry, &output)?;
+        }
+    }
+    Ok(())
+}
+
+fn extract_7z(archive_path: &Path, target_dir: &Path) -> anyhow::Result<()> {
+    let mut archive =
+        sevenz_rust::SevenZReader::open(archive_path, sevenz_rust::Password::empty())?;
+    archive.for_each_entries(|entry, reader| {
+        let output = entry_output_path(target_dir, entry.name())
+            .map_err(|e| sevenz_rust::Error::other(format!("{e:#}")))?;
+        if entry.is_directory() {
+            std::fs::create_dir_all(&output)?;
+        } else {
+            write_entry(reader, &output)
+                .map_err(|e| sevenz_rust::Error::other(format!("{e:#}")))?;
+        }
+        Ok(true)
+    })?;
+    Ok(())
+}
+
+fn unpack_tar(reader: impl Read, target_dir: &Path) -> anyhow::Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_name = entry.path()?.to_string_lossy().to_string();
+        let output = entry_output_path(target_dir, &entry_name)?;
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => std::fs::create_dir_all(&output)
+                .context(format!("Failed to create {}", output.display()))?,
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                write_entry(&mut entry, &output)?
+            }
+            // 链接和设备文件在 Windows 上没有意义, 与 7z 一样跳过
+            _ => log::debug!("Skip tar entry {entry_name}"),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_extract {
+    #[allow(unused_imports)]
+    use super::*;
+    use crate::test_util::test_temp_dir;
+    use std::io::Write;
+
+    fn tar_bytes() -> Vec<u8> {
+        let mut builder = tar::Builder::new(vec![]);
+        let content = b"hello";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "app-1.0/bin/app.txt", &content[..])
+            .unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_extract_zip() {
+        let dir = test_temp_dir("extract_zip");
+        let archive = dir.join("app.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&archive).unwrap());
+        writer
+            .start_file(
+                "app-1.0/bin/app.txt",
+                zip::write::SimpleFileOptions::default(),
+            )
+            .unwrap();
+        writer.write_all(b"hello").unwrap();
+        writer.finish().unwrap();
+
+        let target = dir.join("out");
+        extract_archive_natively(&archive, "app.zip", &ArchiveFormat::ZIP, &target).unwrap();
+        let content = std::fs::read_to_string(target.join("app-1.0/bin/app.txt")).unwrap();
+        assert_eq!(content, "hello");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_extract_tar_gz() {
+        let dir = test_temp_dir("extract_tar_gz");
+        let archive = dir.join("app#1.0#abc.gz");
+        let mut encoder = flate2::write::GzEncoder::new(
+            File::create(&archive).unwrap(),
+            flate2::Compression::default(),
+        );
+        encoder.write_all(&tar_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let target = dir.join("out");
+        extract_archive_natively(&archive, "app-1.0.tar.gz", &ArchiveFormat::GZIP, &target)
+            .unwrap();
+        let content = std::fs::read_to_string(target.join("app-1.0/bin/app.txt")).unwrap();
+        assert_eq!(content, "hello");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_extract_7z() {
+        let dir = test_temp_dir("extract_7z");
+        let source = dir.join("source");
+        std::fs::create_dir_all(source.join("bin")).unwrap();
+        std::fs::write(source.join("bin/app.txt"), b"hello").unwrap();
+        let archive = dir.join("app.7z");
+        sevenz_rust::compress_to_path(&source, &archive).unwrap();
+
+        let target = dir.join("out");
+        extract_archive_natively(&archive, "app.7z", &ArchiveFormat::SevenZip, &target).unwrap();
+        let content = std::fs::read_to_string(target.join("bin/app.txt")).unwrap();
+        assert_eq!(content, "hello");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_decompress_single_file() {
+        let dir = test_temp_dir("extract_zst");
+        let archive = dir.join("app.exe.zst");
+        std::fs::write(&archive, zstd::encode_all(&b"binary"[..], 3).unwrap()).unwrap();
+
+        let target = dir.join("out");
+        extract_archive_natively(&archive, "app.exe.zst", &ArchiveFormat::ZSTD, &target).unwrap();
+        assert_eq!(std::fs::read(target.join("app.exe")).unwrap(), b"binary");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_extract_nupkg() {
+        let dir = test_temp_dir("extract_nupkg");
+        let archive = dir.join("tool.nupkg");
+        let mut writer = zip::ZipWriter::new(File::create(&archive).unwrap());
+        for name in [
+            "_rels/.rels",
+            "package/services/metadata/core-properties/1.psmdcp",
+            "[Content_Types].xml",
+            "tool.nuspec",
+            "tools/my%20tool.exe",
+        ] {
+            writer
+                .start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(b"nupkg").unwrap();
+        }
+        writer.finish().unwrap();
+
+        let target = dir.join("out");
+        extract_archive_natively(&archive, "tool.nupkg", &ArchiveFormat::NUPKG, &target).unwrap();
+        let entries = std::fs::read_dir(&target)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(entries, vec!["tools"]);
+        assert!(target.join("tools/my tool.exe").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_reject_zip_slip() {
+        let dir = test_temp_dir("extract_zip_slip");
+        let archive = dir.join("evil.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&archive).unwrap());
+        writer
+            .start_file("../../evil.txt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"evil").unwrap();
+        writer.finish().unwrap();
+
+        let target = dir.join("out");
+        let error = extract_archive_natively(&archive, "evil.zip", &ArchiveFormat::ZIP, &target)
+            .unwrap_err();
+        assert!(format!("{error:#}").contains("escapes the extract directory"));
+        assert!(!dir.parent().unwrap().join("evil.txt").exists());
+        assert!(entry_output_path(&target, "C:\\Windows\\evil.dll").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_is_compressed_tar() {
+        assert!(is_compressed_tar("node-v20.tar.xz"));
+        assert!(is_compressed_tar("app.TGZ"));
+        assert!(!is_compressed_tar("app.exe.gz"));
+        assert!(!supports_native_extract(&ArchiveFormat::RAR));
+    }
+}
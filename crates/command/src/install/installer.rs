@@ -5,13 +5,16 @@ use crate::init_env::{
     get_shims_root_dir_global,
 };
 use crate::install::{
-    create_default_shim_name_file, install_app, install_from_specific_bucket, DownloadManager,
+    create_default_shim_name_file, install_app, journal_create_dir, journal_create_link,
+    journal_env_var, journal_rename, journal_replace_link, use_aria2_downloader, DownloadManager,
     InstallOptions,
 };
 use crate::manifest::install_manifest::{SuggestObj, SuggestObjValue};
 use crate::manifest::manifest_deserialize::{
     PSModuleStruct, StringArrayOrString, StringOrArrayOrDoubleDimensionArray,
 };
+use crate::offline::is_offline_mode;
+use crate::utils::safe_check::check_manifest_relative_path;
 use crate::utils::system::{
     get_system_default_arch, get_system_env_str, get_system_env_var, get_user_env_str,
     get_user_env_var, set_global_env_var, set_user_env_var,
@@ -74,20 +77,6 @@ pub fn show_notes(notes: StringArrayOrString) -> anyhow::Result<()> {
     Ok(())
 }
 
-pub fn handle_depends(depends: String , options: &[InstallOptions<'_>]) -> anyhow::Result<()> {
-    if depends.contains('/') {
-        let arr = depends.split('/').collect::<Vec<&str>>();
-        if arr.len() != 2 {
-            bail!("manifest depends format error")
-        }
-        let bucket = arr[0].to_string();
-        let app_name = arr[1].to_string();
-        install_from_specific_bucket(&bucket, &app_name, options)?;
-    } else {
-        install_app(&depends, options)?;
-    }
-    Ok(())
-}
 pub fn handle_arch(arch: &[InstallOptions]) -> anyhow::Result<String> {
     if arch.contains(&InstallOptions::ArchOptions("64bit"))
         || arch.contains(&InstallOptions::ArchOptions("32bit"))
@@ -163,6 +152,7 @@ pub fn install_psmodule(
         bail!("module name cannot be empty, manifest format error");
     }
     let link_dir = format!("{}\\{}", psmodule_root_dir, module_name);
+    journal_replace_link(Path::new(&link_dir))?;
     if Path::new(&link_dir).exists() {
         eprintln!(
             "{}",
@@ -210,6 +200,7 @@ fn ensure_in_psmodulepath(psmodule_root_dir: &str, global: bool) -> anyhow::Resu
             psmodule_root_dir,
             if global { "global" } else { "your" }
         );
+        journal_env_var("PSModulePath", global)?;
         if global {
             set_global_env_var("PSModulePath", &format!("{psmodule_root_dir}\\{path}"))?;
         } else {
@@ -329,6 +320,8 @@ pub fn start_create_file_and_dir_link(
     app_name: &str,
     source_dir: &str,
 ) -> anyhow::Result<()> {
+    check_manifest_relative_path("persist", persist_dir)?;
+    check_manifest_relative_path("persist", source_dir)?;
     let persist_root_dir = if global {
         get_persist_dir_path_global()
     } else {
@@ -344,7 +337,9 @@ pub fn start_create_file_and_dir_link(
 
     if Path::new(&target_persist_dir).exists() {
         if Path::new(&source_dir).exists() {
-            std::fs::rename(&source_dir, format!("{source_dir}.original")).context(format!(
+            let original_dir = format!("{source_dir}.original");
+            journal_rename(Path::new(&source_dir), Path::new(&original_dir))?;
+            std::fs::rename(&source_dir, original_dir).context(format!(
                 "rename old source dir failed {} at line 343",
                 source_dir
             ))?;
@@ -357,13 +352,16 @@ pub fn start_create_file_and_dir_link(
                 parent.display()
             ))?;
         }
+        journal_rename(Path::new(&source_dir), Path::new(&target_persist_dir))?;
         std::fs::rename(&source_dir, &target_persist_dir)
             .context(format!("move source dir failed {} at line 355", source_dir))?
     } else {
+        journal_create_dir(Path::new(&target_persist_dir))?;
         ensure_directory(&target_persist_dir)?;
     }
 
     // !create persist data link
+    journal_create_link(Path::new(&source_dir))?;
     if Path::new(&target_persist_dir).is_dir() {
         fs::symlink_dir(&target_persist_dir, &source_dir).context(format!(
             "create target persisted dir failed {} at line 362",
@@ -400,22 +398,20 @@ pub fn install_app_from_url(
     if suffix.is_empty() {
         bail!("url suffix is empty");
     } else if suffix == "exe" {
-        let aria2 = which("aria2c").ok();
-        if aria2.is_none() {
+        if use_aria2_downloader() && !is_offline_mode() && which("aria2c").is_err() {
             install_app("aria2", options)?;
-        } else {
-            let download_manager = DownloadManager::new(
-                options,
-                download_url.to_str().unwrap(),
-                Some(download_url.to_str().unwrap()),
-            );
-            download_manager.start_download()?;
-            let exe_name =
-                download_manager.copy_file_to_app_dir_from_remote_url(app_alias.clone(), "exe")?;
-            download_manager.link_current_from_remote_url()?;
-            let app_name = download_manager.get_download_app_name();
-            create_default_shim_name_file(exe_name, shim_root.as_str(), app_name, options)?;
         }
+        let download_manager = DownloadManager::new(
+            options,
+            download_url.to_str().unwrap(),
+            Some(download_url.to_str().unwrap()),
+        );
+        download_manager.start_download()?;
+        let exe_name =
+            download_manager.copy_file_to_app_dir_from_remote_url(app_alias.clone(), "exe")?;
+        download_manager.link_current_from_remote_url()?;
+        let app_name = download_manager.get_download_app_name();
+        create_default_shim_name_file(exe_name, shim_root.as_str(), app_name, options)?;
     } else if suffix == "bat" || suffix == "cmd" {
         let download_manager = DownloadManager::new(
             options,
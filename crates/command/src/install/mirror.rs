@@ -0,0 +1,156 @@
+use crate::config::get_config_value_no_print;
+use anyhow::{bail, Context};
+use regex::Regex;
+use serde::Deserialize;
+
+/// 配置 `url_rewrite` 中的一条规则, prefix 与 regex 二选一
+///
+/// ```json
+/// [
+///   { "prefix": "https://github.com/", "replace": "https://mirror.corp/github/",
+///     "fallbacks": ["https://ghproxy.net/https://github.com/"] },
+///   { "regex": "^https://downloads\\.sourceforge\\.net/(.+)$", "replace": "https://mirror.corp/sf/$1" }
+/// ]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+struct UrlRewriteConfig {
+    prefix: Option<String>,
+    regex: Option<String>,
+    replace: String,
+    #[serde(default)]
+    fallbacks: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+enum UrlPattern {
+    Prefix(String),
+    Regex(Regex),
+}
+
+#[derive(Debug, Clone)]
+pub struct UrlRewriteRule {
+    pattern: UrlPattern,
+    /// 按顺序尝试的替换目标, 第一个为首选镜像
+    replacements: Vec<String>,
+}
+
+impl UrlRewriteRule {
+    fn rewrite(&self, url: &str) -> Option<Vec<String>> {
+        match &self.pattern {
+            UrlPattern::Prefix(prefix) => {
+                let rest = url.strip_prefix(prefix.as_str())?;
+                Some(
+                    self.replacements
+                        .iter()
+                        .map(|replacement| format!("{replacement}{rest}"))
+                        .collect(),
+                )
+            }
+            UrlPattern::Regex(regex) => {
+                if !regex.is_match(url) {
+                    return None;
+                }
+                Some(
+                    self.replacements
+                        .iter()
+                        .map(|replacement| regex.replace(url, replacement.as_str()).to_string())
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+pub fn parse_url_rewrite_rules(config: &str) -> anyhow::Result<Vec<UrlRewriteRule>> {
+    if config.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    let configs: Vec<UrlRewriteConfig> =
+        serde_json::from_str(config).context("url_rewrite must be a json array of rules")?;
+    configs
+        .into_iter()
+        .map(|config| {
+            let pattern = match (config.prefix, config.regex) {
+                (Some(prefix), None) if !prefix.is_empty() => UrlPattern::Prefix(prefix),
+                (None, Some(regex)) => UrlPattern::Regex(
+                    Regex::new(&regex).context(format!("Invalid url_rewrite regex '{regex}'"))?,
+                ),
+                _ => bail!("url_rewrite rule needs exactly one of 'prefix' or 'regex'"),
+            };
+            let replacements = std::iter::once(config.replace)
+                .chain(config.fallbacks)
+                .collect();
+            Ok(UrlRewriteRule {
+                pattern,
+                replacements,
+            })
+        })
+        .collect()
+}
+
+/// 读取配置中的改写规则, 配置错误时忽略并提示, 不影响正常下载
+pub fn load_url_rewrite_rules() -> Vec<UrlRewriteRule> {
+    parse_url_rewrite_rules(&get_config_value_no_print("url_rewrite")).unwrap_or_else(|e| {
+        log::warn!("Ignore url_rewrite config: {e:#}");
+        vec![]
+    })
+}
+
+/// 返回按顺序尝试的下载地址, 第一条匹配规则的镜像在前, 原始地址作为最后的回退
+pub fn rewrite_download_url(rules: &[UrlRewriteRule], url: &str) -> Vec<String> {
+    let mut candidates = rules
+        .iter()
+        .find_map(|rule| rule.rewrite(url))
+        .unwrap_or_default();
+    candidates.retain(|candidate| candidate != url);
+    candidates.dedup();
+    candidates.push(url.to_string());
+    candidates
+}
+
+#[cfg(test)]
+mod test_mirror {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_rewrite_download_url() {
+        let rules = parse_url_rewrite_rules(
+            r#"[
+                { "prefix": "https://github.com/", "replace": "https://mirror.corp/github/",
+                  "fallbacks": ["https://ghproxy.net/https://github.com/"] },
+                { "regex": "^https://downloads\\.sourceforge\\.net/(.+)$", "replace": "https://mirror.corp/sf/$1" }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            rewrite_download_url(
+                &rules,
+                "https://github.com/cli/cli/releases/download/v2.0/gh.zip"
+            ),
+            vec![
+                "https://mirror.corp/github/cli/cli/releases/download/v2.0/gh.zip",
+                "https://ghproxy.net/https://github.com/cli/cli/releases/download/v2.0/gh.zip",
+                "https://github.com/cli/cli/releases/download/v2.0/gh.zip",
+            ]
+        );
+        assert_eq!(
+            rewrite_download_url(&rules, "https://downloads.sourceforge.net/app/app.7z"),
+            vec![
+                "https://mirror.corp/sf/app/app.7z",
+                "https://downloads.sourceforge.net/app/app.7z"
+            ]
+        );
+        assert_eq!(
+            rewrite_download_url(&rules, "https://example.com/a.zip"),
+            vec!["https://example.com/a.zip"]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_rules() {
+        assert!(parse_url_rewrite_rules("").unwrap().is_empty());
+        assert!(parse_url_rewrite_rules(r#"[{ "replace": "x" }]"#).is_err());
+        assert!(parse_url_rewrite_rules(r#"[{ "regex": "(", "replace": "x" }]"#).is_err());
+    }
+}
@@ -2,8 +2,11 @@ use crate::init_env::{
     get_cache_dir_path, get_cache_dir_path_global, get_shims_root_dir, get_shims_root_dir_global,
 };
 use crate::install::InstallOptions::NoUseDownloadCache;
-use crate::install::{install_app, ArchiveFormat, InstallOptions};
+use crate::install::{
+    extract_archive_natively, install_app, supports_native_extract, ArchiveFormat, InstallOptions,
+};
 use crate::manifest::manifest_deserialize::StringArrayOrString;
+use crate::utils::safe_check::check_manifest_relative_path;
 use crate::utils::system::{is_broken_symlink, kill_processes_using_app};
 use anyhow::{bail, Context};
 use crossterm::style::Stylize;
@@ -330,7 +333,6 @@ impl<'a> SevenZipStruct<'a> {
         if archive_items.is_empty() || archive_paths.is_empty() {
             bail!("No archive files found.");
         }
-        let _7z: String = self.load_7z_to_temp_dir()?;
         if !self.target_is_valid() {
             bail!("Target directory is not in scoop child tree.")
         }
@@ -439,39 +441,31 @@ impl<'a> SevenZipStruct<'a> {
                             .expect("Failed to move child dir to root");
                         Ok(())
                     } else {
-                        let target = format!("-o{}", extract_to);
-                        let output = Command::new(&_7z)
-                            .arg("x")
-                            .arg(path)
-                            .arg(target)
-                            .arg("-aoa") // *!自动覆盖同名文件
-                            .stdout(Stdio::piped())
-                            .stderr(Stdio::piped())
-                            .output()?;
-                        if !output.status.success() {
-                            let error = String::from_utf8_lossy(&output.stderr);
-                            bail!("7z command failed: {}", error)
-                        } else {
-                            let child_dir = format!("{}\\{}", extract_to, extract_dir);
-                            // log::debug!("child dir: {}", child_dir);
-                            for entry in std::fs::read_dir(&child_dir)
-                                .context(format!("Failed to read child directory {}", &child_dir))?
-                            {
-                                let entry = entry?;
-                                let from = entry.path();
-                                let file_name = entry.file_name();
-                                let to = Path::new(&extract_to).join(file_name);
-                                std::fs::rename(&from, &to).context(format!(
-                                    "Failed to move file {} to {}",
-                                    from.display(),
-                                    to.display()
-                                ))?;
-                            }
-                            std::fs::remove_dir_all(&child_dir)
-                                .context(format!("Failed to remove old child {}", &child_dir))?;
-                            println!("✅");
-                            Ok(())
+                        self.extract_archive_file(
+                            path.as_str(),
+                            archive_name,
+                            archive_format,
+                            extract_to.as_str(),
+                        )?;
+                        let child_dir = format!("{}\\{}", extract_to, extract_dir);
+                        // log::debug!("child dir: {}", child_dir);
+                        for entry in std::fs::read_dir(&child_dir)
+                            .context(format!("Failed to read child directory {}", &child_dir))?
+                        {
+                            let entry = entry?;
+                            let from = entry.path();
+                            let file_name = entry.file_name();
+                            let to = Path::new(&extract_to).join(file_name);
+                            std::fs::rename(&from, &to).context(format!(
+                                "Failed to move file {} to {}",
+                                from.display(),
+                                to.display()
+                            ))?;
                         }
+                        std::fs::remove_dir_all(&child_dir)
+                            .context(format!("Failed to remove old child {}", &child_dir))?;
+                        println!("✅");
+                        Ok(())
                     }
                 },
             );
@@ -601,7 +595,6 @@ Expand-InnoArchive "{inno_file}" "{target_dir}"  -Removal
         if archive_items.is_empty() || archive_paths.is_empty() {
             bail!("No archive files found.");
         }
-        let _7z: String = self.load_7z_to_temp_dir()?;
         let archive_counts = archive_items.len();
         let extract_dir_counts = archive_child_dir.len();
         let archive_child_dir = if extract_dir_counts < archive_counts {
@@ -692,32 +685,22 @@ Expand-InnoArchive "{inno_file}" "{target_dir}"  -Removal
                             .expect("Failed to move child dir to root");
                         Ok(())
                     } else {
-                        let target = format!("-o{}", target_dir);
-                        let output = Command::new(&_7z)
-                            .arg("x")
-                            .arg(path)
-                            .arg(target)
-                            .arg("-aoa") // *!自动覆盖同名文件
-                            .stdout(Stdio::piped())
-                            .stderr(Stdio::piped())
-                            .output()
-                            .expect("Failed to extract archive");
-
-                        if !output.status.success() {
-                            let error = String::from_utf8_lossy(&output.stderr);
-                            bail!("7z command failed: {}", error)
-                        } else {
-                            let child_dir = format!("{}\\{}", target_dir, child_dir);
-                            log::debug!("Child dir is {}", target_dir);
-                            log::debug!("Target dir is {}", target_dir);
+                        self.extract_archive_file(
+                            path.as_str(),
+                            archive_name,
+                            archive_format,
+                            target_dir,
+                        )?;
+                        let child_dir = format!("{}\\{}", target_dir, child_dir);
+                        log::debug!("Child dir is {}", target_dir);
+                        log::debug!("Target dir is {}", target_dir);
 
-                            self.move_child_dir_to_root(&child_dir, target_dir)
-                                .expect("Failed to move child dir to root");
+                        self.move_child_dir_to_root(&child_dir, target_dir)
+                            .expect("Failed to move child dir to root");
 
-                            println!("✅");
+                        println!("✅");
 
-                            Ok(())
-                        }
+                        Ok(())
                     }
                 },
             );
@@ -758,13 +741,11 @@ Expand-InnoArchive "{inno_file}" "{target_dir}"  -Removal
             bail!("No archive files found.");
         }
 
-        let _7z: String = self.load_7z_to_temp_dir().expect("Failed to load 7z.exe");
         if !self.target_is_valid() {
             bail!("Target directory is not in scoop child tree.")
         }
         let archive_formats = self.get_archive_format();
         let target_alias_names = self.get_target_alias_name();
-        log::debug!("7z exe is entirely exists");
         if target_dir.is_none() {
             let target_dir = self.get_target_app_version_dir();
             if !Path::new(target_dir).exists() {
@@ -793,7 +774,6 @@ Expand-InnoArchive "{inno_file}" "{target_dir}"  -Removal
                         archive_name,
                         archive_format,
                         target_alias,
-                        _7z.as_str(),
                     );
                     return result;
                 });
@@ -848,7 +828,6 @@ Expand-InnoArchive "{inno_file}" "{target_dir}"  -Removal
                             archive_name,
                             archive_format,
                             target_alias,
-                            _7z.as_str(),
                         );
                         return result;
                     },
@@ -867,6 +846,11 @@ Expand-InnoArchive "{inno_file}" "{target_dir}"  -Removal
         extract_dir: Option<StringArrayOrString>,
         extract_to: Option<StringArrayOrString>,
     ) -> anyhow::Result<()> {
+        check_extract_paths("extract_dir", &extract_dir)?;
+        check_extract_paths("extract_to", &extract_to)?;
+        for alias in self.get_target_alias_name() {
+            check_manifest_relative_path("url", alias)?;
+        }
         if extract_dir.is_none() && extract_to.is_none() {
             self.extract_archive_to_target_dir(None)
                 .expect("extract archive to target directory");
@@ -954,7 +938,6 @@ Expand-InnoArchive "{inno_file}" "{target_dir}"  -Removal
         archive_name: &str,
         archive_format: &ArchiveFormat,
         target_alias: &str,
-        _7z: &str,
     ) -> anyhow::Result<()> {
         let result = if *archive_format == ArchiveFormat::EXE
             || *archive_format == ArchiveFormat::Other
@@ -1003,25 +986,58 @@ Expand-InnoArchive "{inno_file}" "{target_dir}"  -Removal
 
             Ok(())
         } else {
-            log::debug!("file is archive , invoke external command");
-            let target = format!("-o{}", target_dir);
-            let output = Command::new(_7z)
-                .arg("x")
-                .arg(path)
-                .arg(target)
-                .arg("-aoa") // *!自动覆盖同名文件
-                .output()?;
-            if !output.status.success() {
-                let error = String::from_utf8_lossy(&output.stderr);
-                bail!("7z command failed: {}", error)
-            } else {
-                println!("✅");
-                Ok(())
-            }
+            self.extract_archive_file(path.as_str(), archive_name, archive_format, target_dir)?;
+            println!("✅");
+            Ok(())
         };
 
         result
     }
+
+    /// 内置解压器能处理的格式直接解压, 其余格式才释放并调用 7z.exe
+    pub fn extract_archive_file(
+        &self,
+        path: &str,
+        archive_name: &str,
+        archive_format: &ArchiveFormat,
+        target_dir: &str,
+    ) -> anyhow::Result<()> {
+        if supports_native_extract(archive_format) {
+            return extract_archive_natively(
+                Path::new(path),
+                archive_name,
+                archive_format,
+                Path::new(target_dir),
+            );
+        }
+        log::debug!("file is archive , invoke external command");
+        let _7z = self.load_7z_to_temp_dir()?;
+        let target = format!("-o{}", target_dir);
+        let output = Command::new(_7z)
+            .arg("x")
+            .arg(path)
+            .arg(target)
+            .arg("-aoa") // *!自动覆盖同名文件
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()?;
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr);
+            bail!("7z command failed: {}", error)
+        }
+        Ok(())
+    }
+}
+
+fn check_extract_paths(field: &str, paths: &Option<StringArrayOrString>) -> anyhow::Result<()> {
+    let paths = match paths {
+        Some(StringArrayOrString::String(path)) => vec![path.as_str()],
+        Some(StringArrayOrString::StringArray(paths)) => paths.iter().map(String::as_str).collect(),
+        _ => vec![],
+    };
+    paths
+        .into_iter()
+        .try_for_each(|path| check_manifest_relative_path(field, path))
 }
 
 mod test_7z {
@@ -1,10 +1,11 @@
 use crate::init_env::{get_app_current_bin_path, get_shims_root_dir, get_shims_root_dir_global};
-use crate::install::InstallOptions;
 use crate::install::InstallOptions::InteractiveInstall;
+use crate::install::{journal_create_dir, journal_write_file, InstallOptions};
 use crate::manifest::install_manifest::InstallManifest;
 use crate::manifest::manifest_deserialize::{
     ArrayOrDoubleDimensionArray, StringOrArrayOrDoubleDimensionArray,
 };
+use crate::utils::safe_check::check_manifest_relative_path;
 use crate::utils::system::get_system_default_arch;
 use crate::utils::utility::{
     assume_yes_to_cover_shortcuts, exclude_scoop_self_scripts, strip_extended_prefix,
@@ -251,6 +252,8 @@ pub fn create_start_menu_shortcuts(
             if shortcut_name.is_empty() {
                 bail!("Error : shortcut name cannot be empty")
             }
+            check_manifest_relative_path("shortcuts", &bin_name_with_extension)?;
+            check_manifest_relative_path("shortcuts", &shortcut_name)?;
             let start_parameters = if arg_len == 3 || arg_len == 4 {
                 shortcut[2].trim().to_string()
             } else {
@@ -288,6 +291,8 @@ pub fn create_start_menu_shortcuts(
                 if bin_name_with_extension.is_empty() {
                     bail!("Error : shortcuts target link  cannot be empty")
                 }
+                check_manifest_relative_path("shortcuts", &bin_name_with_extension)?;
+                check_manifest_relative_path("shortcuts", &shortcut_name)?;
                 let start_parameters = if arg_len == 3 || arg_len == 4 {
                     shortcut_item[2].trim().to_string()
                 } else {
@@ -336,6 +341,7 @@ pub fn start_create_shortcut<P: AsRef<Path>>(
     if link_path.exists() && options.contains(&InteractiveInstall) {
         let result = assume_yes_to_cover_shortcuts(link_alias_name)?;
         if result {
+            journal_write_file(&link_path)?;
             fs::remove_file(start_menu_path.as_ref())
                 .context("Failed to remove  old  start_menu_path at line 334".to_string())?;
         } else {
@@ -355,8 +361,10 @@ pub fn start_create_shortcut<P: AsRef<Path>>(
     let shell_link = ShellLink::new(link_target_path, args, None, None)?;
     let parent = start_menu_path.as_ref().parent().unwrap();
     if !parent.exists() {
+        journal_create_dir(parent)?;
         fs::create_dir_all(parent).context("Failed to create link parent directory at line 353")?;
     };
+    journal_write_file(&link_path)?;
     shell_link
         .create_lnk(start_menu_path)
         .context("Create shell_link shortcuts failed  at line 357")?;
@@ -372,12 +380,10 @@ pub fn create_alias_shim_name_file(
     program_args: Option<String>,
     options: &[InstallOptions],
 ) -> anyhow::Result<()> {
+    check_manifest_relative_path("bin", &exe_name)?;
+    check_manifest_relative_path("bin", &alias_name)?;
     let out_dir = PathBuf::from(shim_dir);
-    let suffix = if exe_name.contains(".") {
-        exe_name.split('.').last().unwrap().to_lowercase()
-    } else {
-        "".to_string()
-    };
+    let suffix = shim_suffix(&exe_name);
 
     let target_path = get_app_current_bin_path(app_name.into(), &exe_name, options);
 
@@ -470,12 +476,9 @@ pub fn create_default_shim_name_file(
     app_name: &str,
     options: &[InstallOptions],
 ) -> anyhow::Result<()> {
+    check_manifest_relative_path("bin", &exe_name)?;
     let out_dir = PathBuf::from(shim_dir);
-    let suffix = if !exe_name.contains(".") {
-        "".into()
-    } else {
-        exe_name.split('.').last().unwrap().to_lowercase()
-    };
+    let suffix = shim_suffix(&exe_name);
 
     let target_path = get_app_current_bin_path(app_name.into(), &exe_name, options);
     let target_path = fs::canonicalize(&target_path).context(format!(
@@ -528,25 +531,7 @@ pub fn create_py_shim_scripts(
     program_args: Option<String>,
     options: &[InstallOptions],
 ) -> anyhow::Result<()> {
-    let target_name = if alias_name.is_none() {
-        Path::new(&target_path)
-            .file_stem()
-            .and_then(|s| s.to_str().and_then(|s| Some(s.to_lowercase())))
-            .ok_or("Invalid target executable name")
-    } else {
-        let alias_name = alias_name.unwrap();
-        Ok(if alias_name.contains('.') {
-            eprintln!("alias_name {} 包含 . 字符, 自动去除扩展名", alias_name);
-            alias_name.split('.').next().unwrap().to_string()
-        } else {
-            alias_name
-        })
-    };
-    if target_name.is_err() {
-        let target_name = target_name.unwrap();
-        bail!("Invalid target executable name {target_path} \n Error TargetName :{target_name}")
-    }
-    let target_name = target_name.unwrap();
+    let target_name = resolve_shim_target_name(target_path, alias_name.as_deref())?;
     let out_shim_dir: Result<&str, anyhow::Error> = if out_shim_dir.exists() {
         Ok(out_shim_dir.to_str().unwrap())
     } else {
@@ -609,25 +594,7 @@ pub fn create_jar_shim_scripts(
     program_args: Option<String>,
     options: &[InstallOptions],
 ) -> anyhow::Result<()> {
-    let target_name = if alias_name.is_none() {
-        Path::new(&target_path)
-            .file_stem()
-            .and_then(|s| s.to_str().and_then(|s| Some(s.to_lowercase())))
-            .ok_or("Invalid target executable name")
-    } else {
-        let alias_name = alias_name.unwrap();
-        Ok(if alias_name.contains('.') {
-            eprintln!("alias_name {} 包含 . 字符, 自动去除扩展名", alias_name);
-            alias_name.split('.').next().unwrap().to_string()
-        } else {
-            alias_name
-        })
-    };
-    if target_name.is_err() {
-        let target_name = target_name.unwrap();
-        bail!("Invalid target executable name {target_path} \n Error TargetName :{target_name}")
-    }
-    let target_name = target_name.unwrap();
+    let target_name = resolve_shim_target_name(target_path, alias_name.as_deref())?;
     let out_shim_dir: Result<&str, anyhow::Error> = if out_shim_dir.exists() {
         Ok(out_shim_dir.to_str().unwrap())
     } else {
@@ -710,25 +677,7 @@ pub fn create_ps1_shim_scripts(
     program_params: Option<String>,
     options: &[InstallOptions],
 ) -> anyhow::Result<()> {
-    let target_name = if alias_name.is_none() {
-        Path::new(&target_path)
-            .file_stem()
-            .and_then(|s| s.to_str().and_then(|s| Some(s.to_lowercase())))
-            .ok_or("Invalid target executable name")
-    } else {
-        let alias_name = alias_name.unwrap();
-        Ok(if alias_name.contains('.') {
-            eprintln!("alias_name {} 包含 . 字符, 自动去除扩展名", alias_name);
-            alias_name.split('.').next().unwrap().to_string()
-        } else {
-            alias_name
-        })
-    };
-    if target_name.is_err() {
-        let target_name = target_name.unwrap();
-        bail!("Invalid target executable name {target_path} \n Error TargetName :{target_name}")
-    }
-    let target_name = target_name.unwrap();
+    let target_name = resolve_shim_target_name(target_path, alias_name.as_deref())?;
     let resolved_path = target_path;
     let out_shim_dir: Result<&str, anyhow::Error> = if out_shim_dir.exists() {
         Ok(out_shim_dir.to_str().unwrap())
@@ -814,25 +763,7 @@ pub fn create_cmd_or_bat_shim_scripts(
     program_args: Option<String>,
     options: &[InstallOptions],
 ) -> anyhow::Result<()> {
-    let target_name = if alias_name.is_none() {
-        Path::new(&target_path)
-            .file_stem()
-            .and_then(|s| s.to_str().and_then(|s| Some(s.to_lowercase())))
-            .ok_or("Invalid target executable name")
-    } else {
-        let alias_name = alias_name.unwrap();
-        Ok(if alias_name.contains('.') {
-            eprintln!("alias_name {} 包含 . 字符, 自动去除扩展名", alias_name);
-            alias_name.split('.').next().unwrap().to_string()
-        } else {
-            alias_name
-        })
-    };
-    if target_name.is_err() {
-        let target_name = target_name.unwrap();
-        bail!("Invalid target executable name {target_path} \n Error TargetName :{target_name}")
-    }
-    let target_name = target_name.unwrap();
+    let target_name = resolve_shim_target_name(target_path, alias_name.as_deref())?;
 
     let cmd_content = if program_args.is_none() {
         format!("@rem {target_path}\r\n@\"{target_path}\" %*\r\n")
@@ -889,29 +820,11 @@ pub fn create_shell_shim_scripts(
     program_args: Option<String>,
     options: &[InstallOptions],
 ) -> anyhow::Result<()> {
-    let target_name = if alias_name.is_none() {
-        Path::new(&target_path)
-            .file_stem()
-            .and_then(|s| s.to_str().and_then(|s| Some(s.to_lowercase())))
-            .ok_or("Invalid target executable name")
-    } else {
-        let alias_name = alias_name.unwrap();
-        Ok(if alias_name.contains('.') {
-            eprintln!("alias_name {} 包含 . 字符, 自动去除扩展名", alias_name);
-            alias_name.split('.').next().unwrap().to_string()
-        } else {
-            alias_name
-        })
-    };
-    if target_name.is_err() {
-        let target_name = target_name.unwrap();
-        bail!("Invalid target executable name {target_path} \n Error TargetName :{target_name}")
-    }
+    let target_name = resolve_shim_target_name(target_path, alias_name.as_deref())?;
     if !out_shim_dir.exists() {
         fs::create_dir_all(&out_shim_dir)?;
     }
     let out_shim_dir = out_shim_dir.to_str().unwrap();
-    let target_name = target_name.unwrap();
 
     let shim_cmd_path = format!("{out_shim_dir}\\{target_name}.cmd");
     println!(
@@ -974,25 +887,7 @@ pub fn create_exe_type_shim_file_and_shim_bin<P1: AsRef<Path>, P2: AsRef<Path>>(
     let target_path = target_path.as_ref().to_str().unwrap();
     let output_dir = output_dir.as_ref().to_path_buf();
 
-    let target_name = if alias_name.is_none() {
-        Path::new(target_path)
-            .file_stem()
-            .and_then(|s| s.to_str().and_then(|s| Some(s.to_lowercase())))
-            .ok_or("Invalid target executable name")
-    } else {
-        let alias_name = alias_name.unwrap();
-        Ok(if alias_name.contains('.') {
-            eprintln!("alias_name {} 包含 . 字符, 自动去除扩展名", alias_name);
-            alias_name.split('.').next().unwrap().to_string()
-        } else {
-            alias_name
-        })
-    };
-
-    if target_name.is_err() {
-        let target_name = target_name.unwrap();
-        bail!("Invalid target executable name {target_path} \n Error TargetName :{target_name}")
-    }
+    let target_name = resolve_shim_target_name(target_path, alias_name.as_deref())?;
 
     let content = if program_params.is_none() {
         format!("path = \"{}\"", target_path)
@@ -1000,7 +895,10 @@ pub fn create_exe_type_shim_file_and_shim_bin<P1: AsRef<Path>, P2: AsRef<Path>>(
         let program_params = program_params.clone().unwrap();
         format!("path = \"{target_path}\"\nargs = \"{program_params}\"")
     };
-    let target_name = target_name.unwrap();
+    let content = match shim_app_name(target_path, options) {
+        Some(app_name) => format!("{content}\napp = \"{app_name}\""),
+        None => content,
+    };
     // Determine the shim file name
     let shim_name = format!("{}.shim", target_name);
     let shim_name2 = format!("{}.exe", target_name);
@@ -1039,12 +937,72 @@ pub fn create_exe_type_shim_file_and_shim_bin<P1: AsRef<Path>, P2: AsRef<Path>>(
         if shim_path2.exists() {
             return Ok(());
         }
+        journal_write_file(&shim_path2)?;
         fs::write(&shim_path2, DRIVER_SHIM_BYTES)
             .expect("failed create shim.exe, maybe process is running");
     }
     Ok(())
 }
 
+/// bin 的扩展名决定生成哪种 shim, 没有扩展名时视为 shell 脚本
+pub fn shim_suffix(exe_name: &str) -> String {
+    if exe_name.contains('.') {
+        exe_name.split('.').last().unwrap().to_lowercase()
+    } else {
+        String::new()
+    }
+}
+
+/// shim 的名称, 有别名时去掉别名中的扩展名, 别名为空时取目标文件名
+pub fn shim_target_name(target_path: &str, alias_name: Option<&str>) -> Option<String> {
+    match alias_name.filter(|alias_name| !alias_name.trim().is_empty()) {
+        Some(alias_name) => Some(alias_name.split('.').next()?.to_string()),
+        None => Path::new(target_path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_lowercase()),
+    }
+}
+
+fn resolve_shim_target_name(target_path: &str, alias_name: Option<&str>) -> anyhow::Result<String> {
+    if let Some(alias_name) = alias_name.filter(|alias_name| alias_name.contains('.')) {
+        eprintln!("alias_name {} 包含 . 字符, 自动去除扩展名", alias_name);
+    }
+    match shim_target_name(target_path, alias_name) {
+        Some(target_name) => Ok(target_name),
+        None => bail!("Invalid target executable name {target_path}"),
+    }
+}
+
+/// 与 create_*_shim 系列函数一致, 列出 shim 目录下会生成的文件名
+pub fn shim_file_names(exe_name: &str, alias_name: Option<&str>) -> anyhow::Result<Vec<String>> {
+    let target_name = shim_target_name(exe_name, alias_name)
+        .ok_or_else(|| anyhow::anyhow!("Invalid target executable name {exe_name}"))?;
+    let extensions: &[&str] = match shim_suffix(exe_name).as_str() {
+        "exe" | "com" => &[".shim", ".exe"],
+        "ps1" => &[".ps1", ".cmd", ""],
+        "cmd" | "bat" | "jar" | "py" | "" => &[".cmd", ""],
+        suffix => bail!(format!(" 后缀{suffix}类型文件不支持, WTF?")),
+    };
+    Ok(extensions
+        .iter()
+        .map(|extension| format!("{target_name}{extension}"))
+        .collect())
+}
+
+/// 目标位于 `apps\<app>\current` 下时写入 app 名, shim 据此按 `.hp-tools` 切换版本目录
+fn shim_app_name(target_path: &str, options: &[InstallOptions]) -> Option<String> {
+    let app_name = options.iter().find_map(|option| match option {
+        InstallOptions::CurrentInstallApp { app_name, .. } => Some(app_name.to_lowercase()),
+        _ => None,
+    })?;
+    let current_dir = format!("\\{app_name}\\current\\");
+    target_path
+        .to_lowercase()
+        .contains(&current_dir)
+        .then_some(app_name)
+}
+
 #[cfg(test)]
 mod test_shim {
     #[allow(unused)]
@@ -1065,6 +1023,42 @@ mod test_shim {
         create_start_menu_shortcuts(shortcuts, app_name, &options).unwrap();
     }
 
+    #[test]
+    fn test_shim_app_name() {
+        let options = vec![InstallOptions::CurrentInstallApp {
+            app_name: "nodejs".into(),
+            app_version: "20.11.1".into(),
+        }];
+        let target_path = r"A:\Scoop\apps\nodejs\current\node.exe";
+        assert_eq!(
+            shim_app_name(target_path, &options),
+            Some("nodejs".to_string())
+        );
+        let target_path = r"A:\Scoop\apps\nodejs\20.11.1\node.exe";
+        assert_eq!(shim_app_name(target_path, &options), None);
+        assert_eq!(
+            shim_app_name(r"A:\Scoop\apps\nodejs\current\node.exe", &[]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_shim_file_names() {
+        assert_eq!(
+            shim_file_names(r"bin\GH.exe", None).unwrap(),
+            vec!["gh.shim", "gh.exe"]
+        );
+        assert_eq!(
+            shim_file_names("composer.ps1", None).unwrap(),
+            vec!["composer.ps1", "composer.cmd", "composer"]
+        );
+        assert_eq!(
+            shim_file_names("npm.cmd", Some("npm2.cmd")).unwrap(),
+            vec!["npm2.cmd", "npm2"]
+        );
+        assert!(shim_file_names("demo.dll", None).is_err());
+    }
+
     #[test]
     fn test_create_exe_shims() {
         let cwd = env::current_dir().unwrap();
@@ -0,0 +1,544 @@
+use crate::init_env::{get_app_dir, get_app_dir_global, init_scoop_global, init_user_scoop};
+use crate::utils::system::{
+    delete_env_var, delete_global_env_var, get_system_env_var, get_user_env_var,
+    set_global_env_var, set_user_env_var,
+};
+use anyhow::{bail, Context};
+use crossterm::style::Stylize;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::os::windows::fs::symlink_dir;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 放在 scoop 根目录下, 不与 apps 下的 App 和版本目录混在一起
+const TRANSACTION_DIR_NAME: &str = ".hp-transactions";
+const JOURNAL_FILE_NAME: &str = "journal.json";
+
+/// 安装过程中的一次可撤销操作, 回滚时按记录的逆序撤销
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum JournalAction {
+    CreateDir {
+        path: PathBuf,
+    },
+    /// backup 为覆盖前的文件副本, 新建文件时为 None
+    WriteFile {
+        path: PathBuf,
+        backup: Option<PathBuf>,
+    },
+    /// 替换目录链接, 例如 current 与 psmodule, previous 为之前指向的目录
+    ReplaceLink {
+        link: PathBuf,
+        previous: Option<PathBuf>,
+    },
+    CreateLink {
+        path: PathBuf,
+    },
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// 被覆盖安装的目录先移到事务目录, 提交时随事务目录删除, 回滚时移回
+    StashDir {
+        path: PathBuf,
+        backup: PathBuf,
+    },
+    SetEnv {
+        name: String,
+        global: bool,
+        previous: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallJournal {
+    pub app_name: String,
+    pub version: String,
+    pub global: bool,
+    pub actions: Vec<JournalAction>,
+}
+
+/// 嵌套安装(例如依赖)时记录写入最内层的事务
+static ACTIVE_JOURNALS: Lazy<Mutex<Vec<InstallJournal>>> = Lazy::new(|| Mutex::new(vec![]));
+
+fn get_transactions_root(global: bool) -> PathBuf {
+    let scoop_root = if global {
+        init_scoop_global()
+    } else {
+        init_user_scoop()
+    };
+    Path::new(&scoop_root).join(TRANSACTION_DIR_NAME)
+}
+
+fn get_transaction_dir(app_name: &str, global: bool) -> PathBuf {
+    get_transactions_root(global).join(app_name)
+}
+
+fn remove_link(path: &Path) -> anyhow::Result<()> {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return Ok(());
+    };
+    if metadata.file_type().is_symlink() {
+        fs::remove_dir(path)
+            .or_else(|_| fs::remove_file(path))
+            .context(format!("Failed to remove link {}", path.display()))?;
+    } else if metadata.is_dir() {
+        fs::remove_dir_all(path).context(format!("Failed to remove {}", path.display()))?;
+    } else {
+        fs::remove_file(path).context(format!("Failed to remove {}", path.display()))?;
+    }
+    Ok(())
+}
+
+impl InstallJournal {
+    fn transaction_dir(&self) -> PathBuf {
+        get_transaction_dir(&self.app_name, self.global)
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        let dir = self.transaction_dir();
+        fs::create_dir_all(&dir).context(format!("Failed to create {}", dir.display()))?;
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(dir.join(JOURNAL_FILE_NAME), content).context(format!(
+            "Failed to write install journal in {}",
+            dir.display()
+        ))?;
+        Ok(())
+    }
+
+    fn load(app_name: &str, global: bool) -> anyhow::Result<Option<Self>> {
+        let path = get_transaction_dir(app_name, global).join(JOURNAL_FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .context(format!("Failed to read install journal {}", path.display()))?;
+        let journal = serde_json::from_str(&content).context(format!(
+            "Failed to parse install journal {}",
+            path.display()
+        ))?;
+        Ok(Some(journal))
+    }
+
+    fn backup_file(&self, path: &Path) -> anyhow::Result<PathBuf> {
+        let backup_dir = self.transaction_dir().join("backup");
+        fs::create_dir_all(&backup_dir)
+            .context(format!("Failed to create {}", backup_dir.display()))?;
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let backup = backup_dir.join(format!("{}-{file_name}", self.actions.len()));
+        fs::copy(path, &backup).context(format!("Failed to back up {}", path.display()))?;
+        Ok(backup)
+    }
+
+    fn undo(action: &JournalAction) -> anyhow::Result<()> {
+        match action {
+            JournalAction::CreateDir { path } => {
+                if path.exists() {
+                    fs::remove_dir_all(path)
+                        .context(format!("Failed to remove {}", path.display()))?;
+                }
+            }
+            JournalAction::WriteFile { path, backup } => match backup {
+                Some(backup) => {
+                    fs::copy(backup, path)
+                        .context(format!("Failed to restore {}", path.display()))?;
+                }
+                None => {
+                    if path.exists() {
+                        fs::remove_file(path)
+                            .context(format!("Failed to remove {}", path.display()))?;
+                    }
+                }
+            },
+            JournalAction::ReplaceLink { link, previous } => {
+                remove_link(link)?;
+                if let Some(previous) = previous.as_ref().filter(|previous| previous.exists()) {
+                    symlink_dir(previous, link).context(format!(
+                        "Failed to relink {} => {}",
+                        link.display(),
+                        previous.display()
+                    ))?;
+                }
+            }
+            JournalAction::CreateLink { path } => remove_link(path)?,
+            JournalAction::StashDir { path, backup } => {
+                if backup.exists() {
+                    if path.exists() {
+                        fs::remove_dir_all(path)
+                            .context(format!("Failed to remove {}", path.display()))?;
+                    }
+                    fs::rename(backup, path)
+                        .context(format!("Failed to restore {}", path.display()))?;
+                }
+            }
+            JournalAction::Rename { from, to } => {
+                if to.exists() && !from.exists() {
+                    fs::rename(to, from).context(format!(
+                        "Failed to move {} back to {}",
+                        to.display(),
+                        from.display()
+                    ))?;
+                }
+            }
+            JournalAction::SetEnv {
+                name,
+                global,
+                previous,
+            } => match (previous, *global) {
+                (Some(value), true) => set_global_env_var(name, value)?,
+                (Some(value), false) => set_user_env_var(name, value)?,
+                (None, true) => delete_global_env_var(name)?,
+                (None, false) => delete_env_var(name)?,
+            },
+        }
+        Ok(())
+    }
+
+    /// 逆序撤销全部操作, 单个操作失败不会中断其余撤销
+    fn rollback(&self) -> Vec<anyhow::Error> {
+        let errors = self
+            .actions
+            .iter()
+            .rev()
+            .filter_map(|action| Self::undo(action).err())
+            .collect::<Vec<_>>();
+        self.cleanup();
+        errors
+    }
+
+    /// 删除事务目录(包括暂存的旧目录), 回滚后若 App 目录为空一并删除
+    fn cleanup(&self) {
+        let dir = self.transaction_dir();
+        if dir.exists() {
+            let _ = fs::remove_dir_all(&dir);
+        }
+        let app_dir = if self.global {
+            get_app_dir_global(&self.app_name)
+        } else {
+            get_app_dir(&self.app_name)
+        };
+        for dir in [PathBuf::from(app_dir), get_transactions_root(self.global)] {
+            let is_empty = fs::read_dir(&dir)
+                .map(|mut entries| entries.next().is_none())
+                .unwrap_or(false);
+            if is_empty {
+                let _ = fs::remove_dir(&dir);
+            }
+        }
+    }
+}
+
+fn with_active_journal<F>(record: F) -> anyhow::Result<()>
+where
+    F: FnOnce(&InstallJournal) -> anyhow::Result<Option<JournalAction>>,
+{
+    let mut journals = ACTIVE_JOURNALS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let Some(journal) = journals.last_mut() else {
+        return Ok(());
+    };
+    if let Some(action) = record(&*journal)? {
+        journal.actions.push(action);
+        journal.save()?;
+    }
+    Ok(())
+}
+
+/// 在创建目录前调用, 目录已存在时不记录
+pub fn journal_create_dir(path: &Path) -> anyhow::Result<()> {
+    with_active_journal(|_| {
+        if path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(JournalAction::CreateDir {
+            path: path.to_path_buf(),
+        }))
+    })
+}
+
+/// 在写入或删除文件前调用, 已存在的文件会先备份
+pub fn journal_write_file(path: &Path) -> anyhow::Result<()> {
+    with_active_journal(|journal| {
+        let backup = if path.is_file() {
+            Some(journal.backup_file(path)?)
+        } else {
+            None
+        };
+        Ok(Some(JournalAction::WriteFile {
+            path: path.to_path_buf(),
+            backup,
+        }))
+    })
+}
+
+pub fn journal_replace_link(link: &Path) -> anyhow::Result<()> {
+    with_active_journal(|_| {
+        Ok(Some(JournalAction::ReplaceLink {
+            link: link.to_path_buf(),
+            previous: fs::read_link(link).ok(),
+        }))
+    })
+}
+
+pub fn journal_create_link(path: &Path) -> anyhow::Result<()> {
+    with_active_journal(|_| {
+        Ok(Some(JournalAction::CreateLink {
+            path: path.to_path_buf(),
+        }))
+    })
+}
+
+pub fn journal_rename(from: &Path, to: &Path) -> anyhow::Result<()> {
+    with_active_journal(|_| {
+        Ok(Some(JournalAction::Rename {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        }))
+    })
+}
+
+/// 代替直接删除目录: 有活动事务时移到事务目录, 提交后才真正删除
+pub fn journal_stash_dir(path: &Path) -> anyhow::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let mut stashed = None;
+    with_active_journal(|journal| {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let backup = journal
+            .transaction_dir()
+            .join("stash")
+            .join(format!("{}-{file_name}", journal.actions.len()));
+        stashed = Some(backup.clone());
+        Ok(Some(JournalAction::StashDir {
+            path: path.to_path_buf(),
+            backup,
+        }))
+    })?;
+    match stashed {
+        Some(backup) => {
+            let stash_dir = backup.parent().unwrap_or(&backup);
+            fs::create_dir_all(stash_dir)
+                .context(format!("Failed to create {}", stash_dir.display()))?;
+            fs::rename(path, &backup).context(format!(
+                "Failed to move {} to {}",
+                path.display(),
+                backup.display()
+            ))?;
+        }
+        None => {
+            fs::remove_dir_all(path).context(format!("Failed to remove {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn journal_env_var(name: &str, global: bool) -> anyhow::Result<()> {
+    with_active_journal(|_| {
+        let previous = if global {
+            get_system_env_var(name).ok()
+        } else {
+            get_user_env_var(name).ok()
+        };
+        Ok(Some(JournalAction::SetEnv {
+            name: name.to_string(),
+            global,
+            previous,
+        }))
+    })
+}
+
+/// 上次安装中途退出(断电, 被终止)时残留的日志, 开始新的安装前先回滚
+pub fn recover_interrupted_install(app_name: &str, global: bool) -> anyhow::Result<()> {
+    let Some(journal) = InstallJournal::load(app_name, global)? else {
+        return Ok(());
+    };
+    eprintln!(
+        "{}",
+        format!(
+            "Found an interrupted install of '{app_name}' ({}), rolling it back",
+            journal.version
+        )
+        .dark_yellow()
+        .bold()
+    );
+    let errors = journal.rollback();
+    if !errors.is_empty() {
+        bail!(format_rollback_errors(app_name, &errors))
+    }
+    Ok(())
+}
+
+fn format_rollback_errors(app_name: &str, errors: &[anyhow::Error]) -> String {
+    let details = errors
+        .iter()
+        .map(|e| format!("  {e:#}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("Rollback of '{app_name}' was incomplete:\n{details}")
+}
+
+/// 安装事务, 提交前被 drop(例如 panic) 时自动回滚
+pub struct InstallTransaction {
+    app_name: String,
+    finished: bool,
+}
+
+impl InstallTransaction {
+    pub fn begin(app_name: &str, version: &str, global: bool) -> anyhow::Result<Self> {
+        recover_interrupted_install(app_name, global)?;
+        let journal = InstallJournal {
+            app_name: app_name.to_string(),
+            version: version.to_string(),
+            global,
+            actions: vec![],
+        };
+        journal.save()?;
+        ACTIVE_JOURNALS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(journal);
+        Ok(Self {
+            app_name: app_name.to_string(),
+            finished: false,
+        })
+    }
+
+    fn pop_journal(&mut self) -> Option<InstallJournal> {
+        self.finished = true;
+        ACTIVE_JOURNALS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .pop()
+    }
+
+    pub fn commit(mut self) {
+        if let Some(journal) = self.pop_journal() {
+            journal.cleanup();
+        }
+    }
+
+    pub fn rollback(mut self) -> anyhow::Result<()> {
+        let Some(journal) = self.pop_journal() else {
+            return Ok(());
+        };
+        eprintln!(
+            "{}",
+            format!(
+                "Rolling back {} change(s) made while installing '{}'",
+                journal.actions.len(),
+                self.app_name
+            )
+            .dark_yellow()
+            .bold()
+        );
+        let errors = journal.rollback();
+        if !errors.is_empty() {
+            bail!(format_rollback_errors(&self.app_name, &errors))
+        }
+        Ok(())
+    }
+}
+
+impl Drop for InstallTransaction {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        if let Some(journal) = self.pop_journal() {
+            for e in journal.rollback() {
+                eprintln!("{}", format!("{e:#}").dark_red().bold());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_transaction {
+    #[allow(unused_imports)]
+    use super::*;
+    use crate::test_util::test_temp_dir;
+
+    #[test]
+    fn test_journal_action_roundtrip() {
+        let journal = InstallJournal {
+            app_name: "demo".into(),
+            version: "1.0".into(),
+            global: false,
+            actions: vec![
+                JournalAction::CreateDir {
+                    path: PathBuf::from(r"A:\Scoop\apps\demo\1.0"),
+                },
+                JournalAction::ReplaceLink {
+                    link: PathBuf::from(r"A:\Scoop\apps\demo\current"),
+                    previous: Some(PathBuf::from(r"A:\Scoop\apps\demo\0.9")),
+                },
+                JournalAction::SetEnv {
+                    name: "DEMO_HOME".into(),
+                    global: false,
+                    previous: None,
+                },
+            ],
+        };
+        let content = serde_json::to_string(&journal).unwrap();
+        assert!(content.contains(r#""action":"replace_link""#));
+        let parsed: InstallJournal = serde_json::from_str(&content).unwrap();
+        assert_eq!(parsed.actions, journal.actions);
+    }
+
+    #[test]
+    fn test_undo_restores_backup_and_removes_new_dir() {
+        let root = test_temp_dir("transaction_undo");
+        let shim = root.join("demo.cmd");
+        let backup = root.join("0-demo.cmd");
+        fs::write(&shim, "new").unwrap();
+        fs::write(&backup, "old").unwrap();
+        let version_dir = root.join("1.0");
+        fs::create_dir_all(version_dir.join("bin")).unwrap();
+
+        let journal = InstallJournal {
+            app_name: "demo".into(),
+            version: "1.0".into(),
+            global: false,
+            actions: vec![
+                JournalAction::CreateDir {
+                    path: version_dir.clone(),
+                },
+                JournalAction::WriteFile {
+                    path: shim.clone(),
+                    backup: Some(backup),
+                },
+            ],
+        };
+        for action in journal.actions.iter().rev() {
+            InstallJournal::undo(action).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&shim).unwrap(), "old");
+        assert!(!version_dir.exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_undo_restores_stashed_dir() {
+        let root = test_temp_dir("transaction_stash");
+        let app_dir = root.join("demo");
+        let backup = root.join("stash").join("0-demo");
+        fs::create_dir_all(app_dir.join("1.0")).unwrap();
+        fs::create_dir_all(&backup).unwrap();
+        fs::write(backup.join("old.txt"), "old").unwrap();
+
+        let action = JournalAction::StashDir {
+            path: app_dir.clone(),
+            backup: backup.clone(),
+        };
+        InstallJournal::undo(&action).unwrap();
+        assert!(app_dir.join("old.txt").exists());
+        assert!(!app_dir.join("1.0").exists());
+        assert!(!backup.exists());
+        InstallJournal::undo(&action).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
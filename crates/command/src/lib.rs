@@ -1,5 +1,9 @@
+pub mod autoupdate;
 pub mod buckets;
+pub mod bundle;
 pub mod cat;
+pub mod checkver;
+pub mod depends;
 pub mod init_env;
 pub mod list;
 pub mod merge;
@@ -19,8 +23,15 @@ pub mod manifest;
 pub mod config;
 pub mod import;
 pub mod install;
+pub mod local;
+pub mod lockfile;
+pub mod offline;
+pub mod plan;
 pub mod reset;
 pub mod shim;
+pub mod sync;
+#[cfg(test)]
+mod test_util;
 pub mod uninstall;
 pub mod update;
 
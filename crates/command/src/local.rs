@@ -0,0 +1,238 @@
+//! 项目目录下的 `.hp-tools` 为每个 App 固定版本, shim 启动时从当前目录向上查找
+//!
+//! 只有 exe shim 会读取 `.hp-tools`, cmd/ps1/jar/py/sh 脚本 shim 始终运行 current.
+//! exe shim 需要 `.shim` 文件中的 `app = "<app>"` 行, 旧版本创建的 shim 没有该行, 需要 `hp reset <app>` 重建.
+//!
+//! ```text
+//! # <app> <version>
+//! nodejs 20.11.1
+//! python 3.11.9
+//! ```
+use crate::import::installed_version;
+use crate::init_env::{
+    get_app_current_dir, get_app_current_dir_global, get_app_version_dir,
+    get_app_version_dir_global, get_shims_root_dir, get_shims_root_dir_global,
+};
+use crate::install::{
+    install_app_specific_version, read_manifest_obj, shim_suffix, shim_target_name, InstallOptions,
+};
+use crate::plan::{collect_bin_shims, manifest_value};
+use crate::reset::reset_specific_version;
+use crate::utils::safe_check::unsafe_relative_path_reason;
+use anyhow::{bail, Context};
+use crossterm::style::Stylize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+pub const HP_TOOLS_FILE: &str = ".hp-tools";
+
+/// 解析 `<app> <version>` 行, 跳过空行和 `#` 注释
+pub fn parse_tool_versions(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let name = parts.next()?;
+            let version = parts.next()?;
+            (!name.starts_with('#')).then(|| (name.to_lowercase(), version.to_string()))
+        })
+        .collect()
+}
+
+/// 更新已有的 App 行, 没有时追加到末尾, 其余行保持不变
+pub fn set_tool_version(content: &str, app_name: &str, version: &str) -> String {
+    let entry = format!("{app_name} {version}");
+    let mut replaced = false;
+    let mut lines = content
+        .lines()
+        .map(|line| {
+            let name = line.split_whitespace().next().unwrap_or_default();
+            if !replaced && name.eq_ignore_ascii_case(app_name) {
+                replaced = true;
+                entry.clone()
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>();
+    if !replaced {
+        lines.push(entry);
+    }
+    lines.join("\n") + "\n"
+}
+
+/// 从 dir 向上查找最近的 `.hp-tools`
+pub fn find_tools_file(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(HP_TOOLS_FILE))
+        .find(|path| path.is_file())
+}
+
+pub fn is_version_installed(app_name: &str, version: &str, global: bool) -> bool {
+    if check_tool_entry(app_name, version).is_err() {
+        return false;
+    }
+    let version_dir = if global {
+        get_app_version_dir_global(app_name, version)
+    } else {
+        get_app_version_dir(app_name, version)
+    };
+    Path::new(&version_dir).exists()
+}
+
+fn check_tool_entry(app_name: &str, version: &str) -> anyhow::Result<()> {
+    let is_valid =
+        |s: &str| !s.is_empty() && !s.starts_with('#') && !s.contains(char::is_whitespace);
+    if !is_valid(app_name) || !is_valid(version) {
+        bail!("Invalid app name '{app_name}' or version '{version}'")
+    }
+    // 版本号会拼接为 `apps\<app>\<version>`, 只能是单个路径组件
+    if version.contains(['/', '\\']) {
+        bail!("Invalid version '{version}': path separator is not allowed")
+    }
+    if let Some(reason) = unsafe_relative_path_reason(version) {
+        bail!("Invalid version '{version}': {reason}")
+    }
+    Ok(())
+}
+
+/// 把 bin 中的 shim 分为 exe shim 与脚本 shim, 返回两者的名称
+pub fn split_pinnable_shims(bin: &Value) -> (Vec<String>, Vec<String>) {
+    let mut exe_shims = vec![];
+    let mut script_shims = vec![];
+    for (target, alias) in collect_bin_shims(bin) {
+        let Some(name) = shim_target_name(&target, alias.as_deref()) else {
+            continue;
+        };
+        match shim_suffix(&target).as_str() {
+            "exe" | "com" => exe_shims.push(name),
+            _ => script_shims.push(name),
+        }
+    }
+    (exe_shims, script_shims)
+}
+
+/// 没有 exe shim 时拒绝固定版本, 脚本 shim 与缺少 app 行的旧 shim 给出提示
+fn check_pinnable_shims(app_name: &str, global: bool) -> anyhow::Result<()> {
+    let (current_dir, shims_dir) = if global {
+        (
+            get_app_current_dir_global(app_name),
+            get_shims_root_dir_global(),
+        )
+    } else {
+        (get_app_current_dir(app_name), get_shims_root_dir())
+    };
+    let current_dir = Path::new(&current_dir);
+    let manifest = read_manifest_obj(&current_dir.join("manifest.json"))?;
+    let install_info = read_manifest_obj(&current_dir.join("install.json")).unwrap_or_default();
+    let arch = install_info["architecture"].as_str().unwrap_or("64bit");
+    let (exe_shims, script_shims) = manifest_value(&manifest, arch, "bin")
+        .map(split_pinnable_shims)
+        .unwrap_or_default();
+    if exe_shims.is_empty() {
+        bail!("'{app_name}' has no exe shims, {HP_TOOLS_FILE} only applies to exe shims")
+    }
+    if !script_shims.is_empty() {
+        eprintln!(
+            "{}",
+            format!(
+                "Script shims always run current and ignore {HP_TOOLS_FILE}: {}",
+                script_shims.join(", ")
+            )
+            .dark_yellow()
+            .bold()
+        );
+    }
+    let outdated = exe_shims.iter().any(|name| {
+        std::fs::read_to_string(Path::new(&shims_dir).join(format!("{name}.shim")))
+            .is_ok_and(|content| !content.lines().any(|line| line.starts_with("app = ")))
+    });
+    if outdated {
+        eprintln!(
+            "{}",
+            format!("Shims of '{app_name}' predate {HP_TOOLS_FILE}, run `hp reset {app_name}` to regenerate them")
+                .dark_yellow()
+                .bold()
+        );
+    }
+    Ok(())
+}
+
+/// 缺少该版本时先与已有版本并存安装, 再把 current 切回原版本并重建 shim, 最后写入 dir 下的 `.hp-tools`
+pub async fn pin_local_version(
+    dir: &Path,
+    app_name: &str,
+    version: &str,
+    global: bool,
+) -> anyhow::Result<()> {
+    check_tool_entry(app_name, version)?;
+    let previous = installed_version(app_name, global);
+    if previous.is_some() {
+        check_pinnable_shims(app_name, global)?;
+    }
+    if !is_version_installed(app_name, version, global) {
+        let options = if global {
+            vec![InstallOptions::Global]
+        } else {
+            vec![]
+        };
+        install_app_specific_version(app_name, version, &options).await?;
+        if let Some(previous) = previous.as_ref().filter(|previous| *previous != version) {
+            reset_specific_version(app_name, previous, global, true)
+                .context(format!("Failed to switch '{app_name}' back to {previous}"))?;
+        }
+    }
+    if previous.is_none() {
+        check_pinnable_shims(app_name, global)?;
+    }
+    let tools_file = dir.join(HP_TOOLS_FILE);
+    let content = std::fs::read_to_string(&tools_file).unwrap_or_default();
+    std::fs::write(&tools_file, set_tool_version(&content, app_name, version))
+        .context(format!("Failed to write {}", tools_file.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_local {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_tool_versions() {
+        let content = "# project tools\nnodejs 18.0.0\npython 3.11.9\n";
+        assert_eq!(
+            parse_tool_versions(content),
+            vec![
+                ("nodejs".to_string(), "18.0.0".to_string()),
+                ("python".to_string(), "3.11.9".to_string())
+            ]
+        );
+        assert_eq!(
+            set_tool_version(content, "nodejs", "20.11.1"),
+            "# project tools\nnodejs 20.11.1\npython 3.11.9\n"
+        );
+        assert_eq!(set_tool_version("", "go", "1.22.0"), "go 1.22.0\n");
+        assert!(check_tool_entry("nodejs", "20 .1").is_err());
+        assert!(check_tool_entry("nodejs", "20.11.1").is_ok());
+        for version in ["..", "...", "..\\..\\Windows", "20/../..", "20\\bin", "C:x"] {
+            assert!(check_tool_entry("nodejs", version).is_err(), "{version}");
+        }
+    }
+
+    #[test]
+    fn test_split_pinnable_shims() {
+        let bin = serde_json::json!([
+            "node.exe",
+            "npm.cmd",
+            ["npx.ps1", "npx"],
+            ["node.exe", "nodejs"]
+        ]);
+        assert_eq!(
+            split_pinnable_shims(&bin),
+            (
+                vec!["node".to_string(), "nodejs".to_string()],
+                vec!["npm".to_string(), "npx".to_string()]
+            )
+        );
+    }
+}
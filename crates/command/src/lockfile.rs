@@ -0,0 +1,404 @@
+//! `hp export --lock` 生成的锁文件, 记录精确版本、架构、下载地址、哈希与 bucket 提交,
+//! `hp import --locked` 据此在其他机器上还原完全相同的工具链
+use crate::buckets::{get_buckets_path, get_global_all_buckets_dir};
+use crate::init_env::{
+    get_apps_path, get_apps_path_global, get_buckets_root_dir_path,
+    get_buckets_root_dir_path_global,
+};
+use anyhow::{bail, Context};
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+
+pub const LOCKFILE_VERSION: u32 = 1;
+
+/// 顶层字段与普通导出文件一致, 普通导出文件缺少的锁定信息读取为空
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lockfile {
+    #[serde(default)]
+    pub lockfile_version: u32,
+    #[serde(default)]
+    pub buckets: Vec<LockedBucket>,
+    #[serde(default)]
+    pub apps: Vec<LockedApp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LockedBucket {
+    pub name: String,
+    pub source: String,
+    #[serde(default)]
+    pub commit: String,
+    #[serde(default)]
+    pub global: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LockedApp {
+    pub name: String,
+    /// 安装来源的 bucket 名称
+    pub source: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub architecture: String,
+    #[serde(default)]
+    pub held: bool,
+    #[serde(default)]
+    pub global: bool,
+    #[serde(default)]
+    pub urls: Vec<String>,
+    #[serde(default)]
+    pub hashes: Vec<String>,
+}
+
+impl Lockfile {
+    /// 读取 `hp export` 或 `hp export --lock` 导出的文件
+    pub fn parse_export(content: &str) -> anyhow::Result<Self> {
+        serde_json::from_str(content.trim_start_matches('\u{feff}'))
+            .context("Invalid export file, export it with `hp export`")
+    }
+
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        let lockfile = Self::parse_export(content)
+            .context("Invalid lockfile, export it with `hp export --lock`")?;
+        if lockfile.lockfile_version != LOCKFILE_VERSION {
+            bail!(
+                "Unsupported lockfile version {}, expected {}",
+                lockfile.lockfile_version,
+                LOCKFILE_VERSION
+            )
+        }
+        Ok(lockfile)
+    }
+
+    pub fn find_bucket(&self, name: &str, global: bool) -> Option<&LockedBucket> {
+        self.buckets
+            .iter()
+            .find(|bucket| bucket.name == name && bucket.global == global)
+            .or_else(|| self.buckets.iter().find(|bucket| bucket.name == name))
+    }
+}
+
+fn string_or_array(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => vec![s.clone()],
+        Value::Array(arr) => arr
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// 清单中指定架构的下载地址与哈希, 架构块中没有时使用顶层字段
+pub fn manifest_urls_and_hashes(manifest: &Value, arch: &str) -> (Vec<String>, Vec<String>) {
+    let arch_block = &manifest["architecture"][arch];
+    let field = |key: &str| {
+        let value = string_or_array(&arch_block[key]);
+        if value.is_empty() {
+            string_or_array(&manifest[key])
+        } else {
+            value
+        }
+    };
+    let hashes = field("hash")
+        .into_iter()
+        .map(|hash| hash.trim().to_lowercase())
+        .collect();
+    (field("url"), hashes)
+}
+
+/// 锁定的地址与哈希必须与清单一致, 否则无法保证还原出相同的安装包
+pub fn check_locked_manifest(app: &LockedApp, manifest: &Value) -> anyhow::Result<()> {
+    let version = manifest["version"].as_str().unwrap_or_default();
+    if version != app.version {
+        bail!(
+            "Manifest of '{}' has version '{version}', locked version is '{}'",
+            app.name,
+            app.version
+        )
+    }
+    let (urls, hashes) = manifest_urls_and_hashes(manifest, &app.architecture);
+    if !app.urls.is_empty() && urls != app.urls {
+        bail!("Download urls of '{}' differ from the lockfile", app.name)
+    }
+    if !app.hashes.is_empty() && hashes != app.hashes {
+        bail!("Hashes of '{}' differ from the lockfile", app.name)
+    }
+    Ok(())
+}
+
+pub fn get_repo_head_commit(path: &Path) -> anyhow::Result<String> {
+    let repo =
+        Repository::open(path).context(format!("Failed to open repository {}", path.display()))?;
+    let commit = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .context(format!("Failed to read HEAD of {}", path.display()))?;
+    Ok(commit.id().to_string())
+}
+
+/// 不修改工作区, 直接从指定提交中读取 `bucket/<app>.json`
+pub fn read_manifest_at_commit(
+    bucket_dir: &Path,
+    commit: &str,
+    app_name: &str,
+) -> anyhow::Result<String> {
+    let repo = Repository::open(bucket_dir)
+        .context(format!("Failed to open bucket {}", bucket_dir.display()))?;
+    let oid = Oid::from_str(commit).context(format!("Invalid commit '{commit}'"))?;
+    let commit = repo.find_commit(oid).context(format!(
+        "Commit '{commit}' not found in {}, run `hp update` to fetch it",
+        bucket_dir.display()
+    ))?;
+    let tree = commit.tree()?;
+    let manifest = format!("bucket/{app_name}.json");
+    let entry = tree
+        .get_path(Path::new(&manifest))
+        .context(format!("'{manifest}' not found at commit {}", commit.id()))?;
+    let blob = entry.to_object(&repo)?.peel_to_blob()?;
+    Ok(String::from_utf8_lossy(blob.content()).to_string())
+}
+
+fn collect_locked_buckets(bucket_dirs: Vec<String>, global: bool) -> Vec<LockedBucket> {
+    bucket_dirs
+        .into_iter()
+        .filter_map(|dir| {
+            let path = Path::new(&dir);
+            let name = path.file_name()?.to_str()?.to_string();
+            let repo = Repository::open(path).ok()?;
+            let source = repo.find_remote("origin").ok()?.url()?.to_string();
+            let commit = get_repo_head_commit(path).ok()?;
+            Some(LockedBucket {
+                name,
+                source,
+                commit,
+                global,
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn collect_locked_apps(apps_dir: &str, global: bool) -> anyhow::Result<Vec<LockedApp>> {
+    let mut apps = vec![];
+    if !Path::new(apps_dir).is_dir() {
+        return Ok(apps);
+    }
+    for entry in read_dir(apps_dir).context(format!("Failed to read directory {apps_dir}"))? {
+        let path = entry?.path();
+        let current = path.join("current");
+        let (install_file, manifest_file) =
+            (current.join("install.json"), current.join("manifest.json"));
+        if !install_file.is_file() || !manifest_file.is_file() {
+            continue;
+        }
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let read_json = |file: &PathBuf| -> anyhow::Result<Value> {
+            let content = std::fs::read_to_string(file)
+                .context(format!("Failed to read {}", file.display()))?;
+            serde_json::from_str(content.trim_start_matches('\u{feff}'))
+                .context(format!("Failed to parse {}", file.display()))
+        };
+        let install_info = read_json(&install_file)?;
+        let manifest = read_json(&manifest_file)?;
+        let architecture = install_info["architecture"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let (urls, hashes) = manifest_urls_and_hashes(&manifest, &architecture);
+        apps.push(LockedApp {
+            name,
+            source: install_info["bucket"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            version: manifest["version"].as_str().unwrap_or_default().to_string(),
+            architecture,
+            held: install_info["hold"].as_bool().unwrap_or(false),
+            global,
+            urls,
+            hashes,
+        });
+    }
+    Ok(apps)
+}
+
+/// 收集用户与全局安装的全部 App 与 bucket
+pub fn create_lockfile(config: Option<Value>) -> anyhow::Result<Lockfile> {
+    let mut buckets = collect_locked_buckets(get_buckets_path()?, false);
+    buckets.extend(collect_locked_buckets(get_global_all_buckets_dir()?, true));
+    let mut apps = collect_locked_apps(&get_apps_path(), false)?;
+    apps.extend(collect_locked_apps(&get_apps_path_global(), true)?);
+    Ok(Lockfile {
+        lockfile_version: LOCKFILE_VERSION,
+        buckets,
+        apps,
+        config,
+    })
+}
+
+/// 清单 depends 中的 App 名称, 去掉 bucket 前缀
+pub fn manifest_depends(manifest: &Value) -> Vec<String> {
+    string_or_array(&manifest["depends"])
+        .into_iter()
+        .map(|depend| {
+            let name = depend.rsplit('/').next().unwrap_or_default();
+            name.trim().to_lowercase()
+        })
+        .collect()
+}
+
+/// 优先使用 bucket 工作区中版本一致的清单, 否则读取锁定提交中的清单,
+/// 写入 manifest_dir 后返回路径与依赖
+pub fn resolve_locked_manifest(
+    lockfile: &Lockfile,
+    app: &LockedApp,
+    manifest_dir: &Path,
+) -> anyhow::Result<(PathBuf, Vec<String>)> {
+    let bucket_root = if app.global {
+        get_buckets_root_dir_path_global()
+    } else {
+        get_buckets_root_dir_path()
+    };
+    let bucket_dir = Path::new(&bucket_root).join(&app.source);
+    let current = bucket_dir.join("bucket").join(format!("{}.json", app.name));
+    let content = match std::fs::read_to_string(&current) {
+        Ok(content) if locked_version_matches(&content, &app.version) => content,
+        _ => {
+            let Some(bucket) = lockfile.find_bucket(&app.source, app.global) else {
+                bail!(
+                    "Bucket '{}' of '{}' is not recorded in the lockfile",
+                    app.source,
+                    app.name
+                )
+            };
+            read_manifest_at_commit(&bucket_dir, &bucket.commit, &app.name)?
+        }
+    };
+    let manifest: Value = serde_json::from_str(content.trim_start_matches('\u{feff}'))
+        .context(format!("Failed to parse manifest of '{}'", app.name))?;
+    check_locked_manifest(app, &manifest)?;
+    let manifest_path = manifest_dir.join(format!("{}.json", app.name));
+    std::fs::write(&manifest_path, content)
+        .context(format!("Failed to write {}", manifest_path.display()))?;
+    Ok((manifest_path, manifest_depends(&manifest)))
+}
+
+fn locked_version_matches(content: &str, version: &str) -> bool {
+    serde_json::from_str::<Value>(content.trim_start_matches('\u{feff}'))
+        .map(|manifest| manifest["version"].as_str() == Some(version))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod test_lockfile {
+    #[allow(unused_imports)]
+    use super::*;
+    use crate::test_util::test_temp_dir;
+
+    #[test]
+    fn test_manifest_urls_and_hashes() {
+        let manifest: Value = serde_json::from_str(
+            r#"{
+                "version": "1.0",
+                "url": "https://example.com/app.zip",
+                "hash": "ABC",
+                "architecture": {
+                    "64bit": { "url": ["https://example.com/x64.zip", "https://example.com/x64.7z"],
+                               "hash": ["sha1:AA", "bb"] }
+                }
+            }"#,
+        )
+        .unwrap();
+        let (urls, hashes) = manifest_urls_and_hashes(&manifest, "64bit");
+        assert_eq!(
+            urls,
+            ["https://example.com/x64.zip", "https://example.com/x64.7z"]
+        );
+        assert_eq!(hashes, ["sha1:aa", "bb"]);
+        let (urls, hashes) = manifest_urls_and_hashes(&manifest, "32bit");
+        assert_eq!(urls, ["https://example.com/app.zip"]);
+        assert_eq!(hashes, ["abc"]);
+        assert!(manifest_depends(&manifest).is_empty());
+        let manifest_with_depends: Value =
+            serde_json::from_str(r#"{"depends": ["main/7zip", "Dark"]}"#).unwrap();
+        assert_eq!(manifest_depends(&manifest_with_depends), ["7zip", "dark"]);
+
+        let mut app = LockedApp {
+            name: "app".into(),
+            source: "main".into(),
+            version: "1.0".into(),
+            architecture: "32bit".into(),
+            held: false,
+            global: false,
+            urls,
+            hashes,
+        };
+        assert!(check_locked_manifest(&app, &manifest).is_ok());
+        app.hashes = vec!["def".into()];
+        assert!(check_locked_manifest(&app, &manifest).is_err());
+        app.version = "2.0".into();
+        assert!(check_locked_manifest(&app, &manifest).is_err());
+    }
+
+    #[test]
+    fn test_parse_export() {
+        let export = r#"{
+            "buckets": [{ "Name": "main", "Source": "https://github.com/ScoopInstaller/Main", "Updated": "", "Manifests": 1 }],
+            "apps": [{ "Name": "git", "Source": "main", "Updated": "", "Version": "2.47.0" }]
+        }"#;
+        let lockfile = Lockfile::parse_export(export).unwrap();
+        assert_eq!(lockfile.buckets[0].commit, "");
+        assert_eq!(lockfile.apps[0].version, "2.47.0");
+        assert!(!lockfile.apps[0].held);
+        assert!(Lockfile::parse(export).is_err());
+    }
+
+    #[test]
+    fn test_read_manifest_at_commit() {
+        let dir = test_temp_dir("lockfile");
+        let repo = Repository::init(&dir).unwrap();
+        let signature = git2::Signature::now("hp", "hp@example.com").unwrap();
+        let commit_manifest = |version: &str, parent: Option<Oid>| {
+            std::fs::create_dir_all(dir.join("bucket")).unwrap();
+            std::fs::write(
+                dir.join("bucket/app.json"),
+                format!(r#"{{"version": "{version}"}}"#),
+            )
+            .unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new("bucket/app.json")).unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let parents = parent
+                .map(|oid| vec![repo.find_commit(oid).unwrap()])
+                .unwrap_or_default();
+            let parents = parents.iter().collect::<Vec<_>>();
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                version,
+                &tree,
+                &parents,
+            )
+            .unwrap()
+        };
+        let first = commit_manifest("1.0", None);
+        commit_manifest("2.0", Some(first));
+
+        assert_eq!(
+            read_manifest_at_commit(&dir, &first.to_string(), "app").unwrap(),
+            r#"{"version": "1.0"}"#
+        );
+        assert_ne!(get_repo_head_commit(&dir).unwrap(), first.to_string());
+        assert!(read_manifest_at_commit(&dir, &first.to_string(), "other").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
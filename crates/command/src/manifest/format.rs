@@ -0,0 +1,408 @@
+use crate::manifest::install_manifest::InstallManifest;
+use crate::manifest::manifest_deserialize::ManifestObj;
+use crate::utils::detect_encoding::detect_encoding;
+use anyhow::{bail, Context};
+use encoding::all::GBK;
+use encoding::{DecoderTrap, Encoding};
+use serde_json::Value;
+use std::path::Path;
+
+/// 与 Scoop formatjson 一致的顶层字段顺序, 未知字段按字母序排在最后
+const ROOT_KEY_ORDER: &[&str] = &[
+    "$schema",
+    "version",
+    "description",
+    "homepage",
+    "license",
+    "notes",
+    "depends",
+    "suggest",
+    "architecture",
+    "url",
+    "hash",
+    "cookie",
+    "extract_dir",
+    "extract_to",
+    "innosetup",
+    "pre_install",
+    "installer",
+    "post_install",
+    "pre_uninstall",
+    "uninstaller",
+    "post_uninstall",
+    "bin",
+    "shortcuts",
+    "env_add_path",
+    "env_set",
+    "persist",
+    "psmodule",
+    "checkver",
+    "autoupdate",
+];
+
+const ARCH_KEY_ORDER: &[&str] = &[
+    "url",
+    "hash",
+    "extract_dir",
+    "extract_to",
+    "pre_install",
+    "installer",
+    "post_install",
+    "pre_uninstall",
+    "uninstaller",
+    "post_uninstall",
+    "bin",
+    "shortcuts",
+    "env_add_path",
+    "env_set",
+    "checkver",
+];
+
+const AUTOUPDATE_KEY_ORDER: &[&str] = &[
+    "notes",
+    "license",
+    "architecture",
+    "url",
+    "hash",
+    "extract_dir",
+    "extract_to",
+    "pre_install",
+    "installer",
+    "post_install",
+    "bin",
+    "shortcuts",
+    "env_add_path",
+    "env_set",
+    "persist",
+    "psmodule",
+];
+
+const CHECKVER_KEY_ORDER: &[&str] = &[
+    "url",
+    "github",
+    "sourceforge",
+    "useragent",
+    "script",
+    "jsonpath",
+    "jp",
+    "xpath",
+    "regex",
+    "re",
+    "reverse",
+    "replace",
+];
+
+const ARCHITECTURE_ORDER: &[&str] = &["64bit", "32bit", "arm64"];
+
+/// schema 中类型为 string | string[] 的字段, 只在顶层、架构块与 autoupdate 中出现
+const STRING_OR_ARRAY_KEYS: &[&str] = &[
+    "notes",
+    "depends",
+    "url",
+    "hash",
+    "extract_dir",
+    "extract_to",
+    "pre_install",
+    "post_install",
+    "pre_uninstall",
+    "post_uninstall",
+    "bin",
+    "env_add_path",
+    "persist",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// Scoop 官方 bucket 的 .editorconfig 约定
+    #[default]
+    Crlf,
+    Lf,
+}
+
+impl LineEnding {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Lf => "\n",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyOrder {
+    Root,
+    Architecture,
+    ArchBlock,
+    Autoupdate,
+    Checkver,
+    Alphabetical,
+}
+
+impl KeyOrder {
+    fn known_keys(&self) -> &'static [&'static str] {
+        match self {
+            KeyOrder::Root => ROOT_KEY_ORDER,
+            KeyOrder::Architecture => ARCHITECTURE_ORDER,
+            KeyOrder::ArchBlock => ARCH_KEY_ORDER,
+            KeyOrder::Autoupdate => AUTOUPDATE_KEY_ORDER,
+            KeyOrder::Checkver => CHECKVER_KEY_ORDER,
+            KeyOrder::Alphabetical => &[],
+        }
+    }
+
+    fn child(&self, key: &str) -> KeyOrder {
+        match (self, key) {
+            (KeyOrder::Root, "architecture") | (KeyOrder::Autoupdate, "architecture") => {
+                KeyOrder::Architecture
+            }
+            (KeyOrder::Root, "autoupdate") => KeyOrder::Autoupdate,
+            (KeyOrder::Root, "checkver") | (KeyOrder::ArchBlock, "checkver") => KeyOrder::Checkver,
+            (KeyOrder::Architecture, _) => KeyOrder::ArchBlock,
+            _ => KeyOrder::Alphabetical,
+        }
+    }
+
+    fn is_string_or_array(&self, key: &str) -> bool {
+        matches!(
+            self,
+            KeyOrder::Root | KeyOrder::ArchBlock | KeyOrder::Autoupdate
+        ) && STRING_OR_ARRAY_KEYS.contains(&key)
+    }
+
+    /// `#` 开头的注释键始终位于最前, 已知字段按约定顺序, 其余按字母序
+    fn sort_keys<'a>(&self, obj: &'a serde_json::Map<String, Value>) -> Vec<&'a String> {
+        let known = self.known_keys();
+        let mut keys = obj.keys().collect::<Vec<_>>();
+        keys.sort_by_key(|key| {
+            let rank = if key.as_str() == "$schema" {
+                0
+            } else if key.starts_with('#') {
+                1
+            } else if let Some(index) = known.iter().position(|k| *k == key.as_str()) {
+                2 + index
+            } else {
+                2 + known.len()
+            };
+            (rank, key.to_string())
+        });
+        keys
+    }
+}
+
+/// string | string[] 字段中仅包含一个字符串的数组折叠为字符串, 其余数组保持原样
+fn normalize_value(value: &Value, order: KeyOrder) -> Value {
+    match value {
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .map(|(key, value)| {
+                    let value = match value.as_array().map(|arr| arr.as_slice()) {
+                        Some([Value::String(s)]) if order.is_string_or_array(key) => {
+                            Value::String(s.clone())
+                        }
+                        _ => normalize_value(value, order.child(key)),
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+fn write_value(out: &mut String, value: &Value, order: KeyOrder, indent: usize, eol: &str) {
+    let pad = "    ".repeat(indent + 1);
+    let close_pad = "    ".repeat(indent);
+    match value {
+        Value::Array(arr) if !arr.is_empty() => {
+            out.push('[');
+            out.push_str(eol);
+            for (i, item) in arr.iter().enumerate() {
+                out.push_str(&pad);
+                write_value(out, item, KeyOrder::Alphabetical, indent + 1, eol);
+                if i + 1 < arr.len() {
+                    out.push(',');
+                }
+                out.push_str(eol);
+            }
+            out.push_str(&close_pad);
+            out.push(']');
+        }
+        Value::Object(obj) if !obj.is_empty() => {
+            out.push('{');
+            out.push_str(eol);
+            let keys = order.sort_keys(obj);
+            for (i, key) in keys.iter().enumerate() {
+                out.push_str(&pad);
+                out.push_str(&Value::String(key.to_string()).to_string());
+                out.push_str(": ");
+                write_value(out, &obj[key.as_str()], order.child(key), indent + 1, eol);
+                if i + 1 < keys.len() {
+                    out.push(',');
+                }
+                out.push_str(eol);
+            }
+            out.push_str(&close_pad);
+            out.push('}');
+        }
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+/// 将清单格式化为规范文本, 4 空格缩进并以换行结尾
+pub fn format_manifest_value(manifest: &ManifestObj, line_ending: LineEnding) -> String {
+    let eol = line_ending.as_str();
+    let mut out = String::new();
+    write_value(
+        &mut out,
+        &normalize_value(manifest, KeyOrder::Root),
+        KeyOrder::Root,
+        0,
+        eol,
+    );
+    out.push_str(eol);
+    out
+}
+
+/// 通过 BOM 判断编码, 无 BOM 时依次尝试 UTF-8 与 GBK, 返回去掉 BOM 的文本
+pub fn read_manifest_text<P: AsRef<Path>>(path: P) -> anyhow::Result<String> {
+    let path = path.as_ref();
+    let bytes =
+        std::fs::read(path).context(format!("Failed to read manifest {}", path.display()))?;
+    let text = match detect_encoding(&mut bytes.as_slice()) {
+        Some(encoding) => encoding
+            .decode(&bytes, DecoderTrap::Strict)
+            .map_err(|e| anyhow::anyhow!("Failed to decode {}: {e}", path.display()))?,
+        None => match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(e) => GBK
+                .decode(e.as_bytes(), DecoderTrap::Strict)
+                .map_err(|e| anyhow::anyhow!("Failed to decode {}: {e}", path.display()))?,
+        },
+    };
+    Ok(text.trim_start_matches('\u{feff}').to_string())
+}
+
+/// 格式化前确认清单可被 InstallManifest 解析, 避免改写本就无效的清单
+pub fn format_manifest_str(content: &str, line_ending: LineEnding) -> anyhow::Result<String> {
+    let manifest = serde_json::from_str::<ManifestObj>(content).context("Invalid manifest json")?;
+    if !manifest.is_object() {
+        bail!("Manifest must be a json object")
+    }
+    serde_json::from_value::<InstallManifest>(manifest.clone())
+        .context("Manifest can not be deserialized to InstallManifest")?;
+    Ok(format_manifest_value(&manifest, line_ending))
+}
+
+/// 返回文件是否需要格式化, `check` 为 false 时直接写回
+pub fn format_manifest_file<P: AsRef<Path>>(
+    path: P,
+    line_ending: LineEnding,
+    check: bool,
+) -> anyhow::Result<bool> {
+    let path = path.as_ref();
+    let original = std::fs::read(path).context(format!("Failed to read {}", path.display()))?;
+    let content = read_manifest_text(path)?;
+    let formatted = format_manifest_str(&content, line_ending)
+        .context(format!("Failed to format {}", path.display()))?;
+    if original == formatted.as_bytes() {
+        return Ok(false);
+    }
+    if !check {
+        std::fs::write(path, formatted).context(format!("Failed to write {}", path.display()))?;
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test_format {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_canonical_key_order() {
+        let manifest = serde_json::json!({
+            "bin": ["demo.exe"],
+            "url": "https://example.com/demo.zip",
+            "zzz": 1,
+            "version": "1.0",
+            "##": "comment",
+            "checkver": { "regex": "v([\\d.]+)", "url": "https://example.com" },
+            "architecture": {
+                "32bit": { "hash": "b", "url": "https://example.com/32.zip" },
+                "64bit": { "url": "https://example.com/64.zip" }
+            },
+            "shortcuts": [["demo.exe", "Demo"]]
+        });
+        let formatted = format_manifest_value(&manifest, LineEnding::Lf);
+        let expect = r###"{
+    "##": "comment",
+    "version": "1.0",
+    "architecture": {
+        "64bit": {
+            "url": "https://example.com/64.zip"
+        },
+        "32bit": {
+            "url": "https://example.com/32.zip",
+            "hash": "b"
+        }
+    },
+    "url": "https://example.com/demo.zip",
+    "bin": "demo.exe",
+    "shortcuts": [
+        [
+            "demo.exe",
+            "Demo"
+        ]
+    ],
+    "checkver": {
+        "url": "https://example.com",
+        "regex": "v([\\d.]+)"
+    },
+    "zzz": 1
+}
+"###;
+        assert_eq!(formatted, expect);
+    }
+
+    #[test]
+    fn test_format_is_idempotent() {
+        let content = "{\"version\":\"1.0\",\"url\":[\"https://example.com/a.zip\"],\"hash\":[]}";
+        let first = format_manifest_str(content, LineEnding::Crlf).unwrap();
+        assert!(first.contains("\r\n"));
+        assert!(first.contains("\"hash\": []"));
+        let second = format_manifest_str(&first, LineEnding::Crlf).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_collapse_only_string_or_array_fields() {
+        let manifest = serde_json::json!({
+            "bin": [["demo.exe"]],
+            "persist": [["conf.ini"]],
+            "installer": { "args": ["/S"] },
+            "checkver": { "script": ["return 1"] },
+            "architecture": { "64bit": { "url": ["https://example.com/64.zip"] } },
+            "autoupdate": { "hash": { "url": ["$url.sha256"] }, "extract_dir": ["demo"] }
+        });
+        let normalized = normalize_value(&manifest, KeyOrder::Root);
+        assert_eq!(normalized["bin"], serde_json::json!([["demo.exe"]]));
+        assert_eq!(normalized["persist"], serde_json::json!([["conf.ini"]]));
+        assert_eq!(normalized["installer"]["args"], serde_json::json!(["/S"]));
+        assert_eq!(
+            normalized["checkver"]["script"],
+            serde_json::json!(["return 1"])
+        );
+        assert_eq!(
+            normalized["architecture"]["64bit"]["url"],
+            serde_json::json!("https://example.com/64.zip")
+        );
+        assert_eq!(
+            normalized["autoupdate"]["hash"]["url"],
+            serde_json::json!(["$url.sha256"])
+        );
+        assert_eq!(
+            normalized["autoupdate"]["extract_dir"],
+            serde_json::json!("demo")
+        );
+    }
+}
@@ -0,0 +1,676 @@
+use crate::autoupdate::{generate_manifest_for_version, get_version_substitutions, substitute_str};
+use crate::manifest::manifest_deserialize::{AutoUpdateStruct, ManifestObj};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Scoop manifest schema 中的顶层字段, `#` 开头的键视为注释
+const ROOT_KEYS: &[&str] = &[
+    "$schema",
+    "architecture",
+    "autoupdate",
+    "bin",
+    "checkver",
+    "cookie",
+    "depends",
+    "description",
+    "env_add_path",
+    "env_set",
+    "extract_dir",
+    "extract_to",
+    "hash",
+    "homepage",
+    "innosetup",
+    "installer",
+    "license",
+    "notes",
+    "persist",
+    "post_install",
+    "post_uninstall",
+    "pre_install",
+    "pre_uninstall",
+    "psmodule",
+    "shortcuts",
+    "suggest",
+    "uninstaller",
+    "url",
+    "version",
+];
+
+const ARCH_KEYS: &[&str] = &[
+    "bin",
+    "checkver",
+    "env_add_path",
+    "env_set",
+    "extract_dir",
+    "extract_to",
+    "hash",
+    "installer",
+    "post_install",
+    "post_uninstall",
+    "pre_install",
+    "pre_uninstall",
+    "shortcuts",
+    "uninstaller",
+    "url",
+];
+
+const AUTOUPDATE_KEYS: &[&str] = &[
+    "architecture",
+    "bin",
+    "env_add_path",
+    "env_set",
+    "extract_dir",
+    "extract_to",
+    "hash",
+    "installer",
+    "license",
+    "note",
+    "notes",
+    "persist",
+    "post_install",
+    "pre_install",
+    "psmodule",
+    "shortcuts",
+    "url",
+];
+
+const CHECKVER_KEYS: &[&str] = &[
+    "github",
+    "jp",
+    "jsonpath",
+    "re",
+    "regex",
+    "replace",
+    "reverse",
+    "script",
+    "sourceforge",
+    "url",
+    "useragent",
+    "xpath",
+];
+
+const ARCHITECTURES: [&str; 3] = ["64bit", "32bit", "arm64"];
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct LintIssue {
+    /// RFC 6901 JSON Pointer, 空字符串表示整个清单
+    pub pointer: String,
+    pub severity: LintSeverity,
+    pub rule: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct LintReport {
+    pub path: String,
+    pub issues: Vec<LintIssue>,
+}
+
+impl LintReport {
+    pub fn error_count(&self) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == LintSeverity::Error)
+            .count()
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.issues.len() - self.error_count()
+    }
+}
+
+struct Linter {
+    issues: Vec<LintIssue>,
+}
+
+impl Linter {
+    fn error(&mut self, pointer: &str, rule: &str, message: impl Into<String>) {
+        self.push(pointer, LintSeverity::Error, rule, message.into());
+    }
+
+    fn warning(&mut self, pointer: &str, rule: &str, message: impl Into<String>) {
+        self.push(pointer, LintSeverity::Warning, rule, message.into());
+    }
+
+    fn push(&mut self, pointer: &str, severity: LintSeverity, rule: &str, message: String) {
+        self.issues.push(LintIssue {
+            pointer: pointer.to_string(),
+            severity,
+            rule: rule.to_string(),
+            message,
+        });
+    }
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn child_pointer(parent: &str, key: &str) -> String {
+    format!("{}/{}", parent, escape_pointer(key))
+}
+
+/// 字符串或字符串数组统一展开, 返回元素及其 JSON Pointer
+fn string_items(value: &Value, pointer: &str) -> Option<Vec<(String, String)>> {
+    match value {
+        Value::String(s) => Some(vec![(s.clone(), pointer.to_string())]),
+        Value::Array(arr) => arr
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                item.as_str()
+                    .map(|s| (s.to_string(), format!("{pointer}/{i}")))
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+fn check_unknown_keys(linter: &mut Linter, value: &Value, pointer: &str, known: &[&str]) {
+    let Some(obj) = value.as_object() else {
+        return;
+    };
+    for key in obj.keys() {
+        if key.starts_with('#') || known.contains(&key.as_str()) {
+            continue;
+        }
+        linter.warning(
+            &child_pointer(pointer, key),
+            "unknown-key",
+            format!("unknown key '{key}'"),
+        );
+    }
+}
+
+fn check_hash_format(linter: &mut Linter, hash: &str, pointer: &str) {
+    let (algorithm, value) = match hash.split_once(':') {
+        Some((algorithm, value)) => (algorithm.to_lowercase(), value),
+        None => ("sha256".to_string(), hash),
+    };
+    let expected_len = match algorithm.as_str() {
+        "md5" => 32,
+        "sha1" => 40,
+        "sha256" => 64,
+        "sha512" => 128,
+        _ => {
+            linter.error(
+                pointer,
+                "invalid-hash",
+                format!("unsupported hash algorithm '{algorithm}'"),
+            );
+            return;
+        }
+    };
+    if value.len() != expected_len || !value.chars().all(|c| c.is_ascii_hexdigit()) {
+        linter.error(
+            pointer,
+            "invalid-hash",
+            format!(
+                "'{hash}' is not a valid {algorithm} hash, expected {expected_len} hex characters"
+            ),
+        );
+    }
+}
+
+/// 检查 url 与 hash 的格式以及数量是否一一对应
+fn check_url_and_hash(linter: &mut Linter, block: &Value, pointer: &str, is_nightly: bool) {
+    let url_pointer = child_pointer(pointer, "url");
+    let hash_pointer = child_pointer(pointer, "hash");
+    let urls = match block.get("url") {
+        None => return,
+        Some(url) => match string_items(url, &url_pointer) {
+            Some(urls) => urls,
+            None => {
+                linter.error(
+                    &url_pointer,
+                    "invalid-type",
+                    "url must be string or string array",
+                );
+                return;
+            }
+        },
+    };
+    for (url, pointer) in urls.iter() {
+        let without_fragment = url.split('#').next().unwrap_or_default();
+        if url::Url::parse(without_fragment).is_err() {
+            linter.error(
+                pointer,
+                "invalid-url",
+                format!("'{url}' is not a valid url"),
+            );
+        }
+    }
+
+    let Some(hash) = block.get("hash") else {
+        if !is_nightly {
+            linter.error(
+                &hash_pointer,
+                "missing-hash",
+                "url is defined but hash is missing",
+            );
+        }
+        return;
+    };
+    let Some(hashes) = string_items(hash, &hash_pointer) else {
+        linter.error(
+            &hash_pointer,
+            "invalid-type",
+            "hash must be string or string array",
+        );
+        return;
+    };
+    for (hash, pointer) in hashes.iter() {
+        check_hash_format(linter, hash, pointer);
+    }
+    if hashes.len() != urls.len() {
+        linter.error(
+            &hash_pointer,
+            "hash-count-mismatch",
+            format!("{} url(s) but {} hash(es)", urls.len(), hashes.len()),
+        );
+    }
+}
+
+fn bin_targets(bin: &Value, pointer: &str) -> Vec<(String, String)> {
+    match bin {
+        Value::String(s) => vec![(s.clone(), pointer.to_string())],
+        Value::Array(arr) => arr
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| match item {
+                Value::String(s) => Some((s.clone(), format!("{pointer}/{i}"))),
+                Value::Array(inner) => inner
+                    .first()
+                    .and_then(|s| s.as_str())
+                    .map(|s| (s.to_string(), format!("{pointer}/{i}/0"))),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// extract_dir 中的目录在解压时会被提升为 App 根目录, bin 不应再以其开头
+fn check_bin(linter: &mut Linter, block: &Value, pointer: &str, extract_dir: Option<&Value>) {
+    let Some(bin) = block.get("bin") else {
+        return;
+    };
+    let bin_pointer = child_pointer(pointer, "bin");
+    if !matches!(bin, Value::String(_) | Value::Array(_)) {
+        linter.error(&bin_pointer, "invalid-type", "bin must be string or array");
+        return;
+    }
+    let extract_dirs = extract_dir
+        .and_then(|dir| string_items(dir, ""))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(dir, _)| dir.replace('/', "\\").trim_matches('\\').to_lowercase())
+        .filter(|dir| !dir.is_empty())
+        .collect::<Vec<_>>();
+    for (target, pointer) in bin_targets(bin, &bin_pointer) {
+        let normalized = target.replace('/', "\\").to_lowercase();
+        if normalized.split('\\').any(|part| part == "..") || Path::new(&target).is_absolute() {
+            linter.error(
+                &pointer,
+                "invalid-bin",
+                format!("bin '{target}' must be a relative path inside the app directory"),
+            );
+            continue;
+        }
+        if let Some(dir) = extract_dirs
+            .iter()
+            .find(|dir| normalized.starts_with(&format!("{dir}\\")))
+        {
+            linter.error(
+                &pointer,
+                "bin-outside-extract-dir",
+                format!(
+                    "bin '{target}' starts with extract_dir '{dir}', which is flattened into the app directory"
+                ),
+            );
+        }
+    }
+}
+
+fn check_checkver(linter: &mut Linter, checkver: &Value, version: &str) {
+    let pointer = "/checkver";
+    let substitutions = get_version_substitutions(version, &HashMap::new());
+    match checkver {
+        Value::String(regex) => {
+            if regex != "github" {
+                let regex = substitute_str(regex, &substitutions, true);
+                if let Err(e) = Regex::new(&regex) {
+                    linter.error(pointer, "invalid-regex", format!("invalid regex: {e}"));
+                }
+            }
+        }
+        Value::Object(obj) => {
+            check_unknown_keys(linter, checkver, pointer, CHECKVER_KEYS);
+            let has_extractor = [
+                "github",
+                "re",
+                "regex",
+                "jp",
+                "jsonpath",
+                "xpath",
+                "script",
+                "sourceforge",
+            ]
+            .iter()
+            .any(|key| obj.contains_key(*key));
+            if !has_extractor {
+                linter.error(
+                    pointer,
+                    "invalid-checkver",
+                    "checkver needs one of github, regex, jsonpath, xpath or script",
+                );
+            }
+            let invalid_github = obj.get("github").is_some_and(|github| {
+                !github
+                    .as_str()
+                    .is_some_and(|g| g.starts_with("https://github.com/"))
+            });
+            if invalid_github {
+                linter.error(
+                    "/checkver/github",
+                    "invalid-checkver",
+                    "checkver.github must be a https://github.com/ repository url",
+                );
+            }
+            for key in ["re", "regex"] {
+                let Some(regex) = obj.get(key) else {
+                    continue;
+                };
+                let key_pointer = child_pointer(pointer, key);
+                let Some(regex) = regex.as_str() else {
+                    linter.error(&key_pointer, "invalid-type", "regex must be string");
+                    continue;
+                };
+                let regex = substitute_str(regex, &substitutions, true);
+                if let Err(e) = Regex::new(&regex) {
+                    linter.error(&key_pointer, "invalid-regex", format!("invalid regex: {e}"));
+                }
+            }
+            for key in ["jp", "jsonpath"] {
+                let Some(path) = obj.get(key) else {
+                    continue;
+                };
+                let key_pointer = child_pointer(pointer, key);
+                let valid = path
+                    .as_str()
+                    .is_some_and(|p| serde_json_path::JsonPath::parse(p).is_ok());
+                if !valid {
+                    linter.error(&key_pointer, "invalid-jsonpath", "invalid jsonpath");
+                }
+            }
+            if obj.get("reverse").is_some_and(|r| !r.is_boolean()) {
+                linter.error(
+                    "/checkver/reverse",
+                    "invalid-type",
+                    "reverse must be boolean",
+                );
+            }
+        }
+        _ => linter.error(pointer, "invalid-type", "checkver must be string or object"),
+    }
+}
+
+fn check_autoupdate(linter: &mut Linter, manifest: &Value, autoupdate: &Value, version: &str) {
+    let pointer = "/autoupdate";
+    if !autoupdate.is_object() {
+        linter.error(pointer, "invalid-type", "autoupdate must be object");
+        return;
+    }
+    check_unknown_keys(linter, autoupdate, pointer, AUTOUPDATE_KEYS);
+    if let Some(arch) = autoupdate.get("architecture") {
+        check_unknown_keys(linter, arch, "/autoupdate/architecture", &ARCHITECTURES);
+        for name in ARCHITECTURES {
+            if let Some(block) = arch.get(name) {
+                let arch_pointer = format!("/autoupdate/architecture/{name}");
+                check_unknown_keys(linter, block, &arch_pointer, ARCH_KEYS);
+            }
+        }
+    }
+    if let Err(e) = serde_json::from_value::<AutoUpdateStruct>(autoupdate.clone()) {
+        linter.error(pointer, "invalid-autoupdate", format!("{e}"));
+        return;
+    }
+    if manifest.get("checkver").is_none() {
+        linter.warning(
+            pointer,
+            "autoupdate-without-checkver",
+            "autoupdate requires checkver to find new versions",
+        );
+    }
+    if let Err(e) = generate_manifest_for_version(manifest, version, &HashMap::new()) {
+        linter.error(pointer, "invalid-autoupdate", format!("{e}"));
+    }
+}
+
+/// 对已经解析的清单进行检查, 不访问网络
+pub fn lint_manifest_value(manifest: &ManifestObj) -> Vec<LintIssue> {
+    let mut linter = Linter { issues: vec![] };
+    if !manifest.is_object() {
+        linter.error("", "invalid-type", "manifest must be a json object");
+        return linter.issues;
+    }
+    check_unknown_keys(&mut linter, manifest, "", ROOT_KEYS);
+
+    let version = match manifest.get("version") {
+        None => {
+            linter.error("/version", "missing-version", "version is required");
+            ""
+        }
+        Some(Value::String(version)) => {
+            let pattern = Regex::new(r"^[\w.\-+]+$").unwrap();
+            if !pattern.is_match(version) {
+                linter.error(
+                    "/version",
+                    "invalid-version",
+                    format!("version '{version}' contains invalid characters"),
+                );
+            }
+            version.as_str()
+        }
+        Some(_) => {
+            linter.error("/version", "invalid-type", "version must be string");
+            ""
+        }
+    };
+    let is_nightly = version.to_lowercase() == "nightly";
+    for key in ["homepage", "license", "description"] {
+        if manifest.get(key).is_none() {
+            linter.warning(
+                &format!("/{key}"),
+                &format!("missing-{key}"),
+                format!("{key} is recommended"),
+            );
+        }
+    }
+
+    let root_extract_dir = manifest.get("extract_dir");
+    check_url_and_hash(&mut linter, manifest, "", is_nightly);
+    check_bin(&mut linter, manifest, "", root_extract_dir);
+    let has_root_url = manifest.get("url").is_some();
+    let mut has_arch_url = false;
+
+    match manifest.get("architecture") {
+        None => {}
+        Some(Value::Object(arch)) => {
+            check_unknown_keys(
+                &mut linter,
+                &manifest["architecture"],
+                "/architecture",
+                &ARCHITECTURES,
+            );
+            for name in ARCHITECTURES {
+                let Some(block) = arch.get(name) else {
+                    continue;
+                };
+                let arch_pointer = format!("/architecture/{name}");
+                if !block.is_object() {
+                    linter.error(
+                        &arch_pointer,
+                        "invalid-type",
+                        "architecture block must be object",
+                    );
+                    continue;
+                }
+                check_unknown_keys(&mut linter, block, &arch_pointer, ARCH_KEYS);
+                if block.get("url").is_some() {
+                    has_arch_url = true;
+                } else if !has_root_url {
+                    linter.error(
+                        &format!("{arch_pointer}/url"),
+                        "missing-url",
+                        format!("architecture {name} does not define url and there is no root url"),
+                    );
+                }
+                check_url_and_hash(&mut linter, block, &arch_pointer, is_nightly);
+                let extract_dir = block.get("extract_dir").or(root_extract_dir);
+                check_bin(&mut linter, block, &arch_pointer, extract_dir);
+            }
+        }
+        Some(_) => linter.error(
+            "/architecture",
+            "invalid-type",
+            "architecture must be object",
+        ),
+    }
+    if !has_root_url && !has_arch_url && manifest.get("architecture").is_none() {
+        linter.error("/url", "missing-url", "url is required");
+    }
+
+    if let Some(checkver) = manifest.get("checkver") {
+        check_checkver(&mut linter, checkver, version);
+    }
+    if let Some(autoupdate) = manifest.get("autoupdate") {
+        check_autoupdate(&mut linter, manifest, autoupdate, version);
+    }
+    linter.issues
+}
+
+pub fn lint_manifest_file<P: AsRef<Path>>(path: P) -> LintReport {
+    let path = path.as_ref();
+    let path_str = path.display().to_string();
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            return LintReport {
+                path: path_str,
+                issues: vec![LintIssue {
+                    pointer: String::new(),
+                    severity: LintSeverity::Error,
+                    rule: "io-error".into(),
+                    message: e.to_string(),
+                }],
+            };
+        }
+    };
+    let content = content.trim_start_matches('\u{feff}');
+    let issues = match serde_json::from_str::<ManifestObj>(content) {
+        Ok(manifest) => lint_manifest_value(&manifest),
+        Err(e) => vec![LintIssue {
+            pointer: String::new(),
+            severity: LintSeverity::Error,
+            rule: "invalid-json".into(),
+            message: format!("{e}"),
+        }],
+    };
+    LintReport {
+        path: path_str,
+        issues,
+    }
+}
+
+#[cfg(test)]
+mod test_lint {
+    #[allow(unused_imports)]
+    use super::*;
+
+    fn rules(manifest: Value) -> Vec<(String, String)> {
+        lint_manifest_value(&manifest)
+            .into_iter()
+            .map(|issue| (issue.pointer, issue.rule))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_manifest() {
+        let manifest = serde_json::json!({
+            "version": "1.2.0",
+            "description": "demo",
+            "homepage": "https://example.com",
+            "license": "MIT",
+            "url": "https://example.com/demo-1.2.0.zip",
+            "hash": "a".repeat(64),
+            "extract_dir": "demo-1.2.0",
+            "bin": "demo.exe",
+            "checkver": { "github": "https://github.com/demo/demo" },
+            "autoupdate": {
+                "url": "https://example.com/demo-$version.zip",
+                "extract_dir": "demo-$version"
+            }
+        });
+        assert!(rules(manifest).is_empty());
+    }
+
+    #[test]
+    fn test_reports_pointers() {
+        let manifest = serde_json::json!({
+            "homepage": "https://example.com",
+            "license": "MIT",
+            "description": "demo",
+            "extract_dir": "demo-1.2.0",
+            "bin": ["demo-1.2.0\\demo.exe", ["tool.exe", "tool"]],
+            "architecture": {
+                "64bit": {
+                    "url": ["https://example.com/a.zip", "https://example.com/b.zip"],
+                    "hash": ["sha1:1234"]
+                },
+                "32bit": { "hash": "b".repeat(64) }
+            },
+            "foo": 1,
+            "checkver": { "regex": "(unclosed" }
+        });
+        let rules = rules(manifest);
+        let expect = [
+            ("/version", "missing-version"),
+            ("/foo", "unknown-key"),
+            ("/bin/0", "bin-outside-extract-dir"),
+            ("/architecture/64bit/hash/0", "invalid-hash"),
+            ("/architecture/64bit/hash", "hash-count-mismatch"),
+            ("/architecture/32bit/url", "missing-url"),
+            ("/checkver/regex", "invalid-regex"),
+        ];
+        for (pointer, rule) in expect {
+            assert!(
+                rules.contains(&(pointer.to_string(), rule.to_string())),
+                "missing {pointer} {rule} in {rules:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_autoupdate() {
+        let manifest = serde_json::json!({
+            "version": "1.0",
+            "url": "https://example.com/a.zip",
+            "hash": "c".repeat(64),
+            "checkver": "v([\\d.]+)",
+            "autoupdate": { "hash": { "mode": "unknown" }, "url": "https://example.com/$version.zip" }
+        });
+        let rules = rules(manifest);
+        assert!(rules.contains(&("/autoupdate".to_string(), "invalid-autoupdate".to_string())));
+    }
+}
@@ -1,8 +1,11 @@
 use crate::init_env::{
     get_all_buckets_dir_child_bucket_path, get_all_global_buckets_dir_child_bucket_path,
+    get_special_bucket_all_manifest_path, get_special_bucket_all_manifest_path_global,
+    get_special_bucket_child_path, get_special_bucket_child_path_global,
 };
 use crate::list::VersionJSON;
 use crate::manifest::manifest_deserialize::ObjectOrString;
+use crate::utils::version::Version;
 use anyhow::{bail, Context};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -254,7 +257,9 @@ pub fn get_latest_manifest_from_local_bucket(app_name: &str) -> anyhow::Result<P
             }
         })
         .collect::<Vec<_>>();
-    let max_result = result_with_version.iter().max_by(|a, b| a.1.cmp(&b.1));
+    let max_result = result_with_version
+        .iter()
+        .max_by(|a, b| Version::new(&a.1).cmp(&Version::new(&b.1)));
 
     if let Some((path, _)) = max_result {
         Ok(path.to_owned())
@@ -338,7 +343,9 @@ pub fn get_latest_manifest_from_local_bucket_global(app_name: &str) -> anyhow::R
             }
         })
         .collect::<Vec<_>>();
-    let max_result = result_with_version.iter().max_by(|a, b| a.1.cmp(&b.1));
+    let max_result = result_with_version
+        .iter()
+        .max_by(|a, b| Version::new(&a.1).cmp(&Version::new(&b.1)));
 
     if let Some((path, _)) = max_result {
         Ok(path.to_owned())
@@ -346,6 +353,62 @@ pub fn get_latest_manifest_from_local_bucket_global(app_name: &str) -> anyhow::R
         bail!("No app manifest found for '{app_name}'")
     }
 }
+
+/// 支持 `app`, `bucket/app`, `bucket/*`, 清单文件路径以及包含清单的目录
+pub fn resolve_manifest_targets(target: &str, global: bool) -> anyhow::Result<Vec<PathBuf>> {
+    let target_path = Path::new(target);
+    if target.ends_with(".json") && target_path.is_file() {
+        return Ok(vec![target_path.to_path_buf()]);
+    }
+    if target_path.is_dir() {
+        let bucket_dir = target_path.join("bucket");
+        let manifest_dir = if bucket_dir.is_dir() {
+            bucket_dir
+        } else {
+            target_path.to_path_buf()
+        };
+        let mut manifests = std::fs::read_dir(&manifest_dir)
+            .context(format!("Failed to read dir {}", manifest_dir.display()))?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect::<Vec<_>>();
+        manifests.sort();
+        return Ok(manifests);
+    }
+    if let Some((bucket, app)) = target.split_once('/') {
+        if app == "*" {
+            let manifests = if global {
+                get_special_bucket_all_manifest_path_global(bucket)?
+            } else {
+                get_special_bucket_all_manifest_path(bucket)?
+            };
+            let mut manifests = manifests
+                .into_iter()
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect::<Vec<_>>();
+            manifests.sort();
+            return Ok(manifests);
+        }
+        let bucket_dir = if global {
+            get_special_bucket_child_path_global(bucket)
+        } else {
+            get_special_bucket_child_path(bucket)
+        };
+        let manifest_path = Path::new(&bucket_dir).join(format!("{app}.json"));
+        if !manifest_path.exists() {
+            bail!("No manifest found for '{app}' in bucket '{bucket}'")
+        }
+        return Ok(vec![manifest_path]);
+    }
+    let manifest_path = if global {
+        get_best_manifest_from_local_bucket_global(target)?
+    } else {
+        get_best_manifest_from_local_bucket(target)?
+    };
+    Ok(vec![manifest_path])
+}
+
 mod test_manifest {
     #[test]
     fn test_output() {
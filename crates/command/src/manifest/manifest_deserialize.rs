@@ -70,11 +70,12 @@ pub struct AutoUpdateStruct {
     pub env_add_path: Option<StringArrayOrString>,
     pub env_set: Option<ManifestObj>,
     pub note: Option<StringArrayOrString>,
+    pub notes: Option<StringArrayOrString>,
     pub license: Option<StringOrArrayOrDotDimensionArrayOrObject>,
     pub psmodule: Option<PSModuleStruct>,
     pub persist: Option<StringOrArrayOrDoubleDimensionArray>,
-    pub architecture: Option<ArchitectureObject>,
-    pub hash: Option<AutoUpdateHashStruct>,
+    pub architecture: Option<AutoUpdateArchitecture>,
+    pub hash: Option<AutoUpdateHashOrArray>,
     pub installer: Option<InstallerUninstallerStruct>,
     pub uninstaller: Option<InstallerUninstallerStruct>,
     pub url: Option<ObjectArrayOrStringOrObjectOrStringArray>,
@@ -83,6 +84,54 @@ pub struct AutoUpdateStruct {
     pub post_install: Option<StringArrayOrString>,
 }
 
+/// autoupdate 中的架构块, hash 是提取规则对象而不是哈希字符串, 不能复用 ArchitectureObject
+#[must_use]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AutoUpdateArchitecture {
+    #[serde(rename = "64bit")]
+    pub x64bit: Option<AutoUpdateArchStruct>,
+    #[serde(rename = "32bit")]
+    pub x86bit: Option<AutoUpdateArchStruct>,
+    pub arm64: Option<AutoUpdateArchStruct>,
+}
+
+impl AutoUpdateArchitecture {
+    pub fn get_specific_architecture(&self, arch: &str) -> Option<&AutoUpdateArchStruct> {
+        match arch {
+            "64bit" => self.x64bit.as_ref(),
+            "32bit" => self.x86bit.as_ref(),
+            "arm64" => self.arm64.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AutoUpdateArchStruct {
+    pub bin: Option<StringOrArrayOrDoubleDimensionArray>,
+    pub extract_dir: Option<StringArrayOrString>,
+    pub extract_to: Option<StringArrayOrString>,
+    pub env_add_path: Option<StringArrayOrString>,
+    pub env_set: Option<ManifestObj>,
+    pub hash: Option<AutoUpdateHashOrArray>,
+    pub installer: Option<InstallerUninstallerStruct>,
+    pub uninstaller: Option<InstallerUninstallerStruct>,
+    pub url: Option<StringArrayOrString>,
+    pub shortcuts: Option<ArrayOrDoubleDimensionArray>,
+    pub pre_install: Option<StringArrayOrString>,
+    pub post_install: Option<StringArrayOrString>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum AutoUpdateHashOrArray {
+    #[default]
+    Null,
+    Object(AutoUpdateHashStruct),
+    ObjectArray(Vec<AutoUpdateHashStruct>),
+}
+
 // !hash mode jsonpath  regex
 #[must_use]
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
@@ -112,17 +161,19 @@ pub struct AutoUpdateHashStruct {
     pub find: Option<String>,
 }
 
+/// autoupdate.hash.mode , 清单中是小写字符串, 缺省为 extract (正则提取)
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
 pub enum HashModeStruct {
     #[default]
-    Null,
-    Json(String),
-    Xpath(String),
-    Rdf(String),
-    Metalink(String),
-    Fosshub(String),
-    Sourceforge(String),
-    Download(String),
+    Extract,
+    Json,
+    Xpath,
+    Rdf,
+    Metalink,
+    Fosshub,
+    Sourceforge,
+    Download,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
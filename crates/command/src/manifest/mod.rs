@@ -1,6 +1,8 @@
 ﻿pub mod install_manifest;
 pub mod search_manifest;
 pub mod manifest;
+pub mod lint;
+pub mod format;
 pub mod   manifest_deserialize; 
 
 pub mod  update_manifest;
@@ -2,6 +2,7 @@
 use crate::manifest::search_manifest::SearchManifest;
 use crate::utils::request::get_git_repo_remote_url;
 use crate::utils::utility::{remove_bom_and_control_chars_from_utf8_file, LARGE_COMMUNITY_BUCKET};
+use crate::utils::version::Version;
 use anyhow::{anyhow, bail, Context};
 use crossterm::style::Stylize;
 use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressFinish, ProgressStyle};
@@ -186,8 +187,8 @@ fn find_latest_version(
         let old_app_version = old_bucket.app_version.to_string();
         let new_app_versio = merge.app_version.to_string();
         //  insert 会自动覆盖旧值
-        if new_app_versio > old_app_version {
-            map_container.insert(new_app_versio, merge);
+        if Version::new(&new_app_versio) > Version::new(&old_app_version) {
+            map_container.insert(merge.app_name.to_string(), merge);
         }
     };
     Ok(())
@@ -249,13 +250,15 @@ fn remove_old_manifest(
                         remove_file(&path).expect("删除文件失败");
                         return None;
                     }
-                    return if app_version.to_string()
-                        < latest_buckets_map
-                            .lock()
-                            .unwrap()
-                            .get(app_name)
-                            .unwrap()
-                            .app_version
+                    return if Version::new(app_version)
+                        < Version::new(
+                            &latest_buckets_map
+                                .lock()
+                                .unwrap()
+                                .get(app_name)
+                                .unwrap()
+                                .app_version,
+                        )
                     {
                         //  println!("删除的文件{} 版本{}", path.display(), app_version);
                         remove_file(&path).expect("删除文件失败");
@@ -0,0 +1,23 @@
+use crate::config::get_config_value_no_print;
+use anyhow::bail;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static OFFLINE_FLAG: AtomicBool = AtomicBool::new(false);
+
+/// 由全局参数 `--offline` 开启, 进程内所有命令共享
+pub fn set_offline_mode(offline: bool) {
+    OFFLINE_FLAG.store(offline, Ordering::Relaxed);
+}
+
+/// `--offline` 或配置 `offline = true` 时, 只使用缓存目录和本地 bucket, 不访问网络
+pub fn is_offline_mode() -> bool {
+    OFFLINE_FLAG.load(Ordering::Relaxed) || get_config_value_no_print("offline") == "true"
+}
+
+/// 必须联网的操作在离线模式下立即失败, 而不是等待网络超时
+pub fn ensure_online(action: &str) -> anyhow::Result<()> {
+    if is_offline_mode() {
+        bail!("{action} requires network access, but offline mode is enabled")
+    }
+    Ok(())
+}
@@ -0,0 +1,843 @@
+use crate::checkver::build_request_client;
+use crate::init_env::{
+    get_app_current_dir, get_app_current_dir_global, get_app_dir, get_app_dir_global,
+    get_app_dir_manifest_json, get_app_dir_manifest_json_global, get_app_version_dir,
+    get_app_version_dir_global, get_persist_dir_path, get_persist_dir_path_global,
+    get_psmodules_root_dir, get_psmodules_root_global_dir, get_shims_root_dir,
+    get_shims_root_dir_global,
+};
+use crate::install::{
+    find_app_manifest_path, get_bucket_name_from_manifest_path, handle_arch, is_app_installed,
+    read_manifest_obj, resolve_install_targets, shim_file_names, DeferredInstall, DownloadManager,
+    InstallOptions, ResolvedTargets, UpdateOptions,
+};
+use crate::list::get_all_installed_apps_name;
+use crate::manifest::manifest_deserialize::ManifestObj;
+use crate::offline::is_offline_mode;
+use crate::uninstall::shim_and_shortcuts::get_all_shortcuts_link_paths;
+use crate::update::{
+    check_app_version_latest, get_current_version_dir, transform_update_options_to_install,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// 执行计划中的一步, 只描述将要发生的修改, 不执行
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PlanStep {
+    Download {
+        url: String,
+        cache_path: String,
+        cache_hit: bool,
+        size: Option<u64>,
+    },
+    CreateDir {
+        path: String,
+    },
+    RemoveDir {
+        path: String,
+    },
+    LinkCurrent {
+        link: String,
+        target: String,
+    },
+    RunScript {
+        stage: String,
+    },
+    CreateShim {
+        name: String,
+        target: String,
+    },
+    RemoveShim {
+        name: String,
+    },
+    CreateShortcut {
+        path: String,
+        target: String,
+    },
+    RemoveShortcut {
+        path: String,
+    },
+    LinkPsModule {
+        link: String,
+        target: String,
+    },
+    RemovePsModule {
+        link: String,
+    },
+    SetEnv {
+        name: String,
+        value: String,
+    },
+    RemoveEnv {
+        name: String,
+    },
+    AddPath {
+        path: String,
+    },
+    RemovePath {
+        path: String,
+    },
+    PersistLink {
+        source: String,
+        target: String,
+    },
+}
+
+impl Display for PlanStep {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlanStep::Download {
+                url,
+                cache_path,
+                cache_hit,
+                size,
+            } => {
+                if *cache_hit {
+                    write!(f, "use cache      {cache_path}")
+                } else {
+                    let size = size
+                        .map(|size| format!(" ({:.1} MB)", size as f64 / 1024.0 / 1024.0))
+                        .unwrap_or_default();
+                    write!(f, "download       {url}{size}")
+                }
+            }
+            PlanStep::CreateDir { path } => write!(f, "create dir     {path}"),
+            PlanStep::RemoveDir { path } => write!(f, "remove dir     {path}"),
+            PlanStep::LinkCurrent { link, target } => {
+                write!(f, "link           {link} => {target}")
+            }
+            PlanStep::RunScript { stage } => write!(f, "run script     {stage}"),
+            PlanStep::CreateShim { name, target } => write!(f, "create shim    {name} => {target}"),
+            PlanStep::RemoveShim { name } => write!(f, "remove shim    {name}"),
+            PlanStep::CreateShortcut { path, target } => {
+                write!(f, "create link    {path} => {target}")
+            }
+            PlanStep::RemoveShortcut { path } => write!(f, "remove link    {path}"),
+            PlanStep::LinkPsModule { link, target } => {
+                write!(f, "link module    {link} => {target}")
+            }
+            PlanStep::RemovePsModule { link } => write!(f, "remove module  {link}"),
+            PlanStep::SetEnv { name, value } => write!(f, "set env        {name}={value}"),
+            PlanStep::RemoveEnv { name } => write!(f, "remove env     {name}"),
+            PlanStep::AddPath { path } => write!(f, "add PATH       {path}"),
+            PlanStep::RemovePath { path } => write!(f, "remove PATH    {path}"),
+            PlanStep::PersistLink { source, target } => {
+                write!(f, "persist        {source} => {target}")
+            }
+        }
+    }
+}
+
+/// 解压之后的安装阶段, 安装流程与 dry-run 计划按同一顺序遍历
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallStage {
+    /// 解压到版本目录并链接 current
+    Extract,
+    PreInstall,
+    Installer,
+    ShimsAndShortcuts,
+    PsModule,
+    EnvSet,
+    EnvAddPath,
+    Persist,
+    PostInstall,
+}
+
+pub const INSTALL_STAGES: [InstallStage; 9] = [
+    InstallStage::Extract,
+    InstallStage::PreInstall,
+    InstallStage::Installer,
+    InstallStage::ShimsAndShortcuts,
+    InstallStage::PsModule,
+    InstallStage::EnvSet,
+    InstallStage::EnvAddPath,
+    InstallStage::Persist,
+    InstallStage::PostInstall,
+];
+
+/// 卸载阶段, 卸载流程与 dry-run 计划按同一顺序遍历
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UninstallStage {
+    PreUninstall,
+    Uninstaller,
+    PostUninstall,
+    PsModule,
+    EnvAddPath,
+    EnvSet,
+    Shims,
+    Shortcuts,
+    /// 删除 App 目录
+    RemoveAppDir,
+}
+
+pub const UNINSTALL_STAGES: [UninstallStage; 9] = [
+    UninstallStage::PreUninstall,
+    UninstallStage::Uninstaller,
+    UninstallStage::PostUninstall,
+    UninstallStage::PsModule,
+    UninstallStage::EnvAddPath,
+    UninstallStage::EnvSet,
+    UninstallStage::Shims,
+    UninstallStage::Shortcuts,
+    UninstallStage::RemoveAppDir,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanAction {
+    Install,
+    Update,
+    Uninstall,
+    Cleanup,
+    Skip,
+    Unresolved,
+}
+
+impl Display for PlanAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let action = match self {
+            PlanAction::Install => "install",
+            PlanAction::Update => "update",
+            PlanAction::Uninstall => "uninstall",
+            PlanAction::Cleanup => "cleanup",
+            PlanAction::Skip => "skip",
+            PlanAction::Unresolved => "unresolved",
+        };
+        write!(f, "{action}")
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppPlan {
+    pub name: String,
+    pub version: Option<String>,
+    pub action: PlanAction,
+    pub global: bool,
+    /// 跳过或无法解析的原因
+    pub reason: Option<String>,
+    pub steps: Vec<PlanStep>,
+}
+
+impl AppPlan {
+    pub fn new(name: &str, action: PlanAction, global: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            version: None,
+            action,
+            global,
+            reason: None,
+            steps: vec![],
+        }
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExecutionPlan {
+    pub apps: Vec<AppPlan>,
+}
+
+impl ExecutionPlan {
+    /// 需要下载且已知大小的文件总字节数
+    pub fn download_size(&self) -> u64 {
+        self.apps
+            .iter()
+            .flat_map(|app| app.steps.iter())
+            .filter_map(|step| match step {
+                PlanStep::Download {
+                    cache_hit: false,
+                    size,
+                    ..
+                } => *size,
+                _ => None,
+            })
+            .sum()
+    }
+
+    /// 通过 HEAD 请求补全未命中缓存的下载大小, 获取失败时保持未知
+    pub async fn fill_download_sizes(&mut self) {
+        if is_offline_mode() {
+            return;
+        }
+        let Ok(client) = build_request_client(None) else {
+            return;
+        };
+        for step in self.apps.iter_mut().flat_map(|app| app.steps.iter_mut()) {
+            if let PlanStep::Download {
+                url,
+                cache_hit: false,
+                size,
+                ..
+            } = step
+            {
+                let Ok(response) = client.head(url.as_str()).send().await else {
+                    continue;
+                };
+                *size = response
+                    .headers()
+                    .get(reqwest::header::CONTENT_LENGTH)
+                    .and_then(|length| length.to_str().ok())
+                    .and_then(|length| length.parse::<u64>().ok());
+            }
+        }
+    }
+}
+
+/// 架构块中的字段优先于顶层字段, 与安装时的取值规则一致
+pub(crate) fn manifest_value<'a>(
+    manifest: &'a ManifestObj,
+    arch: &str,
+    key: &str,
+) -> Option<&'a Value> {
+    manifest["architecture"][arch]
+        .get(key)
+        .or_else(|| manifest.get(key))
+        .filter(|value| !value.is_null())
+}
+
+fn value_strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => vec![s.clone()],
+        Value::Array(arr) => arr.iter().flat_map(value_strings).collect(),
+        _ => vec![],
+    }
+}
+
+/// bin 中每一项的目标与别名, `[path, alias, args]` 形式带别名
+pub fn collect_bin_shims(bin: &Value) -> Vec<(String, Option<String>)> {
+    let entry = |item: &Value| -> Option<(String, Option<String>)> {
+        match item {
+            Value::String(path) if !path.trim().is_empty() => Some((path.clone(), None)),
+            Value::Array(parts) => {
+                let path = parts.first()?.as_str()?.to_string();
+                let alias = parts
+                    .get(1)
+                    .and_then(|alias| alias.as_str())
+                    .map(|alias| alias.to_string());
+                Some((path, alias))
+            }
+            _ => None,
+        }
+    };
+    match bin {
+        Value::Array(items) => items.iter().filter_map(entry).collect(),
+        _ => entry(bin).into_iter().collect(),
+    }
+}
+
+/// shortcuts 中每一项的快捷方式名称与目标
+pub fn collect_shortcuts(shortcuts: &Value) -> Vec<(String, String)> {
+    let entry = |item: &Value| -> Option<(String, String)> {
+        let parts = item.as_array()?;
+        let target = parts.first()?.as_str()?.to_string();
+        let name = parts.get(1)?.as_str()?.to_string();
+        Some((name, target))
+    };
+    match shortcuts.as_array() {
+        Some(items) if items.iter().all(|item| item.is_string()) => {
+            entry(shortcuts).into_iter().collect()
+        }
+        Some(items) => items.iter().filter_map(entry).collect(),
+        None => vec![],
+    }
+}
+
+/// persist 中每一项的 App 内路径与 persist 目录内路径
+pub fn collect_persist(persist: &Value) -> Vec<(String, String)> {
+    let entry = |item: &Value| -> Option<(String, String)> {
+        match item {
+            Value::String(path) if !path.trim().is_empty() => {
+                Some((path.trim().to_string(), path.trim().to_string()))
+            }
+            Value::Array(parts) => {
+                let source = parts.first()?.as_str()?.trim().to_string();
+                let target = parts
+                    .get(1)
+                    .and_then(|target| target.as_str())
+                    .map(|target| target.trim().to_string())
+                    .unwrap_or_else(|| source.clone());
+                Some((source, target))
+            }
+            _ => None,
+        }
+    };
+    match persist {
+        Value::Array(items) => items.iter().filter_map(entry).collect(),
+        _ => entry(persist).into_iter().collect(),
+    }
+}
+
+/// 清单中定义了该阶段的脚本时返回对应的步骤
+fn script_step(manifest: &ManifestObj, arch: &str, stage: &str) -> Option<PlanStep> {
+    let has_script = match manifest_value(manifest, arch, stage)? {
+        Value::Object(obj) => obj.contains_key("script") || obj.contains_key("file"),
+        value => !value_strings(value).is_empty(),
+    };
+    has_script.then(|| PlanStep::RunScript {
+        stage: stage.to_string(),
+    })
+}
+
+/// 与 install_app_from_local_manifest_file 相同的顺序列出安装步骤
+pub fn plan_install_from_manifest(
+    manifest_path: &Path,
+    bucket: Option<&str>,
+    options: &[InstallOptions],
+) -> anyhow::Result<AppPlan> {
+    let global = options.contains(&InstallOptions::Global);
+    let arch = handle_arch(options)?;
+    let manifest = read_manifest_obj(manifest_path)?;
+    let app_name = manifest_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let version = manifest["version"].as_str().unwrap_or_default().to_string();
+    let mut plan = AppPlan::new(&app_name, PlanAction::Install, global);
+    plan.version = Some(version.clone());
+
+    let dry_run_options = options
+        .iter()
+        .cloned()
+        .chain([InstallOptions::DryRun])
+        .collect::<Vec<_>>();
+    let manifest_path_str = manifest_path.to_string_lossy().to_string();
+    let download_manager = DownloadManager::new(&dry_run_options, &manifest_path_str, bucket);
+    let no_cache = options.contains(&InstallOptions::NoUseDownloadCache)
+        || options.contains(&InstallOptions::ForceDownloadNoInstallOverrideCache);
+    // 配置了镜像时显示首选的下载地址
+    for (urls, cache_path) in download_manager
+        .get_download_mirror_urls()
+        .iter()
+        .zip(download_manager.get_final_cache_file_path().iter())
+    {
+        plan.steps.push(PlanStep::Download {
+            url: urls.first().cloned().unwrap_or_default(),
+            cache_path: cache_path.clone(),
+            cache_hit: !no_cache && Path::new(cache_path).exists(),
+            size: None,
+        });
+    }
+    if options.contains(&InstallOptions::OnlyDownloadNoInstall) {
+        return Ok(plan);
+    }
+
+    let (version_dir, current_dir, shims_dir, persist_dir, psmodule_dir) = if global {
+        (
+            get_app_version_dir_global(&app_name, &version),
+            get_app_current_dir_global(&app_name),
+            get_shims_root_dir_global(),
+            get_persist_dir_path_global(),
+            get_psmodules_root_global_dir(),
+        )
+    } else {
+        (
+            get_app_version_dir(&app_name, &version),
+            get_app_current_dir(&app_name),
+            get_shims_root_dir(),
+            get_persist_dir_path(),
+            get_psmodules_root_dir(),
+        )
+    };
+    for stage in INSTALL_STAGES {
+        match stage {
+            InstallStage::Extract => {
+                if !Path::new(&version_dir).exists() {
+                    plan.steps.push(PlanStep::CreateDir {
+                        path: version_dir.clone(),
+                    });
+                }
+                plan.steps.push(PlanStep::LinkCurrent {
+                    link: current_dir.clone(),
+                    target: version_dir.clone(),
+                });
+            }
+            InstallStage::PreInstall => {
+                plan.steps
+                    .extend(script_step(&manifest, &arch, "pre_install"));
+            }
+            InstallStage::Installer => {
+                plan.steps
+                    .extend(script_step(&manifest, &arch, "installer"));
+            }
+            InstallStage::ShimsAndShortcuts => {
+                if let Some(bin) = manifest_value(&manifest, &arch, "bin") {
+                    for (target, alias) in collect_bin_shims(bin) {
+                        for name in shim_file_names(&target, alias.as_deref())? {
+                            plan.steps.push(PlanStep::CreateShim {
+                                name: format!("{shims_dir}\\{name}"),
+                                target: format!("{current_dir}\\{target}"),
+                            });
+                        }
+                    }
+                }
+                if let Some(shortcuts) = manifest_value(&manifest, &arch, "shortcuts") {
+                    let shortcuts_dir = get_all_shortcuts_link_paths(global);
+                    for (name, target) in collect_shortcuts(shortcuts) {
+                        plan.steps.push(PlanStep::CreateShortcut {
+                            path: shortcuts_dir
+                                .join(format!("{name}.lnk"))
+                                .to_string_lossy()
+                                .to_string(),
+                            target: format!("{current_dir}\\{target}"),
+                        });
+                    }
+                }
+            }
+            InstallStage::PsModule => {
+                if let Some(module_name) = manifest["psmodule"]["name"].as_str() {
+                    plan.steps.push(PlanStep::LinkPsModule {
+                        link: format!("{psmodule_dir}\\{module_name}"),
+                        target: version_dir.clone(),
+                    });
+                }
+            }
+            InstallStage::EnvSet => {
+                if let Some(Value::Object(env_set)) = manifest_value(&manifest, &arch, "env_set") {
+                    for (name, value) in env_set {
+                        plan.steps.push(PlanStep::SetEnv {
+                            name: name.clone(),
+                            value: value.as_str().unwrap_or_default().to_string(),
+                        });
+                    }
+                }
+            }
+            InstallStage::EnvAddPath => {
+                if let Some(env_add_path) = manifest_value(&manifest, &arch, "env_add_path") {
+                    for path in value_strings(env_add_path) {
+                        let path = if path == "." {
+                            current_dir.clone()
+                        } else {
+                            format!("{current_dir}\\{path}")
+                        };
+                        plan.steps.push(PlanStep::AddPath { path });
+                    }
+                }
+            }
+            InstallStage::Persist => {
+                if let Some(persist) = manifest.get("persist") {
+                    for (source, target) in collect_persist(persist) {
+                        plan.steps.push(PlanStep::PersistLink {
+                            source: format!("{current_dir}\\{source}"),
+                            target: format!("{persist_dir}\\{app_name}\\{target}"),
+                        });
+                    }
+                }
+            }
+            InstallStage::PostInstall => {
+                plan.steps
+                    .extend(script_step(&manifest, &arch, "post_install"));
+            }
+        }
+    }
+    Ok(plan)
+}
+
+/// 与 install_apps_with_plan 相同的方式解析目标与依赖, 依赖排在前面
+pub fn plan_install_targets(
+    targets: &[String],
+    options: &[InstallOptions],
+) -> anyhow::Result<ExecutionPlan> {
+    let global = options.contains(&InstallOptions::Global);
+    let arch = handle_arch(options)?;
+    let force = options.contains(&InstallOptions::ForceInstallOverride);
+    let ResolvedTargets {
+        plan,
+        deferred,
+        failures,
+    } = resolve_install_targets(targets, &arch, options);
+    let mut execution_plan = ExecutionPlan::default();
+    for app in plan {
+        let name = app.node.name.as_str();
+        if !force && is_app_installed(name, global) {
+            execution_plan.apps.push(
+                AppPlan::new(name, PlanAction::Skip, global).with_reason("already installed"),
+            );
+            continue;
+        }
+        let app_plan = match plan_install_from_manifest(
+            &app.node.manifest_path,
+            app.node.bucket.as_deref(),
+            options,
+        ) {
+            Ok(app_plan) => app_plan,
+            Err(e) => {
+                AppPlan::new(name, PlanAction::Unresolved, global).with_reason(format!("{e:#}"))
+            }
+        };
+        let app_plan = if app.requested {
+            app_plan
+        } else {
+            app_plan.with_reason("dependency")
+        };
+        execution_plan.apps.push(app_plan);
+    }
+    for (target, install) in deferred {
+        let app_plan = match install {
+            DeferredInstall::Url(url) => {
+                let mut app_plan = AppPlan::new(&target, PlanAction::Install, global);
+                app_plan.steps.push(PlanStep::Download {
+                    url,
+                    cache_path: String::new(),
+                    cache_hit: false,
+                    size: None,
+                });
+                app_plan
+            }
+            DeferredInstall::AutoupdateVersion { name, version } => {
+                let mut app_plan = AppPlan::new(&name, PlanAction::Install, global)
+                    .with_reason("manifest will be generated from autoupdate");
+                app_plan.version = Some(version);
+                app_plan
+            }
+        };
+        execution_plan.apps.push(app_plan);
+    }
+    for (target, e) in failures {
+        execution_plan.apps.push(
+            AppPlan::new(&target, PlanAction::Unresolved, global).with_reason(format!("{e:#}")),
+        );
+    }
+    Ok(execution_plan)
+}
+
+/// 与 update_specific_app 相同的判断顺序, 已是最新版本时跳过
+pub fn plan_update_app(app_name: &str, options: &[UpdateOptions]) -> anyhow::Result<AppPlan> {
+    let global = options.contains(&UpdateOptions::Global);
+    if let Some(version) = check_app_version_latest(app_name, options)? {
+        let mut plan = AppPlan::new(app_name, PlanAction::Skip, global).with_reason("up to date");
+        plan.version = Some(version);
+        return Ok(plan);
+    }
+    let install_options = transform_update_options_to_install(options);
+    let manifest_path = find_app_manifest_path(app_name, &install_options)?;
+    let bucket = get_bucket_name_from_manifest_path(&manifest_path);
+    let mut plan = plan_install_from_manifest(&manifest_path, bucket.as_deref(), &install_options)?;
+    plan.action = PlanAction::Update;
+    if options.contains(&UpdateOptions::ForceUpdateOverride) {
+        let app_dir = if global {
+            get_app_dir_global(app_name)
+        } else {
+            get_app_dir(app_name)
+        };
+        plan.steps.insert(0, PlanStep::RemoveDir { path: app_dir });
+    } else if options.contains(&UpdateOptions::RemoveOldVersionApp) {
+        if let Ok(old_version_dir) = get_current_version_dir(app_name, options) {
+            plan.steps.push(PlanStep::RemoveDir {
+                path: old_version_dir.to_string_lossy().to_string(),
+            });
+        }
+    }
+    Ok(plan)
+}
+
+pub fn plan_update_all(options: &[UpdateOptions]) -> ExecutionPlan {
+    let global = options.contains(&UpdateOptions::Global);
+    let apps = get_all_installed_apps_name()
+        .into_iter()
+        .map(|app| {
+            plan_update_app(&app, options).unwrap_or_else(|e| {
+                AppPlan::new(&app, PlanAction::Unresolved, global).with_reason(format!("{e:#}"))
+            })
+        })
+        .collect();
+    ExecutionPlan { apps }
+}
+
+/// 与 uninstall_app 相同的顺序列出卸载步骤
+pub fn plan_uninstall_app(app_name: &str, global: bool, purge: bool) -> anyhow::Result<AppPlan> {
+    let (app_dir, current_dir, manifest_path, shims_dir, persist_dir, psmodule_dir) = if global {
+        (
+            get_app_dir_global(app_name),
+            get_app_current_dir_global(app_name),
+            get_app_dir_manifest_json_global(app_name),
+            get_shims_root_dir_global(),
+            get_persist_dir_path_global(),
+            get_psmodules_root_global_dir(),
+        )
+    } else {
+        (
+            get_app_dir(app_name),
+            get_app_current_dir(app_name),
+            get_app_dir_manifest_json(app_name),
+            get_shims_root_dir(),
+            get_persist_dir_path(),
+            get_psmodules_root_dir(),
+        )
+    };
+    if !Path::new(&app_dir).exists() {
+        anyhow::bail!("'{app_name}' is not installed")
+    }
+    let mut plan = AppPlan::new(app_name, PlanAction::Uninstall, global);
+    let manifest = read_manifest_obj(Path::new(&manifest_path)).unwrap_or_default();
+    let install_info =
+        read_manifest_obj(&Path::new(&current_dir).join("install.json")).unwrap_or_default();
+    let arch = install_info["architecture"].as_str().unwrap_or("64bit");
+    plan.version = manifest["version"]
+        .as_str()
+        .map(|version| version.to_string());
+
+    for stage in UNINSTALL_STAGES {
+        match stage {
+            UninstallStage::PreUninstall => {
+                plan.steps
+                    .extend(script_step(&manifest, arch, "pre_uninstall"));
+            }
+            UninstallStage::Uninstaller => {
+                plan.steps
+                    .extend(script_step(&manifest, arch, "uninstaller"));
+            }
+            UninstallStage::PostUninstall => {
+                plan.steps
+                    .extend(script_step(&manifest, arch, "post_uninstall"));
+            }
+            UninstallStage::PsModule => {
+                if let Some(module_name) = manifest["psmodule"]["name"].as_str() {
+                    plan.steps.push(PlanStep::RemovePsModule {
+                        link: format!("{psmodule_dir}\\{module_name}"),
+                    });
+                }
+            }
+            UninstallStage::EnvAddPath => {
+                if let Some(env_add_path) = manifest_value(&manifest, arch, "env_add_path") {
+                    for path in value_strings(env_add_path) {
+                        let path = if path == "." {
+                            current_dir.clone()
+                        } else {
+                            format!("{current_dir}\\{path}")
+                        };
+                        plan.steps.push(PlanStep::RemovePath { path });
+                    }
+                }
+            }
+            UninstallStage::EnvSet => {
+                if let Some(Value::Object(env_set)) = manifest_value(&manifest, arch, "env_set") {
+                    for name in env_set.keys() {
+                        plan.steps.push(PlanStep::RemoveEnv { name: name.clone() });
+                    }
+                }
+            }
+            UninstallStage::Shims => {
+                if let Some(bin) = manifest_value(&manifest, arch, "bin") {
+                    for (target, alias) in collect_bin_shims(bin) {
+                        for name in shim_file_names(&target, alias.as_deref()).unwrap_or_default() {
+                            plan.steps.push(PlanStep::RemoveShim {
+                                name: format!("{shims_dir}\\{name}"),
+                            });
+                        }
+                    }
+                }
+            }
+            UninstallStage::Shortcuts => {
+                if let Some(shortcuts) = manifest_value(&manifest, arch, "shortcuts") {
+                    let shortcuts_dir = get_all_shortcuts_link_paths(global);
+                    for (name, _) in collect_shortcuts(shortcuts) {
+                        plan.steps.push(PlanStep::RemoveShortcut {
+                            path: shortcuts_dir
+                                .join(format!("{name}.lnk"))
+                                .to_string_lossy()
+                                .to_string(),
+                        });
+                    }
+                }
+            }
+            UninstallStage::RemoveAppDir => {
+                plan.steps.push(PlanStep::RemoveDir {
+                    path: app_dir.clone(),
+                });
+            }
+        }
+    }
+    if purge {
+        plan.steps.push(PlanStep::RemoveDir {
+            path: format!("{persist_dir}\\{app_name}"),
+        });
+    }
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod test_plan {
+    #[allow(unused_imports)]
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_collect_bin_shims() {
+        let bin = json!([
+            "bin/gh.exe",
+            ["tools\\python.exe", "py3", "-u"],
+            ["run.ps1", ""]
+        ]);
+        assert_eq!(
+            collect_bin_shims(&bin),
+            vec![
+                ("bin/gh.exe".to_string(), None),
+                ("tools\\python.exe".to_string(), Some("py3".to_string())),
+                ("run.ps1".to_string(), Some("".to_string())),
+            ]
+        );
+        assert_eq!(
+            collect_bin_shims(&json!("demo.exe")),
+            vec![("demo.exe".to_string(), None)]
+        );
+    }
+
+    #[test]
+    fn test_collect_shortcuts_and_persist() {
+        assert_eq!(
+            collect_shortcuts(&json!(["demo.exe", "Demo"])),
+            vec![("Demo".to_string(), "demo.exe".to_string())]
+        );
+        assert_eq!(
+            collect_shortcuts(&json!([["a.exe", "A"], ["b.exe", "B", "--flag"]])).len(),
+            2
+        );
+        assert_eq!(
+            collect_persist(&json!(["data", ["conf.ini", "config.ini"]])),
+            vec![
+                ("data".to_string(), "data".to_string()),
+                ("conf.ini".to_string(), "config.ini".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_manifest_value_prefers_architecture() {
+        let manifest = json!({
+            "bin": "root.exe",
+            "architecture": { "64bit": { "bin": "x64.exe" } }
+        });
+        assert_eq!(
+            manifest_value(&manifest, "64bit", "bin"),
+            Some(&json!("x64.exe"))
+        );
+        assert_eq!(
+            manifest_value(&manifest, "32bit", "bin"),
+            Some(&json!("root.exe"))
+        );
+        assert_eq!(manifest_value(&manifest, "64bit", "persist"), None);
+    }
+
+    #[test]
+    fn test_plan_step_json() {
+        let step = PlanStep::Download {
+            url: "https://example.com/demo.zip".into(),
+            cache_path: "demo#1.0#abc.zip".into(),
+            cache_hit: false,
+            size: Some(2 * 1024 * 1024),
+        };
+        let value = serde_json::to_value(&step).unwrap();
+        assert_eq!(value["kind"], "download");
+        assert_eq!(
+            step.to_string(),
+            "download       https://example.com/demo.zip (2.0 MB)"
+        );
+    }
+}
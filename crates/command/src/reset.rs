@@ -106,11 +106,18 @@ fn reset_shim_file(app_name: &str, app_current_path: PathBuf, global: bool) -> a
     let bin = manifest.bin;
     let architecture = manifest.architecture;
     let arch = get_system_default_arch()?;
-    let options: Box<[InstallOptions]> = if global {
-        vec![InstallOptions::Global].into_boxed_slice()
-    } else {
-        vec![].into_boxed_slice()
-    };
+    let mut options = vec![];
+    if global {
+        options.push(InstallOptions::Global);
+    }
+    // 带上版本号, shim 才会指向 current 并写入 app 名
+    if let Some(version) = manifest.version {
+        options.push(InstallOptions::CurrentInstallApp {
+            app_name: app_name.to_string(),
+            app_version: version,
+        });
+    }
+    let options = options.into_boxed_slice();
 
     if bin.is_some() {
         create_shims_file(bin.unwrap(), app_name, &options)?;
@@ -0,0 +1,939 @@
+//! 声明式环境文件 `hpfile` (TOML 或 JSON), `hp sync` 比较文件与本机状态后收敛
+//!
+//! ```toml
+//! [buckets]
+//! main = "https://github.com/ScoopInstaller/Main"
+//! extras = { url = "https://github.com/ScoopInstaller/Extras", branch = "master" }
+//!
+//! [apps]
+//! git = "*"
+//! nodejs = { version = "20.11.1", arch = "64bit", held = true }
+//! python = ">=3.11, <3.13"
+//!
+//! [config]
+//! aria2-enabled = false
+//! ```
+use crate::buckets::{get_buckets_name, Buckets};
+use crate::depends::get_installed_dependents;
+use crate::import::{import_config, set_app_hold};
+use crate::init_env::{
+    get_apps_path, get_apps_path_global, get_buckets_root_dir_path, get_scoop_config_path,
+};
+use crate::install::{
+    find_app_manifest_path, get_install_reason, install_app, install_app_specific_version,
+    install_from_specific_bucket, InstallOptions, InstallReason,
+};
+use crate::lockfile::{collect_locked_apps, LockedApp};
+use crate::uninstall::uninstall_app;
+use crate::utils::version::Version;
+use anyhow::{anyhow, bail, Context};
+use git2::build::CheckoutBuilder;
+use git2::{BranchType, Repository};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// 当前目录下依次查找的文件名
+pub const HPFILE_NAMES: [&str; 3] = ["hpfile", "hpfile.toml", "hpfile.json"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BucketSpec {
+    pub url: String,
+    pub branch: Option<String>,
+    pub commit: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppSpec {
+    /// 版本约束, 例如 `*`、`1.2.3`、`>=1.2, <2`
+    pub version: Option<String>,
+    pub bucket: Option<String>,
+    pub arch: Option<String>,
+    #[serde(default)]
+    pub global: bool,
+    /// 未指定时不改变锁定状态
+    pub held: Option<bool>,
+}
+
+/// 只写 URL 或版本约束的简写形式
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum Entry<T> {
+    Short(String),
+    Full(T),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawHpFile {
+    #[serde(default)]
+    buckets: BTreeMap<String, Entry<BucketSpec>>,
+    #[serde(default)]
+    apps: BTreeMap<String, Entry<AppSpec>>,
+    #[serde(default)]
+    config: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HpFile {
+    pub buckets: BTreeMap<String, BucketSpec>,
+    pub apps: BTreeMap<String, AppSpec>,
+    pub config: Map<String, Value>,
+}
+
+impl HpFile {
+    /// 扩展名为 json 或内容以 `{` 开头时按 JSON 解析, 否则按 TOML 解析
+    pub fn parse(content: &str, file_name: &str) -> anyhow::Result<Self> {
+        let content = content.trim_start_matches('\u{feff}');
+        let is_json = match Path::new(file_name).extension() {
+            Some(ext) => ext.eq_ignore_ascii_case("json"),
+            None => content.trim_start().starts_with('{'),
+        };
+        let raw: RawHpFile = if is_json {
+            serde_json::from_str(content).context(format!("Failed to parse {file_name} as json"))?
+        } else {
+            toml::from_str(content).context(format!("Failed to parse {file_name} as toml"))?
+        };
+        let buckets = raw
+            .buckets
+            .into_iter()
+            .map(|(name, entry)| {
+                let spec = match entry {
+                    Entry::Short(url) => BucketSpec {
+                        url,
+                        ..Default::default()
+                    },
+                    Entry::Full(spec) => spec,
+                };
+                (name.to_lowercase(), spec)
+            })
+            .collect();
+        let apps = raw
+            .apps
+            .into_iter()
+            .map(|(name, entry)| {
+                let spec = match entry {
+                    Entry::Short(version) => AppSpec {
+                        version: Some(version),
+                        ..Default::default()
+                    },
+                    Entry::Full(spec) => spec,
+                };
+                (name.to_lowercase(), spec)
+            })
+            .collect::<BTreeMap<_, _>>();
+        for (name, spec) in &apps {
+            VersionConstraint::parse(spec.version.as_deref().unwrap_or_default())
+                .context(format!("Invalid version constraint of '{name}'"))?;
+        }
+        Ok(HpFile {
+            buckets,
+            apps,
+            config: raw.config,
+        })
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content =
+            std::fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+        Self::parse(&content, &path.to_string_lossy())
+    }
+
+    /// 在目录下按 HPFILE_NAMES 的顺序查找 hpfile
+    pub fn find_in(dir: &Path) -> Option<PathBuf> {
+        HPFILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+/// 逗号分隔的版本条件, 全部满足才匹配, 空或 `*` 匹配任意版本
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionConstraint(Vec<(CompareOp, String)>);
+
+impl VersionConstraint {
+    pub fn parse(constraint: &str) -> anyhow::Result<Self> {
+        let constraint = constraint.trim();
+        if constraint.is_empty() || constraint == "*" {
+            return Ok(Self(vec![]));
+        }
+        let conditions = constraint
+            .split(',')
+            .map(|condition| {
+                let condition = condition.trim();
+                let (op, version) = [
+                    (">=", CompareOp::Ge),
+                    ("<=", CompareOp::Le),
+                    (">", CompareOp::Gt),
+                    ("<", CompareOp::Lt),
+                    ("=", CompareOp::Eq),
+                ]
+                .iter()
+                .find_map(|(prefix, op)| condition.strip_prefix(prefix).map(|rest| (*op, rest)))
+                .unwrap_or((CompareOp::Eq, condition));
+                let version = version.trim();
+                if version.is_empty() || version.contains(char::is_whitespace) {
+                    bail!("Invalid version condition '{condition}'")
+                }
+                Ok((op, version.to_string()))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self(conditions))
+    }
+
+    pub fn is_any(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// 只有单个精确版本时返回该版本
+    pub fn exact(&self) -> Option<&str> {
+        match self.0.as_slice() {
+            [(CompareOp::Eq, version)] => Some(version),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, version: &str) -> bool {
+        let version = Version::new(version);
+        self.0.iter().all(|(op, expected)| {
+            let ordering = version.cmp(&Version::new(expected));
+            match op {
+                CompareOp::Eq => ordering.is_eq(),
+                CompareOp::Gt => ordering.is_gt(),
+                CompareOp::Ge => ordering.is_ge(),
+                CompareOp::Lt => ordering.is_lt(),
+                CompareOp::Le => ordering.is_le(),
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BucketState {
+    pub url: String,
+    /// 分离 HEAD 时为空
+    pub branch: Option<String>,
+    pub commit: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LocalState {
+    pub buckets: BTreeMap<String, BucketState>,
+    pub apps: Vec<LockedApp>,
+    /// 作为依赖安装的 App, prune 时不卸载, 由 autoremove 处理
+    pub dependencies: Vec<String>,
+    pub config: Map<String, Value>,
+}
+
+impl LocalState {
+    fn app(&self, name: &str, global: bool) -> Option<&LockedApp> {
+        self.apps
+            .iter()
+            .find(|app| app.name == name && app.global == global)
+    }
+}
+
+fn read_bucket_state(bucket_dir: &Path) -> Option<BucketState> {
+    let repo = Repository::open(bucket_dir).ok()?;
+    let url = repo.find_remote("origin").ok()?.url()?.to_string();
+    let head = repo.head().ok()?;
+    let commit = head.peel_to_commit().ok()?.id().to_string();
+    let branch = if repo.head_detached().unwrap_or(false) {
+        None
+    } else {
+        head.shorthand().map(|name| name.to_string())
+    };
+    Some(BucketState {
+        url,
+        branch,
+        commit,
+    })
+}
+
+/// 读取本机已添加的 bucket、已安装的 App 与配置
+pub fn read_local_state() -> anyhow::Result<LocalState> {
+    let bucket_root = get_buckets_root_dir_path();
+    let buckets = get_buckets_name()?
+        .into_iter()
+        .filter_map(|name| {
+            let state = read_bucket_state(&Path::new(&bucket_root).join(&name))?;
+            Some((name, state))
+        })
+        .collect();
+    let mut apps = collect_locked_apps(&get_apps_path(), false)?;
+    apps.extend(collect_locked_apps(&get_apps_path_global(), true)?);
+    let dependencies = apps
+        .iter()
+        .filter(|app| get_install_reason(&app.name, app.global) == InstallReason::Dependency)
+        .map(|app| app.name.clone())
+        .collect();
+    let config_path = get_scoop_config_path()?;
+    let content = std::fs::read_to_string(&config_path).unwrap_or_default();
+    let config = match serde_json::from_str::<Value>(content.trim_start_matches('\u{feff}')) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+    Ok(LocalState {
+        buckets,
+        apps,
+        dependencies,
+        config,
+    })
+}
+
+/// 收敛所需的一步修改, 按执行顺序排列
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SyncAction {
+    AddBucket {
+        name: String,
+    },
+    CheckoutBucket {
+        name: String,
+        from: String,
+    },
+    RemoveBucket {
+        name: String,
+    },
+    SetConfig {
+        key: String,
+        value: Value,
+    },
+    Install {
+        name: String,
+        version: Option<String>,
+    },
+    Update {
+        name: String,
+        from: String,
+        to: String,
+    },
+    Downgrade {
+        name: String,
+        from: String,
+        to: String,
+    },
+    Reinstall {
+        name: String,
+        reason: String,
+    },
+    Hold {
+        name: String,
+    },
+    Unhold {
+        name: String,
+    },
+    Uninstall {
+        name: String,
+        global: bool,
+    },
+    Unresolved {
+        name: String,
+        reason: String,
+    },
+}
+
+impl SyncAction {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SyncAction::AddBucket { .. } => "add bucket",
+            SyncAction::CheckoutBucket { .. } => "checkout bucket",
+            SyncAction::RemoveBucket { .. } => "remove bucket",
+            SyncAction::SetConfig { .. } => "set config",
+            SyncAction::Install { .. } => "install",
+            SyncAction::Update { .. } => "update",
+            SyncAction::Downgrade { .. } => "downgrade",
+            SyncAction::Reinstall { .. } => "reinstall",
+            SyncAction::Hold { .. } => "hold",
+            SyncAction::Unhold { .. } => "unhold",
+            SyncAction::Uninstall { .. } => "uninstall",
+            SyncAction::Unresolved { .. } => "unresolved",
+        }
+    }
+
+    pub fn target(&self) -> &str {
+        match self {
+            SyncAction::SetConfig { key, .. } => key,
+            SyncAction::AddBucket { name }
+            | SyncAction::CheckoutBucket { name, .. }
+            | SyncAction::RemoveBucket { name }
+            | SyncAction::Install { name, .. }
+            | SyncAction::Update { name, .. }
+            | SyncAction::Downgrade { name, .. }
+            | SyncAction::Reinstall { name, .. }
+            | SyncAction::Hold { name }
+            | SyncAction::Unhold { name }
+            | SyncAction::Uninstall { name, .. }
+            | SyncAction::Unresolved { name, .. } => name,
+        }
+    }
+}
+
+impl Display for SyncAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let detail = match self {
+            SyncAction::CheckoutBucket { from, .. } => format!(" (from {from})"),
+            SyncAction::SetConfig { value, .. } => format!(" = {value}"),
+            SyncAction::Install {
+                version: Some(version),
+                ..
+            } => format!(" ({version})"),
+            SyncAction::Update { from, to, .. } | SyncAction::Downgrade { from, to, .. } => {
+                format!(" ({from} -> {to})")
+            }
+            SyncAction::Reinstall { reason, .. } | SyncAction::Unresolved { reason, .. } => {
+                format!(" - {reason}")
+            }
+            SyncAction::Uninstall { global: true, .. } => " (global)".to_string(),
+            _ => String::new(),
+        };
+        write!(f, "{} {}{detail}", self.kind(), self.target())
+    }
+}
+
+fn normalize_git_url(url: &str) -> String {
+    url.trim()
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .to_lowercase()
+}
+
+fn bucket_ref_matches(spec: &BucketSpec, state: &BucketState) -> bool {
+    if let Some(commit) = &spec.commit {
+        return state.commit.starts_with(&commit.to_lowercase());
+    }
+    match &spec.branch {
+        Some(branch) => state.branch.as_deref() == Some(branch.as_str()),
+        None => true,
+    }
+}
+
+/// config.json 中的值可能被 `hp config` 写成字符串, 按字符串形式比较
+fn config_value_matches(current: Option<&Value>, desired: &Value) -> bool {
+    let as_text = |value: &Value| match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    current.is_some_and(|current| current == desired || as_text(current) == as_text(desired))
+}
+
+/// 比较 hpfile 与本机状态, 生成收敛步骤; latest_version 返回 bucket 中的最新版本,
+/// installed_dependents 返回仍依赖该 App 的已安装 App
+///
+/// 只有 prune 时才卸载未列出的 App, 并移除未列出且没有已安装 App 使用的 bucket
+pub fn plan_sync(
+    hpfile: &HpFile,
+    state: &LocalState,
+    prune: bool,
+    latest_version: impl Fn(&str, &AppSpec) -> Option<String>,
+    installed_dependents: impl Fn(&str, bool) -> anyhow::Result<Vec<String>>,
+) -> Vec<SyncAction> {
+    let mut actions = vec![];
+    for (name, spec) in &hpfile.buckets {
+        match state.buckets.get(name) {
+            None => actions.push(SyncAction::AddBucket { name: name.clone() }),
+            Some(current) if normalize_git_url(&current.url) != normalize_git_url(&spec.url) => {
+                actions.push(SyncAction::RemoveBucket { name: name.clone() });
+                actions.push(SyncAction::AddBucket { name: name.clone() });
+            }
+            Some(current) if !bucket_ref_matches(spec, current) => {
+                let from = current
+                    .branch
+                    .clone()
+                    .unwrap_or_else(|| current.commit.chars().take(7).collect());
+                actions.push(SyncAction::CheckoutBucket {
+                    name: name.clone(),
+                    from,
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, value) in &hpfile.config {
+        if !config_value_matches(state.config.get(key), value) {
+            actions.push(SyncAction::SetConfig {
+                key: key.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+
+    for (name, spec) in &hpfile.apps {
+        let constraint = VersionConstraint::parse(spec.version.as_deref().unwrap_or_default())
+            .unwrap_or(VersionConstraint(vec![]));
+        let installed = state.app(name, spec.global);
+        let satisfied = installed.is_some_and(|app| constraint.matches(&app.version));
+        // 不满足约束时的目标版本: 精确版本或满足约束的 bucket 最新版本
+        let target = if satisfied || (installed.is_none() && constraint.is_any()) {
+            None
+        } else if let Some(exact) = constraint.exact() {
+            Some(exact.to_string())
+        } else {
+            match latest_version(name, spec) {
+                Some(latest) if constraint.matches(&latest) => Some(latest),
+                latest => {
+                    let latest = latest.unwrap_or_else(|| "none".into());
+                    actions.push(SyncAction::Unresolved {
+                        name: name.clone(),
+                        reason: format!(
+                            "no version satisfies '{}', latest is {latest}",
+                            spec.version.as_deref().unwrap_or_default()
+                        ),
+                    });
+                    continue;
+                }
+            }
+        };
+        match installed {
+            None => actions.push(SyncAction::Install {
+                name: name.clone(),
+                version: target,
+            }),
+            Some(app) => {
+                if let Some(to) = target {
+                    let from = app.version.clone();
+                    if Version::new(&to) > Version::new(&from) {
+                        actions.push(SyncAction::Update {
+                            name: name.clone(),
+                            from,
+                            to,
+                        });
+                    } else {
+                        actions.push(SyncAction::Downgrade {
+                            name: name.clone(),
+                            from,
+                            to,
+                        });
+                    }
+                } else if let Some(arch) = spec.arch.as_deref() {
+                    if !app.architecture.is_empty() && app.architecture != arch {
+                        actions.push(SyncAction::Reinstall {
+                            name: name.clone(),
+                            reason: format!(
+                                "installed {} but {arch} is required",
+                                app.architecture
+                            ),
+                        });
+                    }
+                }
+                match spec.held {
+                    Some(true) if !app.held => {
+                        actions.push(SyncAction::Hold { name: name.clone() })
+                    }
+                    Some(false) if app.held => {
+                        actions.push(SyncAction::Unhold { name: name.clone() })
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+        }
+        if spec.held == Some(true) {
+            actions.push(SyncAction::Hold { name: name.clone() });
+        }
+    }
+
+    if prune {
+        // 保留的 App 仍需要来源 bucket 中的清单来更新
+        let mut used_buckets = hpfile
+            .apps
+            .values()
+            .filter_map(|spec| spec.bucket.clone())
+            .collect::<Vec<_>>();
+        for app in &state.apps {
+            let listed = hpfile
+                .apps
+                .get(&app.name)
+                .is_some_and(|spec| spec.global == app.global);
+            let protected = app.name == "hp" || state.dependencies.contains(&app.name);
+            if listed || protected {
+                used_buckets.push(app.source.to_lowercase());
+                continue;
+            }
+            // 与 `hp uninstall` 相同, 仍被已安装 App 依赖时不卸载
+            let reason = match installed_dependents(&app.name, app.global) {
+                Ok(dependents) if dependents.is_empty() => {
+                    actions.push(SyncAction::Uninstall {
+                        name: app.name.clone(),
+                        global: app.global,
+                    });
+                    continue;
+                }
+                Ok(dependents) => format!(
+                    "not pruned, required by installed app(s): {}",
+                    dependents.join(", ")
+                ),
+                Err(e) => format!("not pruned, {e:#}"),
+            };
+            actions.push(SyncAction::Unresolved {
+                name: app.name.clone(),
+                reason,
+            });
+            used_buckets.push(app.source.to_lowercase());
+        }
+        for name in state.buckets.keys() {
+            if !hpfile.buckets.contains_key(name) && !used_buckets.contains(name) {
+                actions.push(SyncAction::RemoveBucket { name: name.clone() });
+            }
+        }
+    }
+    actions
+}
+
+/// 依赖该 App 的已安装 App 名称, 用于 prune 前的反向依赖检查
+pub fn installed_dependent_names(name: &str, global: bool) -> anyhow::Result<Vec<String>> {
+    Ok(get_installed_dependents(name, global)?
+        .into_iter()
+        .map(|dependent| dependent.name)
+        .collect())
+}
+
+/// bucket 中当前的最新版本, 找不到清单时为 None
+pub fn bucket_latest_version(name: &str, spec: &AppSpec) -> Option<String> {
+    let options = app_options(spec);
+    let manifest_path = match &spec.bucket {
+        Some(bucket) => Path::new(&get_buckets_root_dir_path())
+            .join(bucket)
+            .join("bucket")
+            .join(format!("{name}.json")),
+        None => find_app_manifest_path(name, &options).ok()?,
+    };
+    let content = std::fs::read_to_string(manifest_path).ok()?;
+    let manifest = serde_json::from_str::<Value>(content.trim_start_matches('\u{feff}')).ok()?;
+    manifest["version"]
+        .as_str()
+        .map(|version| version.to_string())
+}
+
+/// 切换到指定分支或提交, 提交优先
+pub fn checkout_bucket_ref(bucket_dir: &Path, spec: &BucketSpec) -> anyhow::Result<()> {
+    let repo = Repository::open(bucket_dir)
+        .context(format!("Failed to open bucket {}", bucket_dir.display()))?;
+    let mut checkout = CheckoutBuilder::new();
+    checkout.force();
+    if let Some(commit) = &spec.commit {
+        let object = repo.revparse_single(commit).context(format!(
+            "Commit '{commit}' not found in {}, run `hp update` to fetch it",
+            bucket_dir.display()
+        ))?;
+        let commit = object.peel_to_commit()?;
+        repo.set_head_detached(commit.id())?;
+        repo.checkout_head(Some(&mut checkout))?;
+        return Ok(());
+    }
+    if let Some(branch) = &spec.branch {
+        let local = match repo.find_branch(branch, BranchType::Local) {
+            Ok(local) => local,
+            Err(_) => {
+                let upstream = format!("origin/{branch}");
+                let remote = repo
+                    .find_branch(&upstream, BranchType::Remote)
+                    .context(format!(
+                        "Branch '{branch}' not found in {}",
+                        bucket_dir.display()
+                    ))?;
+                let commit = remote.get().peel_to_commit()?;
+                let mut local = repo.branch(branch, &commit, false)?;
+                local.set_upstream(Some(&upstream))?;
+                local
+            }
+        };
+        let reference = local
+            .get()
+            .name()
+            .ok_or_else(|| anyhow!("Invalid branch name '{branch}'"))?
+            .to_string();
+        repo.set_head(&reference)?;
+        repo.checkout_head(Some(&mut checkout))?;
+    }
+    Ok(())
+}
+
+fn app_options(spec: &AppSpec) -> Vec<InstallOptions<'_>> {
+    let mut options = vec![];
+    if let Some(arch) = spec.arch.as_deref() {
+        options.push(InstallOptions::ArchOptions(arch));
+    }
+    if spec.global {
+        options.push(InstallOptions::Global);
+    }
+    options
+}
+
+fn install_latest(name: &str, spec: &AppSpec, options: &[InstallOptions]) -> anyhow::Result<()> {
+    match &spec.bucket {
+        Some(bucket) => install_from_specific_bucket(bucket, name, options),
+        None => install_app(name, options),
+    }
+}
+
+fn bucket_spec<'a>(hpfile: &'a HpFile, name: &str) -> anyhow::Result<&'a BucketSpec> {
+    hpfile
+        .buckets
+        .get(name)
+        .ok_or_else(|| anyhow!("bucket '{name}' is not listed in hpfile"))
+}
+
+fn app_spec<'a>(hpfile: &'a HpFile, name: &str) -> anyhow::Result<&'a AppSpec> {
+    hpfile
+        .apps
+        .get(name)
+        .ok_or_else(|| anyhow!("app '{name}' is not listed in hpfile"))
+}
+
+async fn apply_action(hpfile: &HpFile, action: &SyncAction) -> anyhow::Result<()> {
+    let bucket_dir = |name: &str| Path::new(&get_buckets_root_dir_path()).join(name);
+    match action {
+        SyncAction::AddBucket { name } => {
+            let spec = bucket_spec(hpfile, name)?;
+            Buckets::new()?.add_buckets(Some(name.clone()), Some(spec.url.clone()), false)?;
+            checkout_bucket_ref(&bucket_dir(name), spec)
+        }
+        SyncAction::CheckoutBucket { name, .. } => {
+            checkout_bucket_ref(&bucket_dir(name), bucket_spec(hpfile, name)?)
+        }
+        SyncAction::RemoveBucket { name } => Buckets::new()?.rm_buckets(name, false),
+        SyncAction::SetConfig { key, value } => {
+            import_config(&Map::from_iter([(key.clone(), value.clone())]))
+        }
+        SyncAction::Install { name, version } => {
+            let spec = app_spec(hpfile, name)?;
+            let options = app_options(spec);
+            match version {
+                Some(version) => install_app_specific_version(name, version, &options).await,
+                None => install_latest(name, spec, &options),
+            }
+        }
+        SyncAction::Update { name, to, .. } | SyncAction::Downgrade { name, to, .. } => {
+            let options = app_options(app_spec(hpfile, name)?);
+            install_app_specific_version(name, to, &options).await
+        }
+        SyncAction::Reinstall { name, .. } => {
+            let spec = app_spec(hpfile, name)?;
+            let mut options = app_options(spec);
+            options.push(InstallOptions::ForceInstallOverride);
+            install_latest(name, spec, &options)
+        }
+        SyncAction::Hold { name } => set_app_hold(name, app_spec(hpfile, name)?.global, true),
+        SyncAction::Unhold { name } => set_app_hold(name, app_spec(hpfile, name)?.global, false),
+        SyncAction::Uninstall { name, global } => uninstall_app(name, *global),
+        SyncAction::Unresolved { reason, .. } => Err(anyhow!("{reason}")),
+    }
+}
+
+/// 依次执行收敛步骤, 单步失败不会中断其余步骤
+pub async fn apply_sync(
+    hpfile: &HpFile,
+    actions: &[SyncAction],
+) -> Vec<(String, anyhow::Result<()>)> {
+    let mut results = vec![];
+    for action in actions {
+        let result = apply_action(hpfile, action).await;
+        results.push((action.to_string(), result));
+    }
+    results
+}
+
+#[cfg(test)]
+mod test_sync {
+    #[allow(unused_imports)]
+    use super::*;
+
+    fn installed(name: &str, version: &str, held: bool) -> LockedApp {
+        LockedApp {
+            name: name.into(),
+            source: "main".into(),
+            version: version.into(),
+            architecture: "64bit".into(),
+            held,
+            global: false,
+            urls: vec![],
+            hashes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_version_constraint() {
+        let constraint = VersionConstraint::parse(">=3.11, <3.13").unwrap();
+        assert!(constraint.matches("3.11.9"));
+        assert!(constraint.matches("3.12.0"));
+        assert!(!constraint.matches("3.13.1"));
+        assert!(!constraint.matches("3.10"));
+        assert_eq!(constraint.exact(), None);
+        assert_eq!(
+            VersionConstraint::parse("=20.11.1").unwrap().exact(),
+            Some("20.11.1")
+        );
+        assert!(VersionConstraint::parse("*").unwrap().is_any());
+        assert!(VersionConstraint::parse(">= ").is_err());
+    }
+
+    #[test]
+    fn test_parse_hpfile() {
+        let toml = r#"
+            [buckets]
+            main = "https://github.com/ScoopInstaller/Main"
+            extras = { url = "https://github.com/ScoopInstaller/Extras", branch = "dev" }
+
+            [apps]
+            git = "*"
+            NodeJS = { version = "20.11.1", held = true }
+
+            [config]
+            aria2-enabled = false
+        "#;
+        let hpfile = HpFile::parse(toml, "hpfile").unwrap();
+        assert_eq!(hpfile.buckets["extras"].branch.as_deref(), Some("dev"));
+        assert_eq!(hpfile.apps["nodejs"].held, Some(true));
+        assert_eq!(hpfile.config["aria2-enabled"], Value::Bool(false));
+
+        let json = r#"{ "buckets": { "main": "https://github.com/ScoopInstaller/Main" },
+                        "apps": { "git": { "version": "2.47.0", "arch": "64bit" } } }"#;
+        let from_json = HpFile::parse(json, "hpfile").unwrap();
+        assert_eq!(from_json.apps["git"].version.as_deref(), Some("2.47.0"));
+        assert!(HpFile::parse("[apps]\ngit = { versoin = \"1\" }", "hpfile.toml").is_err());
+        assert!(HpFile::parse("[apps]\ngit = \">= \"", "hpfile.toml").is_err());
+    }
+
+    #[test]
+    fn test_plan_sync() {
+        let hpfile = HpFile::parse(
+            r#"
+            [buckets]
+            main = "https://github.com/ScoopInstaller/Main"
+            extras = "https://github.com/ScoopInstaller/Extras"
+
+            [apps]
+            git = "*"
+            nodejs = { version = "20.11.1", held = true }
+            python = ">=3.11, <3.13"
+            gh = "*"
+
+            [config]
+            aria2-enabled = false
+            "#,
+            "hpfile",
+        )
+        .unwrap();
+        let state = LocalState {
+            buckets: BTreeMap::from([
+                (
+                    "main".to_string(),
+                    BucketState {
+                        url: "https://github.com/ScoopInstaller/Main.git".into(),
+                        branch: Some("master".into()),
+                        commit: "abc".into(),
+                    },
+                ),
+                (
+                    "old".to_string(),
+                    BucketState {
+                        url: "https://example.com/old".into(),
+                        branch: Some("master".into()),
+                        commit: "def".into(),
+                    },
+                ),
+            ]),
+            apps: vec![
+                installed("git", "2.47.0", false),
+                installed("nodejs", "22.0.0", false),
+                installed("python", "3.10.0", false),
+                installed("7zip", "24.08", false),
+                installed("dark", "3.14", false),
+            ],
+            dependencies: vec!["dark".into()],
+            config: Map::from_iter([("aria2-enabled".to_string(), Value::from("false"))]),
+        };
+        let no_dependents = |_: &str, _: bool| Ok(vec![]);
+        let actions = plan_sync(
+            &hpfile,
+            &state,
+            true,
+            |name, _| match name {
+                "python" => Some("3.12.4".into()),
+                _ => None,
+            },
+            no_dependents,
+        );
+        assert_eq!(
+            actions,
+            vec![
+                SyncAction::AddBucket {
+                    name: "extras".into()
+                },
+                SyncAction::Install {
+                    name: "gh".into(),
+                    version: None
+                },
+                SyncAction::Downgrade {
+                    name: "nodejs".into(),
+                    from: "22.0.0".into(),
+                    to: "20.11.1".into()
+                },
+                SyncAction::Hold {
+                    name: "nodejs".into()
+                },
+                SyncAction::Update {
+                    name: "python".into(),
+                    from: "3.10.0".into(),
+                    to: "3.12.4".into()
+                },
+                SyncAction::Uninstall {
+                    name: "7zip".into(),
+                    global: false
+                },
+                SyncAction::RemoveBucket { name: "old".into() },
+            ]
+        );
+        let actions = plan_sync(&hpfile, &state, false, |_, _| None, no_dependents);
+        assert!(actions.contains(&SyncAction::Unresolved {
+            name: "python".into(),
+            reason: "no version satisfies '>=3.11, <3.13', latest is none".into()
+        }));
+        assert!(!actions.iter().any(|action| matches!(
+            action,
+            SyncAction::Uninstall { .. } | SyncAction::RemoveBucket { .. }
+        )));
+
+        // 仍被已安装 App 依赖的 App 不卸载, 其 bucket 也保留
+        let actions = plan_sync(
+            &hpfile,
+            &state,
+            true,
+            |_, _| None,
+            |name, _| match name {
+                "7zip" => Ok(vec!["git".into()]),
+                _ => Ok(vec![]),
+            },
+        );
+        assert!(actions.contains(&SyncAction::Unresolved {
+            name: "7zip".into(),
+            reason: "not pruned, required by installed app(s): git".into()
+        }));
+        assert!(!actions
+            .iter()
+            .any(|action| matches!(action, SyncAction::Uninstall { .. })));
+
+        // 没有 [buckets] 时 prune 也不会移除已安装 App 所在的 bucket
+        let hpfile = HpFile::parse("[apps]\ngit = \"*\"", "hpfile").unwrap();
+        let actions = plan_sync(&hpfile, &state, true, |_, _| None, no_dependents);
+        assert!(!actions.contains(&SyncAction::RemoveBucket {
+            name: "main".into()
+        }));
+        assert!(actions.contains(&SyncAction::RemoveBucket { name: "old".into() }));
+    }
+}
@@ -0,0 +1,39 @@
+//! 单元测试共用的临时目录与本地 HTTP 替身
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+
+/// 清空并创建 `hp_<name>_<pid>` 临时目录, 不同测试使用不同的 name 避免并行冲突
+pub fn test_temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hp_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// 本地 HTTP 替身, handler 根据小写的请求文本返回状态行与响应体, HEAD 请求不发送响应体
+pub fn serve_http<F>(handler: F) -> String
+where
+    F: Fn(&str) -> (&'static str, Vec<u8>) + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buffer = [0u8; 4096];
+            let n = stream.read(&mut buffer).unwrap_or(0);
+            let request = String::from_utf8_lossy(&buffer[..n]).to_lowercase();
+            let (status, body) = handler(&request);
+            let header = format!(
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nAccept-Ranges: bytes\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            let _ = stream.write_all(header.as_bytes());
+            if !request.starts_with("head") {
+                let _ = stream.write_all(&body);
+            }
+        }
+    });
+    format!("http://{address}")
+}
@@ -13,6 +13,7 @@ use crate::init_env::{
 };
 use crate::install::LifecycleScripts::{PostUninstall, PreUninstall, Uninstaller};
 use crate::install::{parse_lifecycle_scripts, InstallOptions};
+use crate::plan::{UninstallStage, UNINSTALL_STAGES};
 use crate::utils::system::kill_processes_using_app;
 use shim_and_shortcuts::*;
 
@@ -191,46 +192,58 @@ fn uninstall_matched_app(
                 } else {
                     vec![]
                 };
-                parse_lifecycle_scripts(
-                    PreUninstall,
-                    manifest_path,
-                    &options,
-                    app_name,
-                    Some(arch),
-                )
-                .expect("Failed to run pre-uninstall lifecycle script");
-                println!(
-                    "{} '{}'  ({})",
-                    "Uninstalling".to_string().dark_blue().bold(),
-                    app_name.dark_red().bold(),
-                    version.dark_red().bold()
-                );
-                parse_lifecycle_scripts(Uninstaller, manifest_path, &options, app_name, Some(arch))
-                    .expect("Failed to run Uninstaller lifecycle script");
-                parse_lifecycle_scripts(
-                    PostUninstall,
-                    manifest_path,
-                    &options,
-                    app_name,
-                    Some(arch),
-                )
-                .expect("Failed to run PostUninstall lifecycle script");
-
-                // invoke_hook_script(HookType::Uninstaller, &manifest, arch)?;
-                // invoke_hook_script(HookType::PostUninstall, &manifest, arch)?;
-                uninstall_psmodule(&manifest, is_global)?;
-
-                env_path_var_rm(&current_path, &manifest, is_global)?;
-
-                env_var_rm(&manifest, is_global)?;
-                rm_shim_file(shim_path, &manifest, app_name)?;
-                rm_start_menu_shortcut(&manifest, is_global)?;
-                println!(
-                    "{} {}",
-                    "Unlinking".dark_blue().bold(),
-                    &current_path.display().to_string().dark_green().bold()
-                );
-                rm_all_dir(path.clone())?;
+                // 阶段顺序与 plan_uninstall_app 共用
+                for stage in UNINSTALL_STAGES {
+                    match stage {
+                        UninstallStage::PreUninstall => parse_lifecycle_scripts(
+                            PreUninstall,
+                            manifest_path,
+                            &options,
+                            app_name,
+                            Some(arch),
+                        )
+                        .expect("Failed to run pre-uninstall lifecycle script"),
+                        UninstallStage::Uninstaller => {
+                            println!(
+                                "{} '{}'  ({})",
+                                "Uninstalling".to_string().dark_blue().bold(),
+                                app_name.dark_red().bold(),
+                                version.as_str().dark_red().bold()
+                            );
+                            parse_lifecycle_scripts(
+                                Uninstaller,
+                                manifest_path,
+                                &options,
+                                app_name,
+                                Some(arch),
+                            )
+                            .expect("Failed to run Uninstaller lifecycle script")
+                        }
+                        UninstallStage::PostUninstall => parse_lifecycle_scripts(
+                            PostUninstall,
+                            manifest_path,
+                            &options,
+                            app_name,
+                            Some(arch),
+                        )
+                        .expect("Failed to run PostUninstall lifecycle script"),
+                        UninstallStage::PsModule => uninstall_psmodule(&manifest, is_global)?,
+                        UninstallStage::EnvAddPath => {
+                            env_path_var_rm(&current_path, &manifest, is_global)?
+                        }
+                        UninstallStage::EnvSet => env_var_rm(&manifest, is_global)?,
+                        UninstallStage::Shims => rm_shim_file(shim_path, &manifest, app_name)?,
+                        UninstallStage::Shortcuts => rm_start_menu_shortcut(&manifest, is_global)?,
+                        UninstallStage::RemoveAppDir => {
+                            println!(
+                                "{} {}",
+                                "Unlinking".dark_blue().bold(),
+                                &current_path.display().to_string().dark_green().bold()
+                            );
+                            rm_all_dir(path.clone())?;
+                        }
+                    }
+                }
                 return Ok(());
             }
         }
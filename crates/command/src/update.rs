@@ -5,7 +5,7 @@ use crate::utils::git::{
 };
 use rayon::prelude::*;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 pub(crate) mod update;
 use crate::init_env::{
     get_app_current_dir, get_app_current_dir_global, get_app_dir, get_app_dir_global,
@@ -14,6 +14,7 @@ use crate::install::InstallOptions::UpdateTransaction;
 use crate::install::UpdateOptions::{ForceUpdateOverride, Global, RemoveOldVersionApp};
 use crate::install::{install_app, InstallOptions, UpdateOptions};
 use crate::list::get_all_installed_apps_name;
+use crate::offline::is_offline_mode;
 use crate::utils::progrees_bar::{gen_stats_callback, ProgressOptions};
 use crate::utils::progrees_bar::{
     indicatif::{MultiProgress, ProgressBar, ProgressFinish},
@@ -22,7 +23,6 @@ use crate::utils::progrees_bar::{
 use crate::utils::request::get_git_repo_remote_url;
 use crate::utils::system::kill_processes_using_app;
 use crate::utils::utility::get_official_bucket_path;
-#[allow(unused_imports)]
 use anyhow::bail;
 use anyhow::Context;
 use crossterm::style::Stylize;
@@ -33,31 +33,28 @@ const FINISH_MESSAGE: &str = "✅";
 
 pub fn update_all_apps(options: &[UpdateOptions]) -> Result<(), anyhow::Error> {
     let all_apps_name = get_all_installed_apps_name();
+    let mut failed_apps = vec![];
     for app in all_apps_name {
-        let _ = match check_app_version_latest(&app, &options) {
-            Ok(version) => {
-                if version.is_some() {
-                    continue;
-                }
-            }
-            Err(err) => {
-                eprintln!("{}", err.to_string().dark_red().bold());
-                let app_current = if options.contains(&Global) {
-                    get_app_current_dir_global(&app)
-                } else {
-                    get_app_current_dir(&app)
-                };
-                fs::remove_dir_all(&app_current).context(format!(
-                    "Failed to remove broken link current {app_current} at 50"
-                ))?;
-            }
-        };
-        update_specific_app(&app, options)?;
+        // 检查失败(例如 current 损坏)时交给 update_specific_app 处理
+        if let Ok(Some(_)) = check_app_version_latest(&app, &options) {
+            continue;
+        }
+        // 单个 App 更新失败时已回滚到旧版本, 继续更新其余 App
+        if let Err(err) = update_specific_app(&app, options) {
+            eprintln!("{}", format!("{err:#}").dark_red().bold());
+            failed_apps.push(app);
+        }
+    }
+    if !failed_apps.is_empty() {
+        bail!("Failed to update: {}", failed_apps.join(", "))
     }
     Ok(())
 }
 
-pub fn remove_old_version(app_name: &str, options: &[UpdateOptions]) -> anyhow::Result<()> {
+pub fn get_current_version_dir(
+    app_name: &str,
+    options: &[UpdateOptions],
+) -> anyhow::Result<PathBuf> {
     let app_current_dir = if options.contains(&Global) {
         get_app_current_dir_global(app_name)
     } else {
@@ -65,6 +62,11 @@ pub fn remove_old_version(app_name: &str, options: &[UpdateOptions]) -> anyhow::
     };
     let target_version_path = fs::read_link(app_current_dir)
         .context(format!("failed to read link of {} at line 67", app_name))?;
+    Ok(target_version_path)
+}
+
+/// 删除旧版本目录, 需在新版本安装成功后调用, 否则失败时无法回滚到旧版本
+pub fn remove_old_version(app_name: &str, target_version_path: &Path) -> anyhow::Result<()> {
     log::debug!("target_version_path: {:?}", target_version_path);
     let result = fs::remove_dir_all(&target_version_path).context(format!(
         "failed to remove target version of {} at line 70",
@@ -116,17 +118,7 @@ pub fn update_specific_app(app_name: &str, options: &[UpdateOptions]) -> Result<
     let origin_options = options.to_vec();
     let options = transform_update_options_to_install(options);
 
-    if origin_options.contains(&ForceUpdateOverride) {
-        let special_app_dir = if origin_options.contains(&Global) {
-            get_app_dir_global(&app_name)
-        } else {
-            get_app_dir(&app_name)
-        };
-        if Path::new(&special_app_dir).exists() {
-            fs::remove_dir_all(special_app_dir)
-                .context("Failed to remove old version of app at 102")?;
-        }
-    };
+    // ForceUpdateOverride 会转为 ForceInstallOverride, 旧目录由安装事务移走, 失败时可以回滚
     let _ = match check_app_version_latest(&app_name, &origin_options) {
         Ok(version) => {
             if version.is_some() {
@@ -142,25 +134,34 @@ pub fn update_specific_app(app_name: &str, options: &[UpdateOptions]) -> Result<
             }
         }
         Err(err) => {
-            eprintln!("{}", err.to_string().dark_red().bold());
-            let app_current = if origin_options.contains(&Global) {
-                get_app_current_dir_global(&app_name)
+            let app_dir = if origin_options.contains(&Global) {
+                get_app_dir_global(&app_name)
             } else {
-                get_app_current_dir(&app_name)
+                get_app_dir(&app_name)
             };
-            fs::remove_dir_all(&app_current).context(format!(
-                "Failed to remove broken link current {app_current} at 126"
-            ))?;
+            if !Path::new(&app_dir).exists() {
+                return Err(err);
+            }
+            // 损坏的 current 由安装时的链接步骤替换并记录到事务, 这里不提前删除
+            eprintln!("{}", err.to_string().dark_red().bold());
         }
     };
 
-    if origin_options.contains(&RemoveOldVersionApp)
+    let old_version_dir = if origin_options.contains(&RemoveOldVersionApp)
         && app_name != "hp"
         && !origin_options.contains(&ForceUpdateOverride)
     {
-        remove_old_version(&app_name, &origin_options)?;
-    }
+        get_current_version_dir(&app_name, &origin_options).ok()
+    } else {
+        None
+    };
     install_app(&app_name, options.as_ref())?;
+    if let Some(old_version_dir) = old_version_dir {
+        let current_version_dir = get_current_version_dir(&app_name, &origin_options)?;
+        if current_version_dir != old_version_dir {
+            remove_old_version(&app_name, &old_version_dir)?;
+        }
+    }
     Ok(())
 }
 
@@ -205,6 +206,13 @@ pub fn update_scoop_bar() -> anyhow::Result<()> {
 }
 
 pub fn update_all_buckets_bar_serial() -> anyhow::Result<()> {
+    if is_offline_mode() {
+        println!(
+            "{}",
+            "Offline mode, skip updating buckets".dark_yellow().bold()
+        );
+        return Ok(());
+    }
     let progress_style = style(Some(ProgressOptions::Hide), Some(Message::suffix()));
     let official_buckets = get_include_buckets_name()?;
     let longest_bucket_name = official_buckets
@@ -245,6 +253,13 @@ pub fn update_all_buckets_bar_serial() -> anyhow::Result<()> {
 }
 
 pub fn update_all_buckets_bar_parallel() -> anyhow::Result<()> {
+    if is_offline_mode() {
+        println!(
+            "{}",
+            "Offline mode, skip updating buckets".dark_yellow().bold()
+        );
+        return Ok(());
+    }
     let progress_style = style(Some(ProgressOptions::Hide), Some(Message::suffix()));
     let official_buckets = get_include_buckets_name()?;
     let longest_bucket_name = official_buckets
@@ -8,7 +8,9 @@ use crate::list::VersionJSON;
 use crate::manifest::manifest::{
     get_latest_app_version_from_local_bucket, get_latest_app_version_from_local_bucket_global,
 };
+use crate::offline::is_offline_mode;
 use crate::utils::utility::{get_official_bucket_path, get_official_buckets_name};
+use crate::utils::version::Version;
 use anyhow::{bail, Context};
 use crossterm::style::Stylize;
 use git2::{FetchOptions, ProxyOptions, Repository};
@@ -17,6 +19,9 @@ use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 pub fn check_bucket_update_status<'a>() -> anyhow::Result<bool> {
+    if is_offline_mode() {
+        return Ok(false);
+    }
     let official_buckets = get_official_buckets_name();
     let official_buckets_path = official_buckets
         .iter()
@@ -130,7 +135,10 @@ pub fn check_app_version_latest(
             } else {
                 get_latest_app_version_from_local_bucket(app_name)?
             };
-            if old_version == latest_version {
+            // 本地版本高于 bucket 中的版本时同样视为最新, nightly 版本号不变, 因此也不会自动更新
+            if old_version == latest_version
+                || Version::new(&old_version) > Version::new(&latest_version)
+            {
                 Ok(Some(old_version))
             } else {
                 Ok(None)
@@ -1,4 +1,5 @@
 use crate::config::get_config_value_no_print;
+use crate::offline::ensure_online;
 use crate::utils::pull::run_pull;
 use anyhow::{bail, Context};
 use git2::build::RepoBuilder;
@@ -94,6 +95,7 @@ pub fn current_branch(repo: gix::Repository) -> anyhow::Result<String> {
 }
 
 pub fn remote_latest_scoop_commit() -> anyhow::Result<git2::Oid> {
+    ensure_online("Fetching the latest scoop commit")?;
     let scoop_path = get_local_scoop_git()?; // 你自己的路径获取函数
     let repo = Repository::open(&scoop_path)
         .with_context(|| format!("Failed to open git repo at {:?}", scoop_path))?;
@@ -141,6 +143,7 @@ pub fn remote_latest_scoop_commit() -> anyhow::Result<git2::Oid> {
 }
 
 pub fn remote_latest_scoop_commit__() -> anyhow::Result<ObjectId> {
+    ensure_online("Fetching the latest scoop commit")?;
     let scoop_path = get_local_scoop_git()?;
     let repo = gix::open(scoop_path)?;
     let remote = repo
@@ -180,6 +183,7 @@ struct Release {
 }
 
 pub async fn get_latest_release_version() -> anyhow::Result<String> {
+    ensure_online("Checking the latest scoop release")?;
     use reqwest::header;
     let mut headers = header::HeaderMap::new();
     headers.insert(
@@ -246,6 +250,7 @@ pub fn get_local_scoop_git() -> anyhow::Result<String> {
 pub fn git_pull_update_repo_with_scoop(
     callback: impl Fn(Progress, bool) -> bool + Sized,
 ) -> anyhow::Result<()> {
+    ensure_online("Pulling scoop")?;
     let scoop_path = get_local_scoop_git()?;
     let repo = Repository::open(&scoop_path)?;
     let remote = repo.find_remote("origin")?;
@@ -264,6 +269,7 @@ pub fn git_pull_update_repo<'a>(
     repo_path: &str,
     callback: crate::utils::pull::ProgressCallback<'_>,
 ) -> anyhow::Result<()> {
+    ensure_online(&format!("Pulling {repo_path}"))?;
     let repo = Repository::open(repo_path)?;
     use crate::utils::pull::RepoArgs;
     let remote_name = repo
@@ -351,6 +357,7 @@ fn start_fetch(
 }
 
 pub fn pull_special_local_repo(repo_path: &str) -> anyhow::Result<()> {
+    ensure_online(&format!("Pulling {repo_path}"))?;
     let branch = "master";
     let hp_url = "https://gitee.com/SuperWindcloud/hyperscoop_bucket.git";
     if Path::new(repo_path).exists() {
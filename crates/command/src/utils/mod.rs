@@ -9,4 +9,5 @@ pub mod  system ;
 pub mod  git; 
 pub mod  invoke_hook_script ;
 pub  mod  utility; 
+pub mod version;
 pub mod  pull ;
\ No newline at end of file
@@ -1,4 +1,5 @@
 ﻿use crate::config::get_config_value_no_print;
+use crate::offline::ensure_online;
 use anyhow::{bail, Context};
 use crossterm::style::Stylize;
 use dialoguer::theme::ColorfulTheme;
@@ -18,6 +19,7 @@ pub async fn request_download_git_repo(
     url: &str,
     download_path: &str,
 ) -> Result<String, anyhow::Error> {
+    ensure_online(&format!("Downloading {url}"))?;
     let mut url = url.to_string();
     let mut branch_flag = "-master".to_string();
     if url.contains(".git") {
@@ -102,6 +104,7 @@ pub fn request_download_git_clone(
     repo_url: &str,
     destination: &str,
 ) -> Result<String, anyhow::Error> {
+    ensure_online(&format!("Cloning {repo_url}"))?;
     if Path::new(destination).exists() {
         remove_dir_all(destination).expect("Failed to delete directory for bucket ");
     }
@@ -234,6 +237,7 @@ pub fn request_git_clone_by_git2(
     repo_url: &str,
     destination: String,
 ) -> Result<String, anyhow::Error> {
+    ensure_online(&format!("Cloning {repo_url}"))?;
     if Path::new(&destination).exists() {
         remove_dir_all(&destination).expect("Failed to delete directory for bucket ");
     }
@@ -251,6 +255,7 @@ pub fn request_git_clone_by_git2_with_progress(
     repo_url: &str,
     destination: &String,
 ) -> Result<String, anyhow::Error> {
+    ensure_online(&format!("Cloning {repo_url}"))?;
     if Path::new(destination).exists() {
         let proceed = Confirm::with_theme(&ColorfulTheme::default())
             .with_prompt(format!("目录 {} 已存在，是否删除?", destination))
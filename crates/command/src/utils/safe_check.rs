@@ -1,4 +1,5 @@
-﻿use std::fs::{read_dir};
+﻿use anyhow::bail;
+use std::fs::{read_dir};
 use std::path::PathBuf;
 
 pub fn is_directory_empty(path: &PathBuf) -> bool {
@@ -17,3 +18,56 @@ pub fn is_directory_empty(path: &PathBuf) -> bool {
     }
   }
 }
+
+/// 清单或压缩包提供的相对路径不安全时返回原因, 拒绝 `..`、绝对路径、盘符、UNC 和 `\\?\` 前缀
+pub fn unsafe_relative_path_reason(path: &str) -> Option<&'static str> {
+  let path = path.trim();
+  if path.starts_with("\\\\") || path.starts_with("//") {
+    return Some("UNC or device path is not allowed");
+  }
+  if path.starts_with('\\') || path.starts_with('/') {
+    return Some("absolute path is not allowed");
+  }
+  if path.contains(':') {
+    return Some("drive prefix or stream name is not allowed");
+  }
+  // Windows 会去掉末尾的点和空格, `.. ` 和 `...` 同样指向上级目录
+  let escapes = path
+    .split(['\\', '/'])
+    .any(|component| {
+      component.starts_with("..") && component.trim_end_matches(['.', ' ']).is_empty()
+    });
+  if escapes {
+    return Some("parent directory is not allowed");
+  }
+  None
+}
+
+/// 校验清单中 extract_dir, extract_to, persist, bin, shortcuts 等字段, 确保不会跳出应用目录
+pub fn check_manifest_relative_path(field: &str, path: &str) -> anyhow::Result<()> {
+  if let Some(reason) = unsafe_relative_path_reason(path) {
+    bail!("Manifest field '{field}' has unsafe path '{path}': {reason}")
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod test_safe_check {
+  #[allow(unused_imports)]
+  use super::*;
+
+  #[test]
+  fn test_unsafe_relative_path() {
+    for path in ["", "bin", "bin\\app.exe", "./data", "app-1.0/conf", "..app"] {
+      assert!(unsafe_relative_path_reason(path).is_none(), "{path}");
+    }
+    for path in [
+      "..", "..\\..\\Windows", "bin/../../x", ".. ", "...", "C:\\Windows", "C:app",
+      "\\Windows", "/etc", "\\\\server\\share", "\\\\?\\C:\\x", "file.txt:stream",
+    ] {
+      assert!(unsafe_relative_path_reason(path).is_some(), "{path}");
+    }
+    let error = check_manifest_relative_path("persist", "..\\x").unwrap_err();
+    assert!(error.to_string().contains("persist"));
+  }
+}
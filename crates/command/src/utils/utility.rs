@@ -1,8 +1,9 @@
 use crate::init_env::init_user_scoop;
-use crate::install::InstallOptions;
 use crate::install::InstallOptions::InteractiveInstall;
+use crate::install::{journal_write_file, InstallOptions};
 use crate::merge::Merge;
 use crate::utils::system::get_system_current_time;
+use crate::utils::version::Version;
 use anyhow::{bail, Context};
 use chrono::Local;
 use crossterm::style::Stylize;
@@ -16,25 +17,9 @@ use std::path::{Path};
 use textwrap::LineEnding;
 use url::Url;
 
+/// 按 Scoop 规则比较版本号, 见 [`Version`]
 pub fn compare_versions(ver1: String, ver2: String) -> Ordering {
-    // 分割版本号并转换为数字数组
-    let v1: Vec<i32> = ver1.split('.').flat_map(|s| s.parse()).collect();
-    let v2: Vec<i32> = ver2.split('.').flat_map(|s| s.parse()).collect();
-
-    // 动态比较每一级（自动补零）
-    let max_len = v1.len().max(v2.len());
-    (0..max_len)
-        .map(|i| {
-            (
-                v1.get(i).copied().unwrap_or(0),
-                v2.get(i).copied().unwrap_or(0),
-            )
-        })
-        .find_map(|(a, b)| match a.cmp(&b) {
-            Ordering::Equal => None,
-            diff => Some(diff),
-        })
-        .unwrap_or(Ordering::Equal)
+    Version::new(&ver1).cmp(&Version::new(&ver2))
 }
 
 pub fn add_key_value_to_json(
@@ -310,6 +295,7 @@ pub fn write_utf8_file(path: &str, content: &str, option: &[InstallOptions]) ->
             log::warn!("{}", "覆盖写入".dark_yellow().bold());
         }
     }
+    journal_write_file(Path::new(path))?;
     let mut file =
         File::create(path).context(format!("Failed to create utf8 file {} at line 281", path))?;
     /*
@@ -0,0 +1,208 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+static LETTERS_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[a-zA-Z]+").unwrap());
+static PRE_RELEASE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)alpha|beta|rc|pre").unwrap());
+
+/// 与 Scoop 的 Compare-Version 规则一致的版本号, 只用于比较, 原始字符串保持不变
+///
+/// - `-` 分隔的各段依次比较, 段内含 `.` 或 `_` 时再按其分隔比较
+/// - 纯数字按数值比较, 其余按不区分大小写的字符串比较
+/// - 字母与数字之间视为分隔, `1.1.1w` 大于 `1.1.1`
+/// - 多出的段为 alpha/beta/rc/pre 时版本更小, 否则更大
+/// - `+` 视为 `-`, 构建元数据被当作正式版之后的版本
+/// - `nightly` 版本之间总是相等
+#[derive(Debug, Clone, Default)]
+pub struct Version(String);
+
+impl Version {
+    pub fn new(version: &str) -> Self {
+        Self(version.trim().to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_nightly(&self) -> bool {
+        self.0.to_lowercase().starts_with("nightly")
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<&str> for Version {
+    fn from(version: &str) -> Self {
+        Self::new(version)
+    }
+}
+
+impl From<String> for Version {
+    fn from(version: String) -> Self {
+        Self::new(&version)
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let left = self.0.replace('+', "-");
+        let right = other.0.replace('+', "-");
+        compare_parts(&left, &right, Delimiter::Dash)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Delimiter {
+    Dash,
+    Dot,
+}
+
+impl Delimiter {
+    fn is_delimiter(&self, c: char) -> bool {
+        match self {
+            Delimiter::Dash => c == '-',
+            Delimiter::Dot => c == '.' || c == '_',
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Delimiter::Dash => "-",
+            Delimiter::Dot => ".",
+        }
+    }
+}
+
+/// 字母串两侧补上分隔符后再切分, 空段被丢弃
+fn split_version(version: &str, delimiter: Delimiter) -> Vec<String> {
+    let separated = LETTERS_REGEX.replace_all(version, |caps: &regex::Captures| {
+        format!("{}{}{}", delimiter.as_str(), &caps[0], delimiter.as_str())
+    });
+    separated
+        .split(|c| delimiter.is_delimiter(c))
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_string())
+        .collect()
+}
+
+fn is_pre_release(part: &str) -> bool {
+    PRE_RELEASE_REGEX.is_match(part)
+}
+
+fn compare_parts(left: &str, right: &str, delimiter: Delimiter) -> Ordering {
+    if left.eq_ignore_ascii_case(right) {
+        return Ordering::Equal;
+    }
+    let left_parts = split_version(left, delimiter);
+    let right_parts = split_version(right, delimiter);
+
+    let is_nightly = |parts: &[String]| {
+        parts
+            .first()
+            .is_some_and(|part| part.eq_ignore_ascii_case("nightly"))
+    };
+    if is_nightly(&left_parts) && is_nightly(&right_parts) {
+        return Ordering::Equal;
+    }
+
+    for i in 0..left_parts.len().max(right_parts.len()) {
+        let (Some(left_part), Some(right_part)) = (left_parts.get(i), right_parts.get(i)) else {
+            // 1.1-alpha 小于 1.1, 1.1.1w 大于 1.1.1
+            return match (left_parts.get(i), right_parts.get(i)) {
+                (None, Some(right_part)) if is_pre_release(right_part) => Ordering::Greater,
+                (None, _) => Ordering::Less,
+                (Some(left_part), None) if is_pre_release(left_part) => Ordering::Less,
+                _ => Ordering::Greater,
+            };
+        };
+
+        let has_dot = |part: &str| part.contains(['.', '_']);
+        if delimiter == Delimiter::Dash && (has_dot(left_part) || has_dot(right_part)) {
+            match compare_parts(left_part, right_part, Delimiter::Dot) {
+                Ordering::Equal => continue,
+                result => return result,
+            }
+        }
+
+        let result = match (left_part.parse::<u64>(), right_part.parse::<u64>()) {
+            (Ok(left_number), Ok(right_number)) => left_number.cmp(&right_number),
+            _ => left_part.to_lowercase().cmp(&right_part.to_lowercase()),
+        };
+        if result != Ordering::Equal {
+            return result;
+        }
+    }
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod test_version {
+    #[allow(unused_imports)]
+    use super::*;
+
+    fn assert_less(smaller: &str, bigger: &str) {
+        assert!(
+            Version::new(smaller) < Version::new(bigger),
+            "{smaller} should be less than {bigger}"
+        );
+        assert!(Version::new(bigger) > Version::new(smaller));
+    }
+
+    #[test]
+    fn test_numeric_versions() {
+        assert_less("1.2.3", "1.2.10");
+        assert_less("1.9", "1.10.0");
+        assert_less("1.2", "1.2.1");
+        assert_eq!(Version::new("1.2.3"), Version::new("1.2.3"));
+        assert_less("", "0.1");
+    }
+
+    #[test]
+    fn test_pre_release_and_build() {
+        assert_less("1.2.0-beta.3", "1.2.0");
+        assert_less("1.2.0-alpha", "1.2.0-beta");
+        assert_less("1.2.0-beta.2", "1.2.0-beta.10");
+        assert_less("2.0.0-rc1", "2.0.0");
+        assert_less("1.0.0-pre", "1.0.0");
+        assert_less("1.0.0", "1.0.0+build.5");
+    }
+
+    #[test]
+    fn test_dates_letters_and_separators() {
+        assert_less("2024-05-01", "2024-5-10");
+        assert_less("2023-12-31", "2024-01-01");
+        assert_less("1.1.1", "1.1.1w");
+        assert_less("1.1.1v", "1.1.1w");
+        assert_less("1.2_3", "1.2.4");
+        assert_less("8.0.0p1", "8.1p1");
+    }
+
+    #[test]
+    fn test_nightly_versions() {
+        assert_eq!(
+            Version::new("nightly-20240101"),
+            Version::new("nightly-20240501")
+        );
+        assert!(Version::new("nightly-20240101").is_nightly());
+    }
+}
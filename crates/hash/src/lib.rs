@@ -0,0 +1,249 @@
+//! 流式文件哈希, 原生支持 md5/sha1/sha256/sha512, 可并行校验多个文件
+use anyhow::{bail, Context};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::digest::DynDigest;
+use sha2::{Sha256, Sha512};
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// 每次读取 1 MiB, 内存占用与文件大小无关
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+        }
+    }
+
+    fn hasher(&self) -> Box<dyn DynDigest + Send> {
+        match self {
+            HashAlgorithm::Md5 => Box::new(Md5::default()),
+            HashAlgorithm::Sha1 => Box::new(Sha1::default()),
+            HashAlgorithm::Sha256 => Box::new(Sha256::default()),
+            HashAlgorithm::Sha512 => Box::new(Sha512::default()),
+        }
+    }
+}
+
+impl Display for HashAlgorithm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "md5" => Ok(HashAlgorithm::Md5),
+            "sha1" => Ok(HashAlgorithm::Sha1),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            "sha512" => Ok(HashAlgorithm::Sha512),
+            other => bail!("Unsupported hash algorithm '{other}'"),
+        }
+    }
+}
+
+/// 清单中的哈希, 形如 `sha1:<hex>`, 没有前缀时为 sha256
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedHash {
+    pub algorithm: HashAlgorithm,
+    pub value: String,
+}
+
+impl ExpectedHash {
+    pub fn new(algorithm: HashAlgorithm, value: &str) -> Self {
+        Self {
+            algorithm,
+            value: value.trim().to_lowercase(),
+        }
+    }
+
+    pub fn parse(hash: &str) -> anyhow::Result<Self> {
+        match hash.trim().split_once(':') {
+            Some((algorithm, value)) => Ok(Self::new(algorithm.parse()?, value)),
+            None => Ok(Self::new(HashAlgorithm::Sha256, hash)),
+        }
+    }
+}
+
+/// 增量计算哈希, 适合边下载边计算
+pub struct StreamHasher {
+    algorithm: HashAlgorithm,
+    hasher: Box<dyn DynDigest + Send>,
+}
+
+impl StreamHasher {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        Self {
+            algorithm,
+            hasher: algorithm.hasher(),
+        }
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+
+    /// 小写十六进制
+    pub fn finalize(self) -> String {
+        hex::encode(self.hasher.finalize())
+    }
+}
+
+/// 按块读取计算哈希, 每读取一块回调一次已处理的字节数
+pub fn hash_reader<R: Read>(
+    mut reader: R,
+    algorithm: HashAlgorithm,
+    mut on_progress: impl FnMut(u64),
+) -> std::io::Result<String> {
+    let mut hasher = StreamHasher::new(algorithm);
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut processed = 0u64;
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buffer[..n]);
+        processed += n as u64;
+        on_progress(processed);
+    }
+    Ok(hasher.finalize())
+}
+
+pub fn hash_file(path: impl AsRef<Path>, algorithm: HashAlgorithm) -> anyhow::Result<String> {
+    let path = path.as_ref();
+    let file = File::open(path).context(format!("Failed to open {}", path.display()))?;
+    hash_reader(file, algorithm, |_| {}).context(format!("Failed to read {}", path.display()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashJob {
+    pub path: PathBuf,
+    pub expected: ExpectedHash,
+}
+
+fn verify_file(job: &HashJob, on_progress: impl Fn(u64, u64)) -> anyhow::Result<()> {
+    let path = job.path.as_path();
+    let file = File::open(path).context(format!("Failed to open cache file {}", path.display()))?;
+    let total = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+    let actual = hash_reader(file, job.expected.algorithm, |processed| {
+        on_progress(processed, total)
+    })
+    .context(format!("Failed to read {}", path.display()))?;
+    if actual != job.expected.value {
+        bail!(
+            "{} 文件哈希校验失败\n期望hash: {}\n实际hash: {}",
+            path.display(),
+            job.expected.value,
+            actual
+        )
+    }
+    Ok(())
+}
+
+/// 每个文件一个线程并行校验, 结果顺序与 jobs 一致
+///
+/// on_progress 参数为 (文件序号, 已处理字节数, 文件总字节数)
+pub fn verify_files(
+    jobs: &[HashJob],
+    on_progress: impl Fn(usize, u64, u64) + Sync,
+) -> Vec<anyhow::Result<()>> {
+    let on_progress = &on_progress;
+    std::thread::scope(|scope| {
+        let handles = jobs
+            .iter()
+            .enumerate()
+            .map(|(index, job)| {
+                scope.spawn(move || {
+                    verify_file(job, |processed, total| on_progress(index, processed, total))
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("Hash thread panicked")))
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_hash_reader_all_algorithms() {
+        let data = b"hello world";
+        let hash = |algorithm| hash_reader(&data[..], algorithm, |_| {}).unwrap();
+        assert_eq!(hash(HashAlgorithm::Md5), "5eb63bbbe01eeed093cb22bb8f5acdc3");
+        assert_eq!(
+            hash(HashAlgorithm::Sha1),
+            "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed"
+        );
+        assert_eq!(
+            hash(HashAlgorithm::Sha256),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+        assert!(hash(HashAlgorithm::Sha512).starts_with("309ecc489c12d6eb4cc40f50c902f2b4"));
+    }
+
+    #[test]
+    fn test_parse_expected_hash() {
+        let hash = ExpectedHash::parse("MD5:ABC").unwrap();
+        assert_eq!(hash, ExpectedHash::new(HashAlgorithm::Md5, "abc"));
+        let hash = ExpectedHash::parse("abc").unwrap();
+        assert_eq!(hash.algorithm, HashAlgorithm::Sha256);
+        assert!(ExpectedHash::parse("crc32:abc").is_err());
+    }
+
+    #[test]
+    fn test_verify_files() {
+        let dir = std::env::temp_dir().join(format!("hp_hash_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("hello.txt");
+        std::fs::write(&path, b"hello world").unwrap();
+        let jobs = vec![
+            HashJob {
+                path: path.clone(),
+                expected: ExpectedHash::parse("md5:5eb63bbbe01eeed093cb22bb8f5acdc3").unwrap(),
+            },
+            HashJob {
+                path: path.clone(),
+                expected: ExpectedHash::parse("0000").unwrap(),
+            },
+        ];
+        let results = verify_files(&jobs, |_, processed, total| assert!(processed <= total));
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
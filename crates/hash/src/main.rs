@@ -1,17 +1,8 @@
-use sha2::Digest;
-use sha2::Sha256;
-use std::io::{ Read};
+use hash::{hash_file, HashAlgorithm};
 use std::process::Command;
 
 fn calculate_sha256(input: &str) -> String {
-    let mut hasher = Sha256::new();
-    let mut file = std::fs::File::open(input).unwrap();
-    let mut buffer = vec![];
-    file.read_to_end(&mut buffer).unwrap();
-    hasher.update(&buffer);
-
-    let sha256 = hasher.finalize();
-    format!("{:x}", sha256)
+    hash_file(input, HashAlgorithm::Sha256).unwrap()
 }
 
 fn calculate_sha256_by_pwsh(input: &str) -> String {
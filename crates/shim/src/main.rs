@@ -2,7 +2,7 @@ use std::ffi::{ OsString};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::os::windows::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use windows::core::{s, BOOL, PCWSTR, PWSTR};
 use windows::Win32::Foundation::*;
@@ -28,6 +28,7 @@ type WStringOpt = Option<String>;
 struct ShimInfo {
     pub path: WStringOpt,
     pub args: WStringOpt,
+    pub app: WStringOpt,
 }
 
 fn get_directory(exe_path: &str) -> String {
@@ -53,6 +54,7 @@ fn get_shim_info() -> color_eyre::Result<ShimInfo> {
         return Ok(ShimInfo {
             path: None,
             args: None,
+            app: None,
         });
     }
 
@@ -66,6 +68,7 @@ fn get_shim_info() -> color_eyre::Result<ShimInfo> {
 
     let mut path: WStringOpt = None;
     let mut args: WStringOpt = None;
+    let mut app: WStringOpt = None;
 
     if let Some(reader) = reader {
         for line in reader.lines().flatten() {
@@ -73,6 +76,8 @@ fn get_shim_info() -> color_eyre::Result<ShimInfo> {
                 path = Some(line[7..].trim().to_string());
             } else if line.starts_with("args = ") {
                 args = Some(line[7..].trim().to_string());
+            } else if line.starts_with("app = ") {
+                app = Some(remove_extra_quotes(line[6..].trim()));
             }
         }
     }
@@ -80,7 +85,70 @@ fn get_shim_info() -> color_eyre::Result<ShimInfo> {
     let cur_dir = get_directory(&exe_str);
     normalize_args(&mut args, &cur_dir);
 
-    Ok(ShimInfo { path, args })
+    Ok(ShimInfo { path, args, app })
+}
+
+const HP_TOOLS_FILE: &str = ".hp-tools";
+
+/// `.hp-tools` 每行为 `<app> <version>`, `#` 开头为注释
+fn find_pinned_version(content: &str, app: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let name = parts.next()?;
+        let version = parts.next()?;
+        (!name.starts_with('#') && name.eq_ignore_ascii_case(app)).then(|| version.to_string())
+    })
+}
+
+/// 版本号会拼接为 `apps\<app>\<version>`, 与 `unsafe_relative_path_reason` 一样拒绝跳出 app 目录的值
+fn unsafe_version_reason(version: &str) -> Option<&'static str> {
+    if version.contains(['/', '\\']) {
+        return Some("path separator is not allowed");
+    }
+    if version.contains(':') {
+        return Some("drive prefix or stream name is not allowed");
+    }
+    // Windows 会去掉末尾的点和空格, `.. ` 和 `...` 同样指向上级目录
+    if version.starts_with("..") && version.trim_end_matches(['.', ' ']).is_empty() {
+        return Some("parent directory is not allowed");
+    }
+    None
+}
+
+/// 从当前目录向上查找第一个固定了该 app 版本的 `.hp-tools`
+fn find_local_version(app: &str) -> Option<String> {
+    let cwd = std::env::current_dir().ok()?;
+    cwd.ancestors().find_map(|dir| {
+        let content = std::fs::read_to_string(dir.join(HP_TOOLS_FILE)).ok()?;
+        find_pinned_version(&content, app)
+    })
+}
+
+/// 把 `apps\<app>\current\` 替换为固定的版本目录, 该版本未安装时仍使用 current
+fn resolve_local_version(path: &str, app: &str) -> String {
+    let Some(version) = find_local_version(app) else {
+        return path.to_string();
+    };
+    if let Some(reason) = unsafe_version_reason(&version) {
+        eprintln!("Warning: ignoring {app} {version} from {HP_TOOLS_FILE}: {reason}");
+        return path.to_string();
+    }
+    let unquoted = remove_extra_quotes(path);
+    let current_dir = format!("\\{}\\current\\", app.to_ascii_lowercase());
+    let Some(start) = unquoted.to_ascii_lowercase().find(&current_dir) else {
+        return path.to_string();
+    };
+    let pinned = format!(
+        "{}\\{app}\\{version}\\{}",
+        &unquoted[..start],
+        &unquoted[start + current_dir.len()..]
+    );
+    if Path::new(&pinned).exists() {
+        pinned
+    } else {
+        eprintln!("Warning: {app} {version} from {HP_TOOLS_FILE} is not installed, run `hp local {app}@{version}`");
+        path.to_string()
+    }
 }
 
 fn is_elevation_required(error: &std::io::Error) -> bool {
@@ -237,6 +305,10 @@ fn main() -> color_eyre::Result<()> {
     }
 
     let path = shim_info.path.clone().unwrap();
+    let path = match shim_info.app.as_deref() {
+        Some(app) => resolve_local_version(&path, app),
+        None => path,
+    };
     let args = shim_info.args.clone().unwrap_or_default();
 
     // 解析当前命令行参数并追加
@@ -258,6 +330,7 @@ fn main() -> color_eyre::Result<()> {
     if let Some(mut child) = make_process(&ShimInfo {
         path: Some(path),
         args: Some(full_args),
+        app: None,
     }) {
         if let Some(job) = job {
             unsafe {
@@ -290,4 +363,29 @@ fn test_create_process(){
             eprintln!("Failed to start process: {}", e);
         }
       }  
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_find_pinned_version() {
+    let content = "# project tools\nnodejs 20.11.1\n  python   3.11.9  \n\n";
+    assert_eq!(
+        find_pinned_version(content, "NodeJS"),
+        Some("20.11.1".to_string())
+    );
+    assert_eq!(
+        find_pinned_version(content, "python"),
+        Some("3.11.9".to_string())
+    );
+    assert_eq!(find_pinned_version(content, "go"), None);
+    assert_eq!(find_pinned_version("# nodejs 18", "nodejs"), None);
+}
+
+#[test]
+fn test_unsafe_version_reason() {
+    for version in ["20.11.1", "nightly-20240101", "..1"] {
+        assert_eq!(unsafe_version_reason(version), None, "{version}");
+    }
+    for version in ["..", "...", "..\\..\\Windows", "20/../..", "20\\bin", "C:x"] {
+        assert!(unsafe_version_reason(version).is_some(), "{version}");
+    }
+}
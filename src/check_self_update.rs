@@ -10,8 +10,10 @@ use command_util_lib::init_env::{
 };
 use command_util_lib::install::UpdateOptions;
 use command_util_lib::list::VersionJSON;
+use command_util_lib::offline::is_offline_mode;
 use command_util_lib::utils::git::pull_special_local_repo;
 use command_util_lib::utils::utility::is_valid_url;
+use command_util_lib::utils::version::Version;
 use crossterm::style::Stylize;
 use reqwest::{header, Client};
 use serde::{Deserialize, Serialize};
@@ -49,6 +51,10 @@ pub fn get_app_old_version(app_name: &str, options: &[UpdateOptions]) -> anyhow:
 }
 
 pub async fn auto_check_hp_update(old_version: Option<&str>) -> anyhow::Result<bool> {
+    if is_offline_mode() {
+        log::debug!("offline mode, skip checking hp update");
+        return Ok(false);
+    }
     let version = if old_version.is_none() {
         String::new()
     } else {
@@ -69,7 +75,7 @@ pub async fn auto_check_hp_update(old_version: Option<&str>) -> anyhow::Result<b
         latest_version,
         version
     );
-    if version < latest_version || hash_changed() {
+    if Version::new(&version) < Version::new(&latest_version) || hash_changed() {
         println!("{}", format!("发现hp版本变更 {latest_version}, `hp u hp` or `hp u -f -k hp`  \n请访问https://github.com/Super1Windcloud/hp/releases").dark_cyan().bold());
         let hp_repo = get_hp_bucket_repo_path("hp")?;
         if hp_repo.is_none() {
@@ -84,7 +90,7 @@ pub async fn auto_check_hp_update(old_version: Option<&str>) -> anyhow::Result<b
 }
 
 use command_util_lib::manifest::manifest::get_latest_manifest_from_local_bucket;
-use sha2::{Digest, Sha256};
+use hash::{hash_file, HashAlgorithm};
 
 pub fn hash_changed() -> bool {
     let hp_manifest = get_latest_manifest_from_local_bucket("hp").unwrap();
@@ -101,11 +107,10 @@ pub fn hash_changed() -> bool {
     let manifest: serde_json::Value = serde_json::from_str(&content.unwrap_or_default()).unwrap();
     let hash = manifest.get("hash").unwrap().as_str().unwrap();
 
-    let mut hasher = Sha256::new();
-    let buffer = std::fs::read(&exe_path).unwrap();
-    hasher.update(&buffer);
-    let hash_result = hasher.finalize();
-    let old_hash = hex::encode(hash_result);
+    let old_hash = match hash_file(&exe_path, HashAlgorithm::Sha256) {
+        Ok(hash) => hash,
+        Err(_) => return true,
+    };
     if hash.to_lowercase() != old_hash.to_lowercase() {
         true
     } else {
@@ -1,21 +1,28 @@
 ﻿use crate::check_self_update::auto_check_hp_update;
 use crate::command_args::alias::AliasArgs;
+use crate::command_args::autoremove::AutoremoveArgs;
+use crate::command_args::bundle::BundleArgs;
 use crate::command_args::cat::CatArgs;
 use crate::command_args::checkup::CheckupArgs;
+use crate::command_args::checkver::CheckverArgs;
 use crate::command_args::cleanup::CleanupArgs;
 use crate::command_args::config::ConfigArgs;
+use crate::command_args::depends::DependsArgs;
 use crate::command_args::export::ExportArgs;
 use crate::command_args::home::HomeArgs;
 use crate::command_args::import::ImportArgs;
 use crate::command_args::info::InfoArgs;
 use crate::command_args::install::InstallArgs;
 use crate::command_args::list::ListArgs;
+use crate::command_args::local::LocalArgs;
+use crate::command_args::manifest::ManifestArgs;
 use crate::command_args::merge_bucket::MergeArgs;
 use crate::command_args::prefix::PrefixArgs;
 use crate::command_args::reset::ResetArgs;
 use crate::command_args::search::SearchArgs;
 use crate::command_args::shim::ShimArgs;
 use crate::command_args::status::StatusArgs;
+use crate::command_args::sync::SyncArgs;
 use crate::command_args::uninstall::UninstallArgs;
 use crate::command_args::update::UpdateArgs;
 use crate::command_args::which::WhichArgs;
@@ -39,12 +46,16 @@ use std::path::Path;
 )]
 pub(crate) enum Commands {
     Alias(AliasArgs),
+    Autoremove(AutoremoveArgs),
     Bucket(BucketArgs),
+    Bundle(BundleArgs),
     Cat(CatArgs),
     Cache(CacheArgs),
     Checkup(CheckupArgs),
+    Checkver(CheckverArgs),
     Cleanup(CleanupArgs),
     Config(ConfigArgs),
+    Depends(DependsArgs),
     Export(ExportArgs),
     Home(HomeArgs),
     Hold(HoldArgs),
@@ -52,12 +63,15 @@ pub(crate) enum Commands {
     Info(InfoArgs),
     Install(InstallArgs),
     List(ListArgs),
+    Local(LocalArgs),
+    Manifest(ManifestArgs),
     Prefix(PrefixArgs),
     Reset(ResetArgs),
     #[clap(alias = "s")]
     Search(SearchArgs),
     Shim(ShimArgs),
     Status(StatusArgs),
+    Sync(SyncArgs),
     #[clap(alias = "un")]
     Uninstall(UninstallArgs),
     Update(UpdateArgs),
@@ -66,6 +80,28 @@ pub(crate) enum Commands {
     Credits(CreditsArgs),
 }
 
+impl Commands {
+    /// 会修改本机状态但不生成执行计划的命令, 在全局 `--dry-run` 下应直接拒绝
+    pub(crate) fn dry_run_unsupported(&self) -> Option<&'static str> {
+        match self {
+            Commands::Alias(_) => Some("alias"),
+            Commands::Bucket(_) => Some("bucket"),
+            Commands::Bundle(_) => Some("bundle"),
+            Commands::Cache(_) => Some("cache"),
+            Commands::Config(_) => Some("config"),
+            Commands::Export(_) => Some("export"),
+            Commands::Hold(_) => Some("hold"),
+            Commands::Import(_) => Some("import"),
+            Commands::Local(_) => Some("local"),
+            Commands::Manifest(_) => Some("manifest"),
+            Commands::Merge(_) => Some("merge"),
+            Commands::Reset(_) => Some("reset"),
+            Commands::Shim(_) => Some("shim"),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Args, Debug)]
 #[clap(author, version, about="💖\t\t显示Credit信息", long_about = None)]
 #[command(arg_required_else_help = false, subcommand_negates_reqs = true)]
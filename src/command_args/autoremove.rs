@@ -0,0 +1,13 @@
+use crate::command_args::dry_run::DryRunFormat;
+use clap::Args;
+
+#[derive(Args, Debug)]
+#[command(about = "🧹\t\t卸载仅作为依赖安装且不再被需要的App")]
+#[command(override_usage = "hp  autoremove [--dry-run[=json]]")]
+pub struct AutoremoveArgs {
+    #[arg(from_global)]
+    pub dry_run: Option<DryRunFormat>,
+
+    #[arg(from_global)]
+    pub global: bool,
+}
@@ -0,0 +1,55 @@
+﻿use clap::{Args, Subcommand};
+use command_util_lib::utils::utility::clap_args_to_lowercase;
+
+#[derive(Clone, Subcommand, Debug)]
+pub enum BundleSubcommand {
+    Create(BundleCreateArgs),
+    Install(BundleInstallArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+///下载并校验App及其依赖的安装包, 打包为离线安装包
+#[command(arg_required_else_help = true)]
+pub struct BundleCreateArgs {
+    #[arg(help = "输出的离线安装包路径, 例如 out.hpb")]
+    pub output: String,
+    #[arg(help = "要打包的APP名称,支持 bucket/app, app@version 和本地清单文件", required = true, num_args = 1..,
+    value_parser = clap_args_to_lowercase)]
+    pub app_names: Vec<String>,
+    #[arg(
+        short = 'a',
+        long,
+        help = "指定打包架构, 安装时沿用该架构",
+        default_value = "64bit",
+        value_name = "<32bit|64bit|arm64>",
+        value_parser = clap_args_to_lowercase
+    )]
+    pub arch: Option<String>,
+    #[arg(from_global)]
+    pub global: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+///从离线安装包安装, 不访问bucket和网络
+#[command(arg_required_else_help = true)]
+pub struct BundleInstallArgs {
+    #[arg(help = "离线安装包路径")]
+    pub bundle: String,
+    #[arg(short, long, help = "强制覆盖安装,先删除已安装目录")]
+    pub force_install_override: bool,
+    #[arg(from_global)]
+    pub global: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = true, subcommand_negates_reqs = true)]
+#[command(about = "📦\t\t创建或安装离线安装包, 适用于无网络的机器")]
+#[command(override_usage = " hp  bundle create <out.hpb> <app(s)> | hp bundle install <out.hpb>")]
+#[command(after_help = r#"
+e.g. 打包App及其依赖 :   hp bundle create out.hpb git extras/vscode
+在离线机器上安装 :   hp bundle install out.hpb
+     "#)]
+pub struct BundleArgs {
+    #[clap(subcommand)]
+    pub(crate) command: Option<BundleSubcommand>,
+}
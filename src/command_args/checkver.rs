@@ -0,0 +1,17 @@
+use clap::Args;
+
+#[derive(Args, Debug)]
+#[clap(author, version, about="🔭\t\t根据manifest的checkver检查上游最新版本", long_about = None)]
+#[command(arg_required_else_help = true, subcommand_negates_reqs = true)]
+#[command(override_usage = "hp  checkver [app_name|bucket/app|bucket/*]")]
+#[command(after_help = "hp checkver gh\nhp checkver main/gh\nhp checkver main/*")]
+pub struct CheckverArgs {
+    #[arg(required = true, num_args = 1.., help = "App名称, bucket/app 或 bucket/*, 支持多参数")]
+    pub targets: Vec<String>,
+
+    #[arg(short = 'o', long, help = "只显示有新版本的App")]
+    pub outdated_only: bool,
+
+    #[arg(from_global)]
+    pub global: bool,
+}
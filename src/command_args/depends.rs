@@ -0,0 +1,24 @@
+use clap::Args;
+use command_util_lib::utils::utility::clap_args_to_lowercase;
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = true, subcommand_negates_reqs = true)]
+#[command(about = "🌳\t\t显示App的依赖树或依赖于它的已安装App")]
+#[command(override_usage = "hp  depends [app_name|bucket/app]")]
+#[command(
+    after_help = "hp depends gh\nhp depends extras/vcredist2022 --reverse\nhp depends gh --json"
+)]
+pub struct DependsArgs {
+    #[arg(required = true, help = "App名称, 可以指定bucket, 例如 extras/vcredist2022",
+    value_parser = clap_args_to_lowercase)]
+    pub app_name: String,
+
+    #[arg(short = 'r', long, help = "列出依赖于该App的已安装App")]
+    pub reverse: bool,
+
+    #[arg(short = 'j', long, help = "以JSON格式输出")]
+    pub json: bool,
+
+    #[arg(from_global)]
+    pub global: bool,
+}
@@ -0,0 +1,62 @@
+use clap::ValueEnum;
+use command_util_lib::plan::{ExecutionPlan, PlanAction};
+use crossterm::style::Stylize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DryRunFormat {
+    Human,
+    Json,
+}
+
+/// 输出执行计划, json 格式只输出计划本身以便脚本解析
+pub fn print_execution_plan(plan: &ExecutionPlan, format: DryRunFormat) -> anyhow::Result<()> {
+    if format == DryRunFormat::Json {
+        println!("{}", serde_json::to_string_pretty(plan)?);
+        return Ok(());
+    }
+    if plan.apps.is_empty() {
+        println!("{}", "Nothing to do".dark_green().bold());
+        return Ok(());
+    }
+    println!(
+        "{}",
+        "Dry run, no changes will be made:".dark_yellow().bold()
+    );
+    for app in plan.apps.iter() {
+        let action = match app.action {
+            PlanAction::Skip => app.action.to_string().dark_grey().bold(),
+            PlanAction::Unresolved => app.action.to_string().dark_red().bold(),
+            PlanAction::Uninstall | PlanAction::Cleanup => {
+                app.action.to_string().dark_magenta().bold()
+            }
+            PlanAction::Install | PlanAction::Update => app.action.to_string().dark_green().bold(),
+        };
+        let version = app
+            .version
+            .as_ref()
+            .map(|version| format!(" ({version})"))
+            .unwrap_or_default();
+        let reason = app
+            .reason
+            .as_ref()
+            .map(|reason| format!(" - {reason}"))
+            .unwrap_or_default();
+        println!(
+            "\n{action} {}{version}{}",
+            app.name.clone().dark_cyan().bold(),
+            reason.dark_grey()
+        );
+        for step in app.steps.iter() {
+            println!("    {step}");
+        }
+    }
+    let download_size = plan.download_size();
+    if download_size > 0 {
+        println!(
+            "\n{} {:.1} MB",
+            "Total download size:".dark_yellow().bold(),
+            download_size as f64 / 1024.0 / 1024.0
+        );
+    }
+    Ok(())
+}
@@ -10,4 +10,6 @@ pub struct ExportArgs  {
   pub(crate) file_name : Option<String>,
   #[clap(short, long, help = "一并导出Scoop配置文件")]
   pub(crate) config : bool ,
+  #[clap(short, long, help = "导出锁文件, 记录精确版本、架构、下载地址、哈希和bucket提交, 配合 import --locked 使用")]
+  pub(crate) lock : bool ,
 }
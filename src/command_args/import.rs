@@ -7,6 +7,8 @@
 pub struct ImportArgs {
   #[arg(help = "导入的json配置文件路径")]
   pub(crate) path  : Option<String>,
+  #[arg(short, long, help = "按 export --lock 导出的锁文件还原精确版本")]
+  pub(crate) locked : bool,
 }
 
 
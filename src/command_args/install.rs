@@ -1,5 +1,6 @@
 ﻿use clap::ArgAction;
 use clap::Args;
+use crate::command_args::dry_run::DryRunFormat;
 use command_util_lib::utils::utility::clap_args_to_lowercase;
 
 #[derive(Args, Debug)]
@@ -12,11 +13,12 @@ e.g. 安装应用程序的通常方法（使用您的本地buckets）： hp inst
 安装应用程序的不同版本,如果存在多版本清单 :  hp install gh@2.7.0
 从计算机上的指定路径清单中安装应用程序 :   hp install \path\to\app.json
 从远程URL安装应用程序 :   hp install https://example.com/app.exe ( 支持.cmd,.bat,.ps1,.exe) [exe如果是安装包无效]
+一次安装多个应用程序,统一解析依赖并并发下载 :   hp install git extras/vscode gh@2.7.0
      "#)]
 pub struct InstallArgs {
-    #[arg(help = "安装APP的名称,精准匹配,仅单个安装", required = false,
+    #[arg(help = "安装APP的名称,精准匹配,支持同时安装多个", required = false, num_args = 1..,
     value_parser = clap_args_to_lowercase)]
-    pub app_name: Option<String>,
+    pub app_names: Vec<String>,
 
     #[arg(short, long, help = "跳过下载文件的哈希校验", required = false, action = ArgAction::SetTrue,help_heading = "Install Options"  )]
     pub skip_download_hash_check: bool,
@@ -66,4 +68,7 @@ pub struct InstallArgs {
 
     #[arg(from_global)]
     pub global: bool,
+
+    #[arg(from_global)]
+    pub dry_run: Option<DryRunFormat>,
 }
@@ -0,0 +1,19 @@
+use clap::Args;
+use command_util_lib::utils::utility::clap_args_to_lowercase;
+
+#[derive(Args, Debug)]
+#[command(about = "📌\t\t在 .hp-tools 中固定当前项目使用的App版本")]
+#[command(override_usage = "hp  local [app@version]")]
+#[command(after_help = r#"
+e.g. 固定 nodejs 版本, 未安装时自动安装 :   hp local nodejs@20.11.1
+查看当前目录生效的固定版本 :   hp local
+只对 exe shim 生效, cmd/ps1 等脚本 shim 始终运行 current, 旧版本创建的 shim 需先执行 hp reset <app>
+     "#)]
+pub struct LocalArgs {
+    #[arg(help = "要固定的App和版本, 格式为 app@version, 省略时列出当前生效的固定版本",
+    value_parser = clap_args_to_lowercase)]
+    pub app: Option<String>,
+
+    #[arg(from_global)]
+    pub global: bool,
+}
@@ -0,0 +1,60 @@
+use clap::{Args, Subcommand};
+
+#[derive(Clone, Subcommand, Debug)]
+pub enum ManifestSubcommand {
+    Lint(LintArgs),
+    Fmt(FmtArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+///按照Scoop manifest schema检查清单文件
+#[command(arg_required_else_help = true, subcommand_negates_reqs = true)]
+#[command(override_usage = "hp  manifest lint [app_name|bucket/app|bucket/*|path]")]
+#[command(
+    after_help = "hp manifest lint gh\nhp manifest lint main/*\nhp manifest lint ./bucket --json"
+)]
+pub struct LintArgs {
+    #[arg(required = true, num_args = 1.., help = "App名称, bucket/app, bucket/*, 清单文件或bucket目录, 支持多参数")]
+    pub targets: Vec<String>,
+
+    #[arg(short = 'j', long, help = "以JSON格式输出检查结果")]
+    pub json: bool,
+
+    #[arg(short = 'e', long, help = "只报告错误, 忽略警告")]
+    pub errors_only: bool,
+
+    #[arg(from_global)]
+    pub global: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+///按照Scoop约定的字段顺序格式化清单文件
+#[command(arg_required_else_help = true, subcommand_negates_reqs = true)]
+#[command(override_usage = "hp  manifest fmt [app_name|bucket/app|bucket/*|path]")]
+#[command(after_help = "hp manifest fmt ./bucket/gh.json\nhp manifest fmt ./bucket --check")]
+pub struct FmtArgs {
+    #[arg(required = true, num_args = 1.., help = "App名称, bucket/app, bucket/*, 清单文件或bucket目录, 支持多参数")]
+    pub targets: Vec<String>,
+
+    #[arg(
+        short = 'c',
+        long,
+        help = "只检查是否已格式化, 存在未格式化的清单时以非零状态退出"
+    )]
+    pub check: bool,
+
+    #[arg(long, help = "使用LF换行符, 默认使用CRLF")]
+    pub lf: bool,
+
+    #[arg(from_global)]
+    pub global: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = true, subcommand_negates_reqs = true)]
+#[command(about = "📜\t\t检查或格式化manifest清单文件")]
+#[command(override_usage = "hp  manifest lint|fmt [targets]")]
+pub struct ManifestArgs {
+    #[clap(subcommand)]
+    pub(crate) command: ManifestSubcommand,
+}
@@ -1,23 +1,30 @@
 ﻿pub mod   bucket_args;
+pub mod bundle;
 pub mod  merge_bucket;
 pub  mod  cat;
+pub mod checkver ;
 pub  mod  cache ;
 pub  mod config ;
 pub  mod  cleanup ;
 pub mod  checkup ;
+pub mod depends ;
 pub mod export ;
 pub mod  import ;
 pub mod home ;
 pub mod info ;
 pub mod  install ;
 pub mod list;
+pub mod local;
+pub mod manifest ;
 pub mod prefix ;
 pub mod  reset ;
 pub mod  search ;
 pub mod   shim ;
 pub mod  status ;
+pub mod sync;
 pub mod  uninstall ;
 pub mod  update ;
 pub mod   which;
 pub mod alias;
-
+pub mod autoremove ;
+pub mod dry_run ;
@@ -0,0 +1,24 @@
+use crate::command_args::dry_run::DryRunFormat;
+use clap::Args;
+
+#[derive(Args, Debug)]
+#[command(about = "🔄\t\t按 hpfile 声明的 bucket、App 和配置收敛本机环境")]
+#[command(override_usage = "hp  sync [hpfile] [--prune] [--dry-run[=json]]")]
+#[command(after_help = r#"
+e.g. 按当前目录的 hpfile 收敛 :   hp sync
+只检查是否有偏差, 有偏差时返回非零 :   hp sync --dry-run
+同时卸载 hpfile 中未列出的App :   hp sync ./hpfile.toml --prune
+     "#)]
+pub struct SyncArgs {
+    #[arg(help = "hpfile 路径, 默认依次查找当前目录的 hpfile, hpfile.toml, hpfile.json")]
+    pub file: Option<String>,
+
+    #[arg(
+        long,
+        help = "卸载 hpfile 中未列出的App(作为依赖安装或仍被其他App依赖的除外), 并移除未列出且不再被使用的bucket"
+    )]
+    pub prune: bool,
+
+    #[arg(from_global)]
+    pub dry_run: Option<DryRunFormat>,
+}
@@ -1,5 +1,6 @@
 
 use  clap::Args;
+use crate::command_args::dry_run::DryRunFormat;
 use command_util_lib::utils::utility::clap_args_to_lowercase;
 
 #[derive(Args, Debug)]
@@ -13,7 +14,11 @@ pub struct  UninstallArgs           {
   pub(crate) purge : bool ,
   #[arg(from_global)]
   pub  global :bool ,
-  #[arg(short ,long , help = "强制删除,自动杀掉运行中进程" )]
-  pub  force : bool, 
+  #[arg(short ,long , help = "强制删除,自动杀掉运行中进程, 忽略依赖于该APP的已安装APP" )]
+  pub  force : bool,
+  #[arg(short ,long , help = "同时卸载依赖于该APP的已安装APP" , conflicts_with = "force")]
+  pub  cascade : bool, 
+  #[arg(from_global)]
+  pub dry_run : Option<DryRunFormat>,
   
 }
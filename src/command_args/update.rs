@@ -1,4 +1,5 @@
 use clap::Args;
+use crate::command_args::dry_run::DryRunFormat;
 use command_util_lib::utils::utility::clap_args_to_lowercase;
 
 #[derive(Args, Debug)]
@@ -56,4 +57,7 @@ pub struct UpdateArgs {
 
     #[arg(from_global)]
     pub global: bool,
+
+    #[arg(from_global)]
+    pub dry_run: Option<DryRunFormat>,
 }
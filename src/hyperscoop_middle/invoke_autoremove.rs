@@ -0,0 +1,42 @@
+use crate::command_args::autoremove::AutoremoveArgs;
+use crate::command_args::dry_run::print_execution_plan;
+use command_util_lib::depends::get_orphaned_dependencies;
+use command_util_lib::plan::{plan_uninstall_app, AppPlan, ExecutionPlan, PlanAction};
+use command_util_lib::uninstall::uninstall_app;
+use crossterm::style::Stylize;
+
+pub fn execute_autoremove_command(args: AutoremoveArgs) -> anyhow::Result<()> {
+    let orphans = get_orphaned_dependencies(args.global)?;
+    if orphans.is_empty() {
+        println!("{}", "No orphaned dependencies found".dark_green().bold());
+        return Ok(());
+    }
+    if let Some(format) = args.dry_run {
+        let apps = orphans
+            .iter()
+            .map(|name| {
+                plan_uninstall_app(name, args.global, false).unwrap_or_else(|e| {
+                    AppPlan::new(name, PlanAction::Unresolved, args.global)
+                        .with_reason(format!("{e:#}"))
+                })
+            })
+            .collect();
+        return print_execution_plan(&ExecutionPlan { apps }, format);
+    }
+    for name in orphans.iter() {
+        match uninstall_app(name, args.global) {
+            Ok(_) => println!(
+                "'{}' {}",
+                name.clone().dark_cyan().bold(),
+                "was uninstalled successfully!".dark_green().bold()
+            ),
+            Err(e) => eprintln!(
+                "{}",
+                format!("Failed to uninstall '{name}': {e}")
+                    .dark_red()
+                    .bold()
+            ),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use crate::command_args::bundle::{BundleArgs, BundleSubcommand};
+use crate::hyperscoop_middle::invoke_install::print_install_summary;
+use command_util_lib::bundle::{create_bundle, install_bundle};
+use command_util_lib::install::InstallOptions;
+use command_util_lib::utils::system::{is_admin, request_admin};
+use crossterm::style::Stylize;
+use std::env;
+use std::path::Path;
+
+pub fn execute_bundle_command(args: BundleArgs) -> anyhow::Result<()> {
+    let Some(command) = args.command else {
+        return Ok(());
+    };
+    let global = match &command {
+        BundleSubcommand::Create(args) => args.global,
+        BundleSubcommand::Install(args) => args.global,
+    };
+    if global && !is_admin()? {
+        let args = env::args().skip(1).collect::<Vec<String>>();
+        request_admin(args.join(" ").as_str())?;
+        return Ok(());
+    }
+    match command {
+        BundleSubcommand::Create(args) => {
+            let mut options = global_options(global);
+            let arch = args.arch.unwrap_or_default();
+            if !arch.is_empty() {
+                options.push(InstallOptions::ArchOptions(arch.as_str()));
+            }
+            let output = Path::new(&args.output);
+            let index = create_bundle(output, &args.app_names, &options)?;
+            println!(
+                "{}",
+                format!(
+                    "Bundle '{}' created with {} app(s) [{}]",
+                    output.display(),
+                    index.apps.len(),
+                    index.architecture
+                )
+                .dark_green()
+                .bold()
+            );
+            Ok(())
+        }
+        BundleSubcommand::Install(args) => {
+            let mut options = global_options(global);
+            if args.force_install_override {
+                options.push(InstallOptions::ForceInstallOverride);
+            }
+            let results = install_bundle(Path::new(&args.bundle), &options)?;
+            print_install_summary(&results)
+        }
+    }
+}
+
+fn global_options<'a>(global: bool) -> Vec<InstallOptions<'a>> {
+    if global {
+        vec![InstallOptions::Global]
+    } else {
+        vec![]
+    }
+}
@@ -1,20 +1,25 @@
 use crate::command_args::cleanup::CleanupArgs;
+use crate::command_args::dry_run::print_execution_plan;
 use anyhow::bail;
 use anyhow::{anyhow, Context};
 use command_util_lib::init_env::{
     get_app_dir, get_app_dir_global, get_app_version_dir, get_app_version_dir_global,
     get_apps_path, get_apps_path_global,
 };
+use command_util_lib::plan::{AppPlan, ExecutionPlan, PlanAction, PlanStep};
 use command_util_lib::utils::system::kill_processes_using_app;
 use command_util_lib::utils::utility::compare_versions;
 use crossterm::style::Stylize;
 use rayon::prelude::*;
 use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
 
 pub fn execute_cleanup_command(args: CleanupArgs) -> Result<(), anyhow::Error> {
+    if let Some(format) = args.dry_run {
+        let plan = plan_cleanup(args.app_names.as_deref(), args.all, args.global)?;
+        return print_execution_plan(&plan, format);
+    }
     if let Some(name) = args.app_names {
         if args.all {
             clean_all_old_versions(args.global)?
@@ -44,48 +49,19 @@ fn clean_specific_old_version(app_name: Vec<String>, is_global: bool) -> anyhow:
     let result = app_dirs.iter().try_for_each(|dir| {
         let dir = Path::new(dir);
         let app_name = dir.file_stem().unwrap().to_str().unwrap();
-        let child_dirs = dir
-            .read_dir()
-            .context(format!("Failed to read directory: {}", dir.display()))?
-            .filter_map(|dir| {
-                let dir = dir.unwrap();
-                let binding = dir.file_name();
-                let name = binding.to_str().unwrap();
-                if name == "current" {
-                    return None;
-                } else {
-                    Some(dir.path())
-                }
-            })
-            .collect::<Vec<_>>();
-        let highest_version_dir = child_dirs
-            .par_iter()
-            .max_by(|dir1, dir2| {
-                let file_name = dir1.file_name().unwrap().to_str().unwrap();
-                let file_name2 = dir2.file_name().unwrap().to_str().unwrap();
-                compare_versions(file_name.to_string(), file_name2.to_string())
-            })
-            .ok_or(anyhow!("No version directory found at line 64"))?;
-
-        let retain_dir = highest_version_dir;
-        let flag = Arc::new(Mutex::new(false));
-        let result = child_dirs.par_iter().try_for_each(|dir| {
-            if dir != retain_dir {
-                log::info!("Removing old version: {}", dir.display());
-                let result = std::fs::remove_dir_all(dir).context("Failed to remove old version");
-                *flag.lock().unwrap() = true;
-                if result.is_err() {
-                    kill_processes_using_app(app_name);
-                    std::fs::remove_dir_all(dir)
-                        .context("Failed to remove old version at line 80")?;
-                }
-            }
-            Ok(())
-        });
-        if !*flag.lock().unwrap() {
+        let old_version_dirs = find_old_version_dirs(dir)?;
+        if old_version_dirs.is_empty() {
             println!("No old version for '{}'", dir.display());
         }
-        result
+        old_version_dirs.par_iter().try_for_each(|dir| {
+            log::info!("Removing old version: {}", dir.display());
+            let result = std::fs::remove_dir_all(dir).context("Failed to remove old version");
+            if result.is_err() {
+                kill_processes_using_app(app_name);
+                std::fs::remove_dir_all(dir).context("Failed to remove old version at line 80")?;
+            }
+            Ok(())
+        })
     });
     if result.is_err() {
         let err: anyhow::Error = result.unwrap_err();
@@ -94,6 +70,85 @@ fn clean_specific_old_version(app_name: Vec<String>, is_global: bool) -> anyhow:
     Ok(())
 }
 
+/// 除 current 与最高版本外的全部版本目录
+fn find_old_version_dirs(app_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let child_dirs = app_dir
+        .read_dir()
+        .context(format!("Failed to read directory: {}", app_dir.display()))?
+        .filter_map(|dir| dir.ok())
+        .filter(|dir| dir.path().is_dir() && dir.file_name() != "current")
+        .map(|dir| dir.path())
+        .collect::<Vec<_>>();
+    let highest_version_dir = child_dirs
+        .iter()
+        .max_by(|dir1, dir2| {
+            let file_name = dir1.file_name().unwrap().to_string_lossy().to_string();
+            let file_name2 = dir2.file_name().unwrap().to_string_lossy().to_string();
+            compare_versions(file_name, file_name2)
+        })
+        .ok_or(anyhow!("No version directory found at line 64"))?;
+    Ok(child_dirs
+        .iter()
+        .filter(|dir| *dir != highest_version_dir)
+        .cloned()
+        .collect())
+}
+
+fn plan_cleanup(
+    app_names: Option<&[String]>,
+    all: bool,
+    is_global: bool,
+) -> anyhow::Result<ExecutionPlan> {
+    let app_dirs = if all {
+        let apps_dir = if is_global {
+            get_apps_path_global()
+        } else {
+            get_apps_path()
+        };
+        std::fs::read_dir(apps_dir)
+            .context("Failed to read apps  root directory at line 96")?
+            .filter_map(|dir| dir.ok())
+            .map(|dir| dir.path())
+            .filter(|dir| dir.is_dir())
+            .collect::<Vec<_>>()
+    } else {
+        app_names
+            .unwrap_or_default()
+            .iter()
+            .map(|name| {
+                PathBuf::from(if is_global {
+                    get_app_dir_global(name)
+                } else {
+                    get_app_dir(name)
+                })
+            })
+            .collect()
+    };
+    let mut plan = ExecutionPlan::default();
+    for app_dir in app_dirs {
+        let app_name = app_dir.file_name().unwrap().to_string_lossy().to_string();
+        let app_plan = match find_old_version_dirs(&app_dir) {
+            Ok(old_version_dirs) if old_version_dirs.is_empty() => {
+                AppPlan::new(&app_name, PlanAction::Skip, is_global).with_reason("no old version")
+            }
+            Ok(old_version_dirs) => {
+                let mut app_plan = AppPlan::new(&app_name, PlanAction::Cleanup, is_global);
+                app_plan.steps = old_version_dirs
+                    .iter()
+                    .map(|dir| PlanStep::RemoveDir {
+                        path: dir.to_string_lossy().to_string(),
+                    })
+                    .collect();
+                app_plan
+            }
+            Err(e) => AppPlan::new(&app_name, PlanAction::Unresolved, is_global)
+                .with_reason(format!("{e:#}")),
+        };
+        plan.apps.push(app_plan);
+    }
+    Ok(plan)
+}
+
 fn clean_all_old_versions(is_global: bool) -> anyhow::Result<()> {
     let apps_dir = if is_global {
         get_apps_path_global()
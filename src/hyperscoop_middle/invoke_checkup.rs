@@ -1,6 +1,7 @@
 use anyhow::Context;
 use color_eyre::owo_colors::OwoColorize;
 use command_util_lib::init_env::{init_scoop_global, init_user_scoop};
+use command_util_lib::offline::is_offline_mode;
 use crossterm::style::Stylize;
 use std::process::Command;
 use std::{env, path::Path};
@@ -308,6 +309,13 @@ fn check_innounp() -> anyhow::Result<CheckupResult> {
 }
 
 async fn check_github() -> anyhow::Result<CheckupResult> {
+    if is_offline_mode() {
+        return Ok(CheckupResult {
+            passed: true,
+            message: "GitHub check skipped in offline mode".to_string(),
+            fix_hint: None,
+        });
+    }
     let client = reqwest::Client::new();
     let response = client
         .head("https://github.com")
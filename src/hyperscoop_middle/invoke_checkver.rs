@@ -0,0 +1,54 @@
+use crate::command_args::checkver::CheckverArgs;
+use command_util_lib::checkver::checkver_manifests;
+use command_util_lib::manifest::manifest::resolve_manifest_targets;
+use crossterm::style::Stylize;
+
+pub async fn execute_checkver_command(args: CheckverArgs) -> anyhow::Result<()> {
+    let mut manifest_paths = vec![];
+    for target in args.targets.iter() {
+        match resolve_manifest_targets(target, args.global) {
+            Ok(paths) => manifest_paths.extend(paths),
+            Err(e) => eprintln!("{}", format!("{target}: {e}").dark_red().bold()),
+        }
+    }
+    log::info!("checkver manifests: {:?}", manifest_paths);
+
+    let results = checkver_manifests(&manifest_paths).await;
+    for (path, result) in results {
+        let app_name = path.file_stem().unwrap_or_default().to_string_lossy();
+        match result {
+            Ok(result) if result.is_outdated() => {
+                let autoupdate = if result.has_autoupdate {
+                    "autoupdate available".dark_green().bold()
+                } else {
+                    "autoupdate not available".dark_yellow().bold()
+                };
+                println!(
+                    "{}: {} (scoop version is {}) {}",
+                    app_name.to_string().dark_cyan().bold(),
+                    result.latest_version.dark_red().bold(),
+                    result.current_version,
+                    autoupdate
+                );
+            }
+            Ok(result) => {
+                if args.outdated_only {
+                    continue;
+                }
+                println!(
+                    "{}: {}",
+                    app_name.to_string().dark_cyan().bold(),
+                    result.latest_version.dark_green().bold()
+                );
+            }
+            Err(e) => {
+                eprintln!(
+                    "{}: {}",
+                    app_name.to_string().dark_cyan().bold(),
+                    format!("{e:#}").dark_red().bold()
+                );
+            }
+        }
+    }
+    Ok(())
+}
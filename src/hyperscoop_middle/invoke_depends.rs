@@ -0,0 +1,46 @@
+use crate::command_args::depends::DependsArgs;
+use command_util_lib::depends::{
+    get_dependency_tree, get_installed_dependents, render_dependency_tree,
+};
+use crossterm::style::Stylize;
+
+pub fn execute_depends_command(args: DependsArgs) -> anyhow::Result<()> {
+    if args.reverse {
+        let dependents = get_installed_dependents(&args.app_name, args.global)?;
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&dependents)?);
+            return Ok(());
+        }
+        if dependents.is_empty() {
+            println!(
+                "{}",
+                format!("No installed app depends on '{}'", args.app_name)
+                    .dark_green()
+                    .bold()
+            );
+            return Ok(());
+        }
+        for dependent in dependents {
+            let name = match dependent.bucket {
+                Some(bucket) => format!("{bucket}/{}", dependent.name),
+                None => dependent.name,
+            };
+            println!(
+                "{} {}",
+                name.dark_cyan().bold(),
+                format!("({})", dependent.sources.join(", ")).dark_grey()
+            );
+        }
+        return Ok(());
+    }
+
+    let tree = get_dependency_tree(&args.app_name, args.global)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&tree)?);
+        return Ok(());
+    }
+    for line in render_dependency_tree(&tree) {
+        println!("{}", line.dark_cyan().bold());
+    }
+    Ok(())
+}
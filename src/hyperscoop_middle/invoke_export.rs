@@ -4,6 +4,9 @@ use command_util_lib::export::*;
 pub fn execute_export_command(file: ExportArgs) -> Result<(), anyhow::Error> {
     if let Some(file_name) = file.file_name {
         log::info!("Exporting to {}", file_name);
+        if file.lock {
+            return export_lockfile(file_name, file.config);
+        }
         if file_name.contains('\\') || file_name.contains('/') {
             if file.config {
                 log::info!("Exporting Scoop config to {}", file_name); 
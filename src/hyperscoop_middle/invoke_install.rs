@@ -1,8 +1,11 @@
 use crate::check_self_update::auto_check_hp_update;
+use crate::command_args::dry_run::print_execution_plan;
 use crate::command_args::install::InstallArgs;
 use crate::hyperscoop_middle::invoke_update::{update_buckets_parallel, update_hp};
 use anyhow::bail;
 use command_util_lib::install::*;
+use command_util_lib::offline::is_offline_mode;
+use command_util_lib::plan::plan_install_targets;
 use command_util_lib::utils::system::{get_system_default_arch, is_admin, request_admin};
 use command_util_lib::utils::utility::is_valid_url;
 use crossterm::style::Stylize;
@@ -10,11 +13,21 @@ use std::env;
 use std::path::Path;
 
 pub async fn execute_install_command(args: InstallArgs) -> Result<(), anyhow::Error> {
-    if args.app_name.is_none() {
+    if args.app_names.is_empty() {
         return Ok(());
     }
-  
-    let app_name = args.app_name.clone().unwrap();
+    if let Some(format) = args.dry_run {
+        let options = inject_user_options(&args)?;
+        let targets = args
+            .app_names
+            .iter()
+            .map(|app_name| convert_path(app_name.trim()).to_lowercase())
+            .collect::<Vec<_>>();
+        let mut plan = plan_install_targets(&targets, &options)?;
+        plan.fill_download_sizes().await;
+        return print_execution_plan(&plan, format);
+    }
+
     if args.global && !is_admin()? {
         let args = env::args().skip(1).collect::<Vec<String>>();
         let args_str = args.join(" ");
@@ -30,17 +43,23 @@ pub async fn execute_install_command(args: InstallArgs) -> Result<(), anyhow::Er
     if options.contains(&InstallOptions::CheckCurrentVersionIsLatest) {
         auto_check_hp_update(None).await?;
     }
-    if options.contains(&InstallOptions::UpdateHpAndBuckets) {
+    if options.contains(&InstallOptions::UpdateHpAndBuckets) && !is_offline_mode() {
         println!("{}", "开始更新hp和buckets".dark_cyan().bold());
         let update_option = create_update_options(&options)?;
         update_buckets_parallel()?;
         update_hp(&update_option).await?;
     }
-    if args.app_name.is_none() {
-        return Ok(());
+    if args.app_names.len() > 1 {
+        let targets = args
+            .app_names
+            .iter()
+            .map(|app_name| convert_path(app_name.trim()).to_lowercase())
+            .collect::<Vec<_>>();
+        let results = install_apps_with_plan(&targets, &options).await;
+        return print_install_summary(&results);
     }
 
-    let app_name = convert_path(app_name.trim()).to_lowercase();
+    let app_name = convert_path(args.app_names[0].trim()).to_lowercase();
     let app_path = Path::new(&app_name);
     if app_path.exists() {
         if app_path.is_file() {
@@ -110,6 +129,60 @@ pub async fn execute_install_command(args: InstallArgs) -> Result<(), anyhow::Er
     Ok(())
 }
 
+/// 批量操作中单项成功时的状态, 跳过时返回原因
+pub(crate) trait SummaryStatus {
+    fn skipped(&self) -> Option<&str>;
+}
+
+impl SummaryStatus for () {
+    fn skipped(&self) -> Option<&str> {
+        None
+    }
+}
+
+pub(crate) fn print_install_summary(
+    results: &[(String, anyhow::Result<()>)],
+) -> anyhow::Result<()> {
+    print_summary("Install", "app(s)", "install", results)
+}
+
+/// 批量操作共用的汇总输出, 有失败项时返回错误
+pub(crate) fn print_summary<T: SummaryStatus>(
+    label: &str,
+    noun: &str,
+    verb: &str,
+    results: &[(String, anyhow::Result<T>)],
+) -> anyhow::Result<()> {
+    println!("\n{}", format!("{label} summary:").dark_cyan().bold());
+    let (mut done, mut skipped, mut failed) = (0, 0, 0);
+    for (name, result) in results {
+        match result.as_ref().map(|status| status.skipped()) {
+            Ok(None) => {
+                done += 1;
+                println!("  ✅ {}", name.as_str().dark_green().bold())
+            }
+            Ok(Some(reason)) => {
+                skipped += 1;
+                println!("  ⏭  {}: {}", name.as_str().dark_grey().bold(), reason)
+            }
+            Err(e) => {
+                failed += 1;
+                println!("  ❌ {}: {:#}", name.as_str().dark_red().bold(), e);
+            }
+        }
+    }
+    if skipped > 0 {
+        println!(
+            "{}",
+            format!("{done} done, {skipped} skipped, {failed} failed").bold()
+        );
+    }
+    if failed > 0 {
+        bail!("{failed} of {} {noun} failed to {verb}", results.len())
+    }
+    Ok(())
+}
+
 fn create_update_options(option: &[InstallOptions]) -> anyhow::Result<Vec<UpdateOptions>> {
     let mut update_options = vec![];
     if option.contains(&InstallOptions::UpdateHpAndBuckets) {
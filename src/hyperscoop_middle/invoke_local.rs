@@ -0,0 +1,62 @@
+use crate::command_args::local::LocalArgs;
+use anyhow::bail;
+use command_util_lib::local::{
+    find_tools_file, is_version_installed, parse_tool_versions, pin_local_version, HP_TOOLS_FILE,
+};
+use command_util_lib::utils::system::{is_admin, request_admin};
+use crossterm::style::Stylize;
+use std::env;
+use std::path::Path;
+
+pub async fn execute_local_command(args: LocalArgs) -> anyhow::Result<()> {
+    let cwd = env::current_dir()?;
+    let Some(app) = args.app else {
+        return print_local_versions(&cwd, args.global);
+    };
+    let Some((app_name, version)) = app.split_once('@') else {
+        bail!("Invalid argument '{app}', expected app@version")
+    };
+    if args.global && !is_admin()? {
+        let args = env::args().skip(1).collect::<Vec<String>>();
+        request_admin(args.join(" ").as_str())?;
+        return Ok(());
+    }
+    pin_local_version(&cwd, app_name, version, args.global).await?;
+    println!(
+        "{} {}",
+        format!("Pinned {app_name}@{version} in")
+            .dark_green()
+            .bold(),
+        cwd.join(HP_TOOLS_FILE)
+            .display()
+            .to_string()
+            .dark_cyan()
+            .bold()
+    );
+    Ok(())
+}
+
+fn print_local_versions(cwd: &Path, global: bool) -> anyhow::Result<()> {
+    let Some(tools_file) = find_tools_file(cwd) else {
+        println!(
+            "{}",
+            format!("No {HP_TOOLS_FILE} found").dark_yellow().bold()
+        );
+        return Ok(());
+    };
+    println!("{}", tools_file.display().to_string().dark_cyan().bold());
+    let content = std::fs::read_to_string(&tools_file)?;
+    for (app_name, version) in parse_tool_versions(&content) {
+        if is_version_installed(&app_name, &version, global) {
+            println!("  {} {}", app_name.dark_green().bold(), version);
+        } else {
+            println!(
+                "  {} {} {}",
+                app_name.dark_red().bold(),
+                version,
+                "(not installed)".dark_grey()
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use crate::command_args::manifest::{FmtArgs, LintArgs, ManifestArgs, ManifestSubcommand};
+use command_util_lib::manifest::format::{format_manifest_file, LineEnding};
+use command_util_lib::manifest::lint::{lint_manifest_file, LintSeverity};
+use command_util_lib::manifest::manifest::resolve_manifest_targets;
+use crossterm::style::Stylize;
+use std::path::PathBuf;
+
+pub fn execute_manifest_command(args: ManifestArgs) -> anyhow::Result<()> {
+    match args.command {
+        ManifestSubcommand::Lint(args) => execute_lint_command(args),
+        ManifestSubcommand::Fmt(args) => execute_fmt_command(args),
+    }
+}
+
+/// 返回解析到的清单路径与无法解析的目标数量
+fn resolve_targets(targets: &[String], global: bool) -> (Vec<PathBuf>, usize) {
+    let mut manifest_paths = vec![];
+    let mut unresolved = 0;
+    for target in targets.iter() {
+        match resolve_manifest_targets(target, global) {
+            Ok(paths) => manifest_paths.extend(paths),
+            Err(e) => {
+                unresolved += 1;
+                eprintln!("{}", format!("{target}: {e}").dark_red().bold())
+            }
+        }
+    }
+    (manifest_paths, unresolved)
+}
+
+fn execute_lint_command(args: LintArgs) -> anyhow::Result<()> {
+    let (manifest_paths, unresolved) = resolve_targets(&args.targets, args.global);
+    log::info!("lint manifests: {:?}", manifest_paths);
+
+    let mut reports = manifest_paths
+        .iter()
+        .map(lint_manifest_file)
+        .collect::<Vec<_>>();
+    if args.errors_only {
+        reports.iter_mut().for_each(|report| {
+            report
+                .issues
+                .retain(|issue| issue.severity == LintSeverity::Error)
+        });
+    }
+    let error_count = reports.iter().map(|r| r.error_count()).sum::<usize>();
+    let warning_count = reports.iter().map(|r| r.warning_count()).sum::<usize>();
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        for report in reports.iter().filter(|r| !r.issues.is_empty()) {
+            println!("{}", report.path.clone().dark_cyan().bold());
+            for issue in report.issues.iter() {
+                let severity = match issue.severity {
+                    LintSeverity::Error => "error".dark_red().bold(),
+                    LintSeverity::Warning => "warning".dark_yellow().bold(),
+                };
+                let pointer = if issue.pointer.is_empty() {
+                    "/".to_string()
+                } else {
+                    issue.pointer.clone()
+                };
+                println!(
+                    "  {severity} {} [{}] {}",
+                    pointer.dark_grey(),
+                    issue.rule,
+                    issue.message
+                );
+            }
+        }
+        println!(
+            "{}",
+            format!(
+                "Checked {} manifest(s): {error_count} error(s), {warning_count} warning(s)",
+                reports.len()
+            )
+            .dark_green()
+            .bold()
+        );
+    }
+    if error_count > 0 || unresolved > 0 {
+        // 以非零退出码结束, 便于在CI中拦截不合规的清单
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn execute_fmt_command(args: FmtArgs) -> anyhow::Result<()> {
+    let (manifest_paths, unresolved) = resolve_targets(&args.targets, args.global);
+    log::info!("format manifests: {:?}", manifest_paths);
+    let line_ending = if args.lf {
+        LineEnding::Lf
+    } else {
+        LineEnding::Crlf
+    };
+
+    let mut unformatted = 0;
+    let mut failed = unresolved;
+    for path in manifest_paths.iter() {
+        match format_manifest_file(path, line_ending, args.check) {
+            Ok(false) => {}
+            Ok(true) => {
+                unformatted += 1;
+                let status = if args.check {
+                    "not formatted".dark_yellow().bold()
+                } else {
+                    "formatted".dark_green().bold()
+                };
+                println!(
+                    "{}: {status}",
+                    path.display().to_string().dark_cyan().bold()
+                );
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("{}", format!("{e:#}").dark_red().bold());
+            }
+        }
+    }
+    if failed > 0 || (args.check && unformatted > 0) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
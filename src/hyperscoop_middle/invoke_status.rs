@@ -8,6 +8,7 @@ use command_util_lib::init_env::{
     get_buckets_root_dir_path_global,
 };
 use command_util_lib::list::VersionJSON;
+use command_util_lib::utils::version::Version;
 use rayon::prelude::*;
 use std::collections::HashMap;
 
@@ -76,7 +77,7 @@ pub fn execute_status_command(status_args: StatusArgs) -> Result<(), anyhow::Err
         .iter()
         .zip(current_versions.iter().zip(latest_versions.iter()))
     {
-        if latest_version > current_version {
+        if Version::new(latest_version) > Version::new(current_version) {
             final_installed_apps.push(vec![
                 app_name.to_string(),
                 current_version.to_string(),
@@ -159,7 +160,7 @@ fn build_version_map(
             version_map
                 .entry(file_name)
                 .and_modify(|v| {
-                    if &version > v {
+                    if Version::new(&version) > Version::new(v) {
                         *v = version.clone();
                     }
                 })
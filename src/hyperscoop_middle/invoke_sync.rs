@@ -0,0 +1,77 @@
+use crate::command_args::dry_run::DryRunFormat;
+use crate::command_args::sync::SyncArgs;
+use crate::hyperscoop_middle::invoke_install::print_summary;
+use anyhow::anyhow;
+use command_util_lib::sync::{
+    apply_sync, bucket_latest_version, installed_dependent_names, plan_sync, read_local_state,
+    HpFile, SyncAction,
+};
+use crossterm::style::Stylize;
+use std::path::PathBuf;
+
+pub async fn execute_sync_command(args: SyncArgs) -> anyhow::Result<()> {
+    let path = match args.file {
+        Some(file) => PathBuf::from(file),
+        None => HpFile::find_in(&std::env::current_dir()?)
+            .ok_or_else(|| anyhow!("No hpfile found in current directory"))?,
+    };
+    let hpfile = HpFile::load(&path)?;
+    let state = read_local_state()?;
+    let actions = plan_sync(
+        &hpfile,
+        &state,
+        args.prune,
+        bucket_latest_version,
+        installed_dependent_names,
+    );
+
+    if let Some(format) = args.dry_run {
+        print_sync_plan(&actions, format)?;
+        if !actions.is_empty() {
+            if format == DryRunFormat::Human {
+                eprintln!(
+                    "{}",
+                    format!("Drift detected, {} change(s) needed", actions.len())
+                        .dark_red()
+                        .bold()
+                );
+            }
+            // 以非零退出码结束, 便于在CI中检测环境漂移
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if actions.is_empty() {
+        println!("{}", "Already in sync".dark_green().bold());
+        return Ok(());
+    }
+    let results = apply_sync(&hpfile, &actions).await;
+    print_summary("Sync", "change(s)", "apply", &results)
+}
+
+fn print_sync_plan(actions: &[SyncAction], format: DryRunFormat) -> anyhow::Result<()> {
+    if format == DryRunFormat::Json {
+        println!("{}", serde_json::to_string_pretty(actions)?);
+        return Ok(());
+    }
+    if actions.is_empty() {
+        println!("{}", "Already in sync".dark_green().bold());
+        return Ok(());
+    }
+    println!(
+        "{}",
+        "Dry run, no changes will be made:".dark_yellow().bold()
+    );
+    for action in actions {
+        let line = action.to_string();
+        let line = match action {
+            SyncAction::Unresolved { .. } => line.dark_red().bold(),
+            SyncAction::RemoveBucket { .. } | SyncAction::Uninstall { .. } => {
+                line.dark_magenta().bold()
+            }
+            _ => line.dark_green().bold(),
+        };
+        println!("  {line}");
+    }
+    Ok(())
+}
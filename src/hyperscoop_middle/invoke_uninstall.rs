@@ -1,6 +1,9 @@
+use crate::command_args::dry_run::print_execution_plan;
 use crate::command_args::uninstall::UninstallArgs;
 use anyhow::{bail, Context};
+use command_util_lib::depends::{check_uninstall_dependents, get_cascade_uninstall_order};
 use command_util_lib::init_env::{get_app_dir, get_app_dir_global};
+use command_util_lib::plan::{plan_uninstall_app, AppPlan, ExecutionPlan, PlanAction};
 use command_util_lib::uninstall::*;
 use command_util_lib::utils::system::{is_admin, kill_processes_using_app, request_admin};
 use crossterm::style::Stylize;
@@ -9,6 +12,10 @@ use std::path::Path;
 
 pub fn execute_uninstall_command(args: UninstallArgs) -> Result<(), anyhow::Error> {
     if let Some(app_name) = args.app_name {
+        if let Some(format) = args.dry_run {
+            let plan = plan_uninstall(&app_name, &args)?;
+            return print_execution_plan(&plan, format);
+        }
         if args.global && !is_admin()? {
             let args = env::args().skip(1).collect::<Vec<String>>();
             let args_str = args.join(" ");
@@ -20,68 +27,118 @@ pub fn execute_uninstall_command(args: UninstallArgs) -> Result<(), anyhow::Erro
             return Ok(());
         }
 
-        if args.purge {
-            log::info!("purging app {}", &app_name);
-            let result = uninstall_app_with_purge(&app_name, args.global);
-            match result {
-                Ok(_) => {
-                    println!(
-                        "'{}' {}",
-                        app_name.dark_cyan().bold(),
-                        "was purge uninstalled successfully!".dark_green().bold()
-                    );
-                }
-                Err(e) => {
-                 
-                    bail!("Failed to purge app, {}", e)
-                }
-            }
+        let dependents =
+            check_uninstall_dependents(&app_name, args.global, args.force, args.cascade)?;
+        if !dependents.is_empty() && args.force {
+            eprintln!(
+                "{}",
+                format!(
+                    "'{app_name}' is still required by {} installed app(s), they may stop working",
+                    dependents.len()
+                )
+                .dark_yellow()
+                .bold()
+            );
+        }
+        let app_names = if args.cascade {
+            get_cascade_uninstall_order(&app_name, args.global)?
         } else {
-            log::info!("Uninstalling app {}", &app_name);
+            vec![app_name]
+        };
+        for app_name in app_names.iter() {
+            uninstall_single_app(app_name, args.purge, args.global)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// 与实际卸载相同的依赖检查与级联顺序, 只生成计划
+fn plan_uninstall(app_name: &str, args: &UninstallArgs) -> anyhow::Result<ExecutionPlan> {
+    if let Err(e) = check_uninstall_dependents(app_name, args.global, args.force, args.cascade) {
+        return Ok(ExecutionPlan {
+            apps: vec![
+                AppPlan::new(app_name, PlanAction::Skip, args.global).with_reason(format!("{e:#}"))
+            ],
+        });
+    }
+    let app_names = if args.cascade {
+        get_cascade_uninstall_order(app_name, args.global)?
+    } else {
+        vec![app_name.to_string()]
+    };
+    let apps = app_names
+        .iter()
+        .map(|name| {
+            plan_uninstall_app(name, args.global, args.purge).unwrap_or_else(|e| {
+                AppPlan::new(name, PlanAction::Unresolved, args.global)
+                    .with_reason(format!("{e:#}"))
+            })
+        })
+        .collect();
+    Ok(ExecutionPlan { apps })
+}
+
+fn uninstall_single_app(app_name: &str, purge: bool, global: bool) -> Result<(), anyhow::Error> {
+    if purge {
+        log::info!("purging app {}", &app_name);
+        let result = uninstall_app_with_purge(app_name, global);
+        match result {
+            Ok(_) => {
+                println!(
+                    "'{}' {}",
+                    app_name.dark_cyan().bold(),
+                    "was purge uninstalled successfully!".dark_green().bold()
+                );
+            }
+            Err(e) => {
+                bail!("Failed to purge app, {}", e)
+            }
+        }
+    } else {
+        log::info!("Uninstalling app {}", &app_name);
+
+        let result = uninstall_app(app_name, global);
+        match result {
+            Ok(_) => {
+                println!(
+                    "'{}' {}",
+                    app_name.dark_cyan().bold(),
+                    "was already uninstalled successfully!".dark_green().bold()
+                );
+            }
+            Err(_) => {
+                let app_dir = if global {
+                    get_app_dir_global(app_name)
+                } else {
+                    get_app_dir(app_name)
+                };
+                let app_dir = Path::new(&app_dir);
+                if app_dir.exists() {
+                    if std::fs::remove_dir_all(app_dir)
+                        .context(format!(
+                            "Failed to remove app directory {}",
+                            app_dir.display()
+                        ))
+                        .is_err()
+                    {
+                        kill_processes_using_app(app_name);
+                        std::fs::remove_dir_all(app_dir).context(format!(
+                            "Failed to remove app dir  {} at line 68",
+                            app_dir.display()
+                        ))?;
+                    }
 
-            let result = uninstall_app(&app_name, args.global);
-            match result {
-                Ok(_) => {
                     println!(
                         "'{}' {}",
                         app_name.dark_cyan().bold(),
-                        "was already uninstalled successfully!".dark_green().bold()
+                        "has been uninstalled successfully!".dark_green().bold()
                     );
-                }
-                Err(_ ) => {
-                    let app_dir = if args.global {
-                        get_app_dir_global(&app_name)
-                    } else {
-                        get_app_dir(&app_name)
-                    };
-                    let app_dir = Path::new(&app_dir);
-                    if app_dir.exists() {
-                        if std::fs::remove_dir_all(app_dir)
-                            .context(format!(
-                                "Failed to remove app directory {}",
-                                app_dir.display()
-                            ))
-                            .is_err()
-                        {
-                            kill_processes_using_app(&app_name);
-                            std::fs::remove_dir_all(app_dir).context(format!(
-                                "Failed to remove app dir  {} at line 68",
-                                app_dir.display()
-                            ))?;
-                        }
-
-                        println!(
-                            "'{}' {}",
-                            app_name.clone().dark_cyan().bold(),
-                            "has been uninstalled successfully!".dark_green().bold()
-                        );
-                    } else {
-                        bail!("'{app_name}' 并没有安装")
-                    }
+                } else {
+                    bail!("'{app_name}' 并没有安装")
                 }
             }
         }
     }
-
     Ok(())
 }
@@ -1,4 +1,5 @@
 ﻿use crate::check_self_update::{auto_check_hp_update, get_app_old_version};
+use crate::command_args::dry_run::print_execution_plan;
 use crate::command_args::update::UpdateArgs;
 use anyhow::Context;
 use command_util_lib::init_env::{
@@ -7,6 +8,8 @@ use command_util_lib::init_env::{
 };
 use command_util_lib::install::UpdateOptions::ForceUpdateOverride;
 use command_util_lib::install::{install_and_replace_hp, InstallOptions, UpdateOptions};
+use command_util_lib::offline::{ensure_online, is_offline_mode};
+use command_util_lib::plan::{plan_update_all, plan_update_app, ExecutionPlan};
 use command_util_lib::update::*;
 use command_util_lib::utils::system::{is_admin, request_admin};
 use command_util_lib::utils::utility::update_scoop_config_last_update_time;
@@ -20,6 +23,19 @@ use std::process::Command;
 
 pub async fn execute_update_command(update_args: UpdateArgs) -> Result<(), anyhow::Error> {
     let options = inject_update_user_options(&update_args)?;
+    if let Some(format) = update_args.dry_run {
+        let mut plan = if update_args.all {
+            plan_update_all(&options)
+        } else if let Some(app_name) = update_args.app_name.as_ref() {
+            ExecutionPlan {
+                apps: vec![plan_update_app(app_name, &options)?],
+            }
+        } else {
+            ExecutionPlan::default()
+        };
+        plan.fill_download_sizes().await;
+        return print_execution_plan(&plan, format);
+    }
 
     if update_args.update_self_and_buckets {
         println!("{}", "开始更新hp和buckets".dark_cyan().bold());
@@ -29,7 +45,9 @@ pub async fn execute_update_command(update_args: UpdateArgs) -> Result<(), anyho
         } else {
             update_buckets_parallel()?
         };
-        update_hp(&options).await?;
+        if !is_offline_mode() {
+            update_hp(&options).await?;
+        }
         return Ok(());
     }
     if update_args.all {
@@ -111,6 +129,7 @@ pub(crate) fn update_buckets_serial() -> Result<(), anyhow::Error> {
 }
 
 pub async fn update_hp(options: &[UpdateOptions]) -> Result<(), anyhow::Error> {
+    ensure_online("Updating hp")?;
     let update_options = options;
     let install_options = transform_update_options_to_install(options);
     let global = if install_options.contains(&InstallOptions::Global) {
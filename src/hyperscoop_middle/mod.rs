@@ -1,11 +1,13 @@
 ﻿mod init_env;
 mod invoke_bucket;
+mod invoke_bundle;
 mod invoke_cat;
 mod invoke_list;
 mod invoke_merge;
 mod invoke_search;
 mod invoke_update;
 pub use invoke_bucket::execute_bucket_command;
+pub use invoke_bundle::execute_bundle_command;
 pub use invoke_list::execute_list_installed_apps;
 pub use invoke_merge::execute_merge_command;
 
@@ -15,6 +17,14 @@ pub use invoke_update::execute_update_command;
 mod invoke_install ;
 pub use invoke_install::execute_install_command ;
 pub use invoke_cat::execute_cat_command;
+mod invoke_checkver ;
+pub use invoke_checkver::execute_checkver_command ;
+mod invoke_autoremove ;
+pub use invoke_autoremove::execute_autoremove_command ;
+mod invoke_depends ;
+pub use invoke_depends::execute_depends_command ;
+mod invoke_manifest ;
+pub use invoke_manifest::execute_manifest_command ;
 
 mod invoke_home;
 pub use invoke_home::execute_home_command;
@@ -22,6 +32,9 @@ pub use invoke_home::execute_home_command;
 mod invoke_info;
 pub use invoke_info::execute_info_command;
 
+mod invoke_local;
+pub use invoke_local::execute_local_command;
+
 
 mod invoke_prefix ;
 pub use invoke_prefix::execute_prefix_command ;
@@ -54,6 +67,8 @@ pub use invoke_reset::execute_reset_command ;
 
 mod invoke_status ;
 pub use invoke_status::execute_status_command ;
+mod invoke_sync;
+pub use invoke_sync::execute_sync_command;
 mod  invoke_uninstall ;
 
 pub use  invoke_uninstall::execute_uninstall_command ;
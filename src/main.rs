@@ -18,9 +18,11 @@ mod check_self_update;
 mod crypto;
 use crate::command::{execute_credits_command, execute_hold_command, Commands};
 use crate::command_args::alias::execute_alias_command;
+use crate::command_args::dry_run::DryRunFormat;
 #[allow(unused_imports)]
 use crate::logger_err::{init_color_output, invoke_admin_process};
 use check_self_update::*;
+use command_util_lib::offline::set_offline_mode;
 use crossterm::style::{Print, Stylize};
 
 const WONDERFUL_STYLES: Styles = Styles::styled()
@@ -86,18 +88,44 @@ struct Cli {
         help_heading = "Global Options"
     )]
     pub no_color: bool,
+
+    #[arg(
+        long,
+        required = false,
+        global = true,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "human",
+        value_name = "FORMAT",
+        help = "只输出执行计划, 不做任何修改, 支持 --dry-run=json, 其余会修改本机状态的命令将拒绝执行",
+        help_heading = "Global Options"
+    )]
+    pub dry_run: Option<DryRunFormat>,
+
+    #[arg(
+        long,
+        required = false,
+        global = true,
+        help = "离线模式, 只使用缓存和本地bucket, 不访问网络",
+        help_heading = "Global Options"
+    )]
+    pub offline: bool,
 }
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> anyhow::Result<()> {
-    println!(
-        "{ } \n ",
-        "🦀 次世代更快更强更精美的Windows包管理器!"
-            .dark_magenta()
-            .bold()
-    );
     let cli = Cli::parse();
+    // json 计划需要保持 stdout 可被直接解析
+    if cli.dry_run != Some(DryRunFormat::Json) {
+        println!(
+            "{ } \n ",
+            "🦀 次世代更快更强更精美的Windows包管理器!"
+                .dark_magenta()
+                .bold()
+        );
+    }
     init_color_output(cli.no_color);
+    set_offline_mode(cli.offline);
     unsafe { init_logger(&cli); }
     color_eyre::install().unwrap();
     // if cli.command.is_some() && cli.global {
@@ -105,6 +133,18 @@ async fn main() -> anyhow::Result<()> {
     //     return Ok(());
     // }
 
+    if let Some(command) = cli.command.as_ref().filter(|_| cli.dry_run.is_some()) {
+        if let Some(name) = command.dry_run_unsupported() {
+            eprintln!(
+                "{}",
+                format!("`hp {name}` does not support --dry-run, no changes were made")
+                    .dark_red()
+                    .bold()
+            );
+            std::process::exit(1);
+        }
+    }
+
     let result = match cli.command {
         None => {
             auto_check_hp_update(None).await?;
@@ -113,23 +153,30 @@ async fn main() -> anyhow::Result<()> {
         }
         Some(input_command) => match input_command {
             Commands::Alias(alias_args) => execute_alias_command(alias_args),
+            Commands::Autoremove(args) => execute_autoremove_command(args),
             Commands::Bucket(bucket) => execute_bucket_command(bucket),
+            Commands::Bundle(args) => execute_bundle_command(args),
             Commands::Cat(cat) => execute_cat_command(cat),
             Commands::Cache(cache_args) => execute_cache_command(cache_args),
             Commands::Checkup(args) => execute_checkup_command(args.global).await,
+            Commands::Checkver(args) => execute_checkver_command(args).await,
             Commands::Cleanup(args) => execute_cleanup_command(args),
             Commands::Config(args) => execute_config_command(args),
+            Commands::Depends(args) => execute_depends_command(args),
             Commands::Export(file) => execute_export_command(file),
             Commands::Home(home) => execute_home_command(home),
             Commands::Import(args) => execute_import_command(args),
             Commands::Info(info) => execute_info_command(info),
             Commands::Install(args) => execute_install_command(args).await,
             Commands::List(query_app) => execute_list_installed_apps(query_app),
+            Commands::Local(args) => execute_local_command(args).await,
+            Commands::Manifest(args) => execute_manifest_command(args),
             Commands::Prefix(prefix) => execute_prefix_command(prefix),
             Commands::Reset(args) => execute_reset_command(args),
             Commands::Search(search_app) => execute_search_command(search_app),
             Commands::Shim(args) => execute_shim_command(args),
             Commands::Status(args) => execute_status_command(args),
+            Commands::Sync(args) => execute_sync_command(args).await,
             Commands::Uninstall(args) => execute_uninstall_command(args),
             Commands::Update(update_args) => execute_update_command(update_args).await,
             Commands::Which(which) => execute_which_command(which),